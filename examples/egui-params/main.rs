@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::time::Duration;
+
+use wgsim::app::App;
+use wgsim::ctx::DrawingContext;
+use wgsim::egui;
+use wgsim::fullscreen::{FullscreenPass, FullscreenPassBuilder};
+use wgsim::primitive::Color;
+use wgsim::render::Render;
+use wgsim::util::{BindGroupBuilder, BindGroupLayoutBuilder, UniformBuffer};
+
+// cargo run --example egui-params --features egui
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("egui-params", initial).with_egui();
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  Initial {
+    params: Params {
+      color: Color::from_srgb8(90, 170, 255, 255),
+      frequency: 60.0,
+      speed: 4.0,
+      sources: 3,
+      time: 0.0,
+    },
+  }
+}
+
+struct Initial {
+  params: Params,
+}
+
+#[repr(C)]
+#[derive(
+  Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType,
+)]
+struct Params {
+  color: Color,
+  frequency: f32,
+  speed: f32,
+  sources: u32,
+  time: f32,
+}
+
+struct State {
+  params: UniformBuffer<Params>,
+  params_bind_group: wgpu::BindGroup,
+  pass: FullscreenPass,
+  paused: bool,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let params = UniformBuffer::new(&ctx.device, initial.params);
+
+    let layout = BindGroupLayoutBuilder::new(&ctx.device)
+      .label("params bind group layout")
+      .entry(
+        0,
+        wgpu::ShaderStages::FRAGMENT,
+        UniformBuffer::<Params>::binding_type(),
+      )
+      .build();
+    let params_bind_group = BindGroupBuilder::new(&ctx.device, &layout)
+      .label("params bind group")
+      .entry(0, params.as_binding_resource())
+      .build();
+
+    let pass = FullscreenPassBuilder::new(ctx, include_str!("./waves.wgsl"))
+      .bind_group_layout(&layout)
+      .build();
+
+    Self {
+      params,
+      params_bind_group,
+      pass,
+      paused: false,
+    }
+  }
+
+  // スライダーを動かした値はそのまま uniform バッファに入る
+  fn ui(&mut self, ctx: &egui::Context) {
+    egui::Window::new("Parameters").show(ctx, |ui| {
+      self.params.update(|params| {
+        ui.add(
+          egui::Slider::new(&mut params.frequency, 5.0..=200.0)
+            .text("frequency"),
+        );
+        ui.add(egui::Slider::new(&mut params.speed, 0.0..=20.0).text("speed"));
+        ui.add(egui::Slider::new(&mut params.sources, 1..=12).text("sources"));
+
+        let mut rgb = [params.color.r, params.color.g, params.color.b];
+        ui.horizontal(|ui| {
+          ui.color_edit_button_rgb(&mut rgb);
+          ui.label("color");
+        });
+        params.color = Color::linear(rgb[0], rgb[1], rgb[2], 1.0);
+      });
+      ui.checkbox(&mut self.paused, "pause");
+    });
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: Duration) {
+    if !self.paused {
+      self.params.update(|params| params.time = dt.as_secs_f32());
+    }
+    self.params.flush(&ctx.queue);
+    self.pass.update(ctx);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self.pass.draw(encoder, render_target_view, &[&self.params_bind_group]);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,28 @@
+struct Params {
+  color: vec4f,
+  frequency: f32,
+  speed: f32,
+  sources: u32,
+  time: f32,
+}
+
+@group(1) @binding(0) var<uniform> params: Params;
+
+// 円周上に並んだ波源からの波を重ね合わせる
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let aspect = fullscreen.resolution.x / fullscreen.resolution.y;
+  let p = (in.uv - 0.5) * vec2f(aspect, 1.0);
+
+  var height = 0.0;
+  for (var i = 0u; i < params.sources; i++) {
+    let angle = f32(i) / f32(params.sources) * 6.2831853;
+    let source = vec2f(cos(angle), sin(angle)) * 0.25;
+    let d = length(p - source);
+    height += sin(d * params.frequency - params.time * params.speed);
+  }
+  height /= f32(max(params.sources, 1u));
+
+  let intensity = height * 0.5 + 0.5;
+  return vec4f(params.color.rgb * intensity, 1.0);
+}
@@ -0,0 +1,42 @@
+override WIDTH: u32;
+override HEIGHT: u32;
+
+@group(0) @binding(0) var<storage, read> src: array<u32>;
+@group(0) @binding(1) var<storage, read_write> dst: array<u32>;
+
+fn cell(x: i32, y: i32) -> u32 {
+  // 端はトーラス状につなげる
+  let w = i32(WIDTH);
+  let h = i32(HEIGHT);
+  let xx = u32((x + w) % w);
+  let yy = u32((y + h) % h);
+  return src[yy * WIDTH + xx];
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3u) {
+  if (id.x >= WIDTH || id.y >= HEIGHT) {
+    return;
+  }
+
+  let x = i32(id.x);
+  let y = i32(id.y);
+
+  var neighbors = 0u;
+  for (var dy = -1; dy <= 1; dy++) {
+    for (var dx = -1; dx <= 1; dx++) {
+      if (dx != 0 || dy != 0) {
+        neighbors += cell(x + dx, y + dy);
+      }
+    }
+  }
+
+  let alive = cell(x, y);
+  let index = id.y * WIDTH + id.x;
+
+  if (neighbors == 3u || (alive == 1u && neighbors == 2u)) {
+    dst[index] = 1u;
+  } else {
+    dst[index] = 0u;
+  }
+}
@@ -0,0 +1,246 @@
+use std::error::Error;
+use std::time::Duration;
+
+use wgsim::app::App;
+use wgsim::camera::{Camera, Camera2D, CameraBinding, CAMERA_WGSL};
+use wgsim::ctx::DrawingContext;
+use wgsim::kernel::{ComputeKernel, ComputeKernelBuilder, DispatchOptions};
+use wgsim::ping_pong::PingPong;
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::primitive::Rect;
+use wgsim::profiler::GpuProfiler;
+use wgsim::render::Render;
+use wgsim::util;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+const GRID_WIDTH: u32 = 128;
+const GRID_HEIGHT: u32 = 128;
+
+const READ_STORAGE_BINDING_TYPE: wgpu::BindingType =
+  wgpu::BindingType::Buffer {
+    ty: wgpu::BufferBindingType::Storage { read_only: true },
+    has_dynamic_offset: false,
+    min_binding_size: None,
+  };
+
+const WRITE_STORAGE_BINDING_TYPE: wgpu::BindingType =
+  wgpu::BindingType::Buffer {
+    ty: wgpu::BufferBindingType::Storage { read_only: false },
+    has_dynamic_offset: false,
+    min_binding_size: None,
+  };
+
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("game-of-life", initial)
+    .with_update_interval(Duration::from_millis(50))
+    .with_gpu_profiling()
+    .with_default_hotkeys();
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  // 乱数生成器を持ち込まないため、簡単な線形合同法で初期状態を作る
+  let mut seed = 0x2545_f491_u32;
+  let cells = (0..GRID_WIDTH * GRID_HEIGHT)
+    .map(|_| {
+      seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+      u32::from(seed >> 28 < 4)
+    })
+    .collect();
+
+  Initial { cells }
+}
+
+struct Initial {
+  cells: Vec<u32>,
+}
+
+struct State {
+  cells: PingPong<wgpu::Buffer>,
+  step_kernel: ComputeKernel,
+  render_pipeline: wgpu::RenderPipeline,
+  profiler: GpuProfiler,
+  camera: Camera2D,
+  camera_binding: CameraBinding,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let grid_constants =
+      [("WIDTH", GRID_WIDTH as f64), ("HEIGHT", GRID_HEIGHT as f64)];
+
+    let compute_source = include_str!("./compute.wgsl");
+    let render_shader =
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("render shader"),
+        source: wgpu::ShaderSource::Wgsl(
+          format!("{}\n{}", CAMERA_WGSL, include_str!("./render.wgsl")).into(),
+        ),
+      });
+
+    let compute_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[READ_STORAGE_BINDING_TYPE, WRITE_STORAGE_BINDING_TYPE],
+      &[wgpu::ShaderStages::COMPUTE, wgpu::ShaderStages::COMPUTE],
+    );
+    let render_bind_group_layout = util::create_bind_group_layout(
+      &ctx.device,
+      &[READ_STORAGE_BINDING_TYPE],
+      &[wgpu::ShaderStages::FRAGMENT],
+    );
+
+    let cells = PingPong::buffers(
+      &ctx.device,
+      &compute_bind_group_layout,
+      bytemuck::cast_slice(&initial.cells),
+      wgpu::BufferUsages::empty(),
+    )
+    .with_read_layout(&ctx.device, &render_bind_group_layout);
+
+    let compute_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("compute pipeline layout"),
+        bind_group_layouts: &[&compute_bind_group_layout],
+        push_constant_ranges: &[],
+      });
+
+    let step_kernel =
+      ComputeKernelBuilder::new(&ctx.device, compute_source, "cs_main")
+        .pipeline_layout(&compute_pipeline_layout)
+        .constants(&grid_constants)
+        .build()
+        .expect("Failed to build the step kernel");
+
+    // ドラッグとホイールで盤面を動かせるよう、最初は盤面全体を収める
+    let camera = Camera2D::fit(
+      Rect::new(0.0, 0.0, GRID_WIDTH as f32, GRID_HEIGHT as f32),
+      *ctx.size(),
+    );
+    let camera_binding = CameraBinding::new(&ctx.device, &camera);
+
+    let render_pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("render pipeline layout"),
+        bind_group_layouts: &[
+          &render_bind_group_layout,
+          camera_binding.layout(),
+        ],
+        push_constant_ranges: &[],
+      });
+
+    let render_pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&render_shader, "vs_main")
+      .fs_shader(&render_shader, "fs_main")
+      .pipeline_layout(&render_pipeline_layout)
+      .constants(&grid_constants)
+      .build();
+
+    let mut profiler = GpuProfiler::new(ctx);
+    profiler.start_trace();
+
+    Self {
+      cells,
+      step_kernel,
+      render_pipeline,
+      profiler,
+      camera,
+      camera_binding,
+    }
+  }
+
+  // P キーで計測結果を表示し、Chrome のトレース形式で書き出す
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let WindowEvent::KeyboardInput {
+      event:
+        KeyEvent {
+          physical_key: PhysicalKey::Code(KeyCode::KeyP),
+          state: ElementState::Pressed,
+          ..
+        },
+      ..
+    } = event
+    else {
+      return self.camera.process_event(event);
+    };
+
+    if !self.profiler.is_enabled() {
+      eprintln!("Timestamp queries are not supported on this adapter");
+      return true;
+    }
+
+    for timing in self.profiler.timings() {
+      println!(
+        "{:>8}: {:.3} ms (min {:.3}, max {:.3})",
+        timing.name, timing.average_ms, timing.min_ms, timing.max_ms
+      );
+    }
+    match self.profiler.write_chrome_trace("game-of-life-trace.json") {
+      Ok(()) => println!("Trace has been saved to game-of-life-trace.json"),
+      Err(e) => eprintln!("{:?}", e),
+    }
+
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: Duration) {
+    self.profiler.update(ctx);
+    self.camera.update(ctx);
+    self.camera_binding.update(&ctx.queue, &self.camera);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self
+      .cells
+      .step(
+        encoder,
+        &self.step_kernel,
+        0,
+        [GRID_WIDTH, GRID_HEIGHT, 1],
+        DispatchOptions::new()
+          .timestamp_writes(self.profiler.compute_pass_timestamps("step")),
+      )
+      .expect("Grid is larger than the workgroup limit");
+
+    let color_attachment = wgpu::RenderPassColorAttachment {
+      view: render_target_view,
+      resolve_target: None,
+      ops: wgpu::Operations {
+        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        store: wgpu::StoreOp::Store,
+      },
+    };
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("render pass"),
+        color_attachments: &[Some(color_attachment)],
+        timestamp_writes: self.profiler.render_pass_timestamps("render"),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.render_pipeline);
+    render_pass.set_bind_group(0, self.cells.read_bind_group(), &[]);
+    render_pass.set_bind_group(1, self.camera_binding.bind_group(), &[]);
+    render_pass.draw(0..6, 0..1);
+
+    drop(render_pass);
+
+    self.profiler.end_frame(encoder);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,40 @@
+override WIDTH: u32;
+override HEIGHT: u32;
+
+@group(0) @binding(0) var<storage, read> cells: array<u32>;
+
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+// 盤面はワールド座標の (0, 0) から (WIDTH, HEIGHT) に置く
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  var uv = array<vec2f, 6>(
+    vec2f(1.0, 0.0),
+    vec2f(1.0, 1.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(0.0, 0.0),
+  );
+
+  // uv の y は下向き、ワールド座標の y は上向き
+  let world = vec2f(uv[i].x, 1.0 - uv[i].y) * vec2f(f32(WIDTH), f32(HEIGHT));
+
+  var output: VertexOutput;
+  output.position = camera.view_proj * vec4f(world, 0.0, 1.0);
+  output.uv = uv[i];
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  let x = min(u32(in.uv.x * f32(WIDTH)), WIDTH - 1u);
+  let y = min(u32(in.uv.y * f32(HEIGHT)), HEIGHT - 1u);
+  let alive = f32(cells[y * WIDTH + x]);
+  return vec4f(vec3f(alive), 1.0);
+}
@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::time::Duration;
+
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use wgsim::app::App;
+use wgsim::ctx::DrawingContext;
+use wgsim::effect::{EffectChain, EffectChainBuilder};
+use wgsim::fullscreen::{FullscreenPass, FullscreenPassBuilder};
+use wgsim::render::Render;
+use wgsim::surface_cfg::SurfaceConfigBuilder;
+use wgsim::tonemap::{Tonemap, TonemapOperator, TonemapUniforms};
+use wgsim::util::UniformBuffer;
+
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  // Rgba16Float のサーフェスがなければ SDR で表示してトーンマップする
+  let surface_cfg = SurfaceConfigBuilder::new().prefer_hdr();
+
+  let mut app: App<State> =
+    App::new("hdr", ()).with_surface_cfg_builder(&surface_cfg);
+  app.run()?;
+
+  Ok(())
+}
+
+struct State {
+  scene_pass: FullscreenPass,
+  effect_chain: EffectChain,
+  tonemap: Tonemap,
+  tonemap_uniform: UniformBuffer<TonemapUniforms>,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = ();
+
+  async fn new(ctx: &DrawingContext<'a>, _initial: &Self::Initial) -> Self {
+    let tonemap = Tonemap::new(ctx.format());
+    println!("Output: {:?} ({:?})", ctx.format(), ctx.color_space());
+
+    let tonemap_uniform = tonemap.uniform_buffer(&ctx.device);
+    let effect_chain = EffectChainBuilder::new(ctx)
+      .hdr()
+      .effect(Tonemap::effect(&tonemap_uniform))
+      .build();
+
+    let scene_pass =
+      FullscreenPassBuilder::new(ctx, include_str!("./scene.wgsl"))
+        .format(effect_chain.format())
+        .build();
+
+    Self {
+      scene_pass,
+      effect_chain,
+      tonemap,
+      tonemap_uniform,
+    }
+  }
+
+  // T キーでトーンマップの方式を切り替える
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let WindowEvent::KeyboardInput {
+      event:
+        KeyEvent {
+          physical_key: PhysicalKey::Code(KeyCode::KeyT),
+          state: ElementState::Pressed,
+          ..
+        },
+      ..
+    } = event
+    else {
+      return false;
+    };
+
+    self.tonemap.operator = match self.tonemap.operator {
+      TonemapOperator::Clamp => TonemapOperator::Reinhard,
+      TonemapOperator::Reinhard => TonemapOperator::Aces,
+      TonemapOperator::Aces => TonemapOperator::Clamp,
+    };
+    println!("Tonemap: {:?}", self.tonemap.operator);
+    true
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: Duration) {
+    // 露出を -2EV から +2EV まで揺らす
+    self.tonemap.exposure = (2.0 * (dt.as_secs_f32() * 0.5).sin()).exp2();
+    self.tonemap_uniform.set(self.tonemap.uniforms());
+    self.tonemap_uniform.flush(&ctx.queue);
+
+    self.scene_pass.update(ctx);
+    self.effect_chain.update(ctx);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self.scene_pass.draw(encoder, self.effect_chain.input_view(), &[]);
+
+    self.effect_chain.run(encoder, render_target_view);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,19 @@
+// 1.0 を超える放射輝度を含むシーン
+fn emitter(uv: vec2f, center: vec2f, radius: f32, radiance: vec3f) -> vec3f {
+  let d = distance(uv, center);
+  let core = 1.0 - smoothstep(radius * 0.8, radius, d);
+  let glow = radius * radius / (d * d + 0.0001) * 0.05;
+  return radiance * (core + glow);
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let uv = in.uv;
+  var color = mix(vec3f(0.02, 0.03, 0.08), vec3f(0.2, 0.25, 0.4), 1.0 - uv.y);
+
+  color += emitter(uv, vec2f(0.25, 0.35), 0.08, vec3f(16.0, 6.0, 1.5));
+  color += emitter(uv, vec2f(0.7, 0.3), 0.05, vec3f(2.0, 8.0, 24.0));
+  color += emitter(uv, vec2f(0.5, 0.75), 0.12, vec3f(0.9, 0.9, 0.8));
+
+  return vec4f(color, 1.0);
+}
@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use winit::event::WindowEvent;
+
+use wgsim::app::App;
+use wgsim::camera::{Camera, CameraBinding, OrbitCamera, CAMERA_WGSL};
+use wgsim::ctx::DrawingContext;
+use wgsim::mesh::{MeshData, Model};
+use wgsim::ppl::RenderPipelineBuilder;
+use wgsim::primitive::{Mat4, Size, Vec3};
+use wgsim::render::Render;
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+
+// cargo run --example mesh-viewer -- model.gltf
+// 引数がなければ立方体・球・格子を表示する
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("mesh-viewer", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  Initial {
+    path: std::env::args().nth(1).map(PathBuf::from),
+  }
+}
+
+struct Initial {
+  path: Option<PathBuf>,
+}
+
+struct State {
+  model: Model,
+  camera: OrbitCamera,
+  camera_binding: CameraBinding,
+  pipeline: wgpu::RenderPipeline,
+  depth_view: wgpu::TextureView,
+}
+
+fn procedural_scene() -> Vec<MeshData> {
+  let mut cube = MeshData::cube(1.0);
+  cube.transform(
+    &(Mat4::translation(Vec3::new(-1.0, 0.5, 0.0)) * Mat4::rotation_y(0.5)),
+  );
+  let mut sphere = MeshData::sphere(0.6, 32, 16);
+  sphere.transform(&Mat4::translation(Vec3::new(1.0, 0.6, 0.0)));
+  let grid = MeshData::grid(4.0, 4.0, 8, 8);
+
+  vec![cube, sphere, grid]
+}
+
+fn create_depth_view(ctx: &DrawingContext) -> wgpu::TextureView {
+  let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("depth texture"),
+    size: (*ctx.size()).into(),
+    mip_level_count: 1,
+    sample_count: ctx.sample_count,
+    dimension: wgpu::TextureDimension::D2,
+    format: DEPTH_FORMAT,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+    view_formats: &[],
+  });
+  texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let data = match &initial.path {
+      Some(path) => MeshData::load(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+      }),
+      None => procedural_scene(),
+    };
+    let model = Model::from_data(&ctx.device, &data);
+
+    // モデル全体が収まる距離から眺める
+    let (min, max) = model.bounds.unwrap_or((-Vec3::ONE, Vec3::ONE));
+    let radius = ((max - min).length() * 0.5).max(1e-3);
+    let mut camera = OrbitCamera::new((min + max) * 0.5, radius * 2.5)
+      .angles(0.6, 0.4)
+      .distance_limits(radius * 0.1, radius * 20.0);
+    camera.perspective.near = radius * 0.01;
+    camera.perspective.far = radius * 100.0;
+    camera.update(ctx);
+    let camera_binding = CameraBinding::new(&ctx.device, &camera);
+
+    let shader =
+      ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("mesh shader"),
+        source: wgpu::ShaderSource::Wgsl(
+          format!("{}\n{}", CAMERA_WGSL, include_str!("./shader.wgsl")).into(),
+        ),
+      });
+
+    let pipeline_layout =
+      ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("mesh pipeline layout"),
+        bind_group_layouts: &[camera_binding.layout()],
+        push_constant_ranges: &[],
+      });
+
+    let vertex_buffer_layout = [wgsim::mesh::Mesh::layout()];
+    let pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(&vertex_buffer_layout)
+      .primitive(wgpu::PrimitiveState {
+        cull_mode: Some(wgpu::Face::Back),
+        ..Default::default()
+      })
+      .enable_depth_stencil(None)
+      .build();
+
+    Self {
+      model,
+      camera,
+      camera_binding,
+      pipeline,
+      depth_view: create_depth_view(ctx),
+    }
+  }
+
+  fn resize(&mut self, ctx: &mut DrawingContext, size: Size) {
+    if size.width > 0 && size.height > 0 {
+      ctx.resize(size);
+      self.depth_view = create_depth_view(ctx);
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    self.camera.process_event(event)
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: Duration) {
+    self.camera.update(ctx);
+    self.camera_binding.update(&ctx.queue, &self.camera);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("mesh pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: render_target_view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color {
+              r: 0.02,
+              g: 0.02,
+              b: 0.03,
+              a: 1.0,
+            }),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        depth_stencil_attachment: Some(
+          wgpu::RenderPassDepthStencilAttachment {
+            view: &self.depth_view,
+            depth_ops: Some(wgpu::Operations {
+              load: wgpu::LoadOp::Clear(1.0),
+              store: wgpu::StoreOp::Discard,
+            }),
+            stencil_ops: None,
+          },
+        ),
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, self.camera_binding.bind_group(), &[]);
+    self.model.draw(&mut render_pass);
+
+    Ok(())
+  }
+}
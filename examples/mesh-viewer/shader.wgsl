@@ -0,0 +1,34 @@
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+struct VertexInput {
+  @location(0) position: vec3f,
+  @location(1) normal: vec3f,
+  @location(2) uv: vec2f,
+}
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) normal: vec3f,
+  @location(1) uv: vec2f,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  var output: VertexOutput;
+  output.position = camera.view_proj * vec4f(in.position, 1.0);
+  output.normal = in.normal;
+  output.uv = in.uv;
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  // UV の市松模様に半球ライティングを掛ける
+  let checker = f32((u32(floor(in.uv.x * 8.0)) + u32(floor(in.uv.y * 8.0))) % 2u);
+  let albedo = mix(vec3f(0.8, 0.5, 0.3), vec3f(0.9, 0.85, 0.8), checker);
+  let n = normalize(in.normal);
+  let light = normalize(vec3f(0.4, 1.0, 0.6));
+  let diffuse = max(dot(n, light), 0.0);
+  let ambient = mix(0.15, 0.35, n.y * 0.5 + 0.5);
+  return vec4f(albedo * (diffuse * 0.8 + ambient), 1.0);
+}
@@ -0,0 +1,43 @@
+override COUNT: u32;
+
+struct Particle {
+  position: vec2f,
+  velocity: vec2f,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+@group(1) @binding(0) var<storage, read> particles: array<Particle>;
+@group(1) @binding(1) var<storage, read_write> visible: array<Particle>;
+@group(1) @binding(2) var<storage, read_write> args: DrawIndirectArgs;
+
+// 粒子の大きさ (instance.wgsl と揃える)
+const MARGIN: f32 = 0.02;
+
+fn to_world(ndc: vec2f) -> vec2f {
+  let p = camera.inv_view_proj * vec4f(ndc, 0.0, 1.0);
+  return p.xy / p.w;
+}
+
+// ワークグループ数の上限を超える COUNT は y, z に折り返してディスパッチする
+@compute @workgroup_size(64)
+fn cs_main(
+  @builtin(global_invocation_id) id: vec3u,
+  @builtin(num_workgroups) workgroups: vec3u,
+) {
+  let i = linear_index(id, workgroups, 64u);
+  if (i >= COUNT) {
+    return;
+  }
+
+  // 画面に映る範囲の外にある粒子は描かない
+  let min_world = to_world(vec2f(-1.0, -1.0)) - MARGIN;
+  let max_world = to_world(vec2f(1.0, 1.0)) + MARGIN;
+  let p = particles[i];
+  if (any(p.position < min_world) || any(p.position > max_world)) {
+    return;
+  }
+
+  let slot = atomicAdd(&args.instance_count, 1u);
+  visible[slot] = p;
+}
@@ -0,0 +1,17 @@
+struct Particle {
+  position: vec2f,
+  velocity: vec2f,
+}
+
+fn instance_attributes(p: Particle) -> InstanceAttributes {
+  // 速いものほど明るい黄色にする
+  let speed = clamp(length(p.velocity) * 1.5, 0.0, 1.0);
+  let color = mix(vec3f(0.1, 0.3, 1.0), vec3f(1.0, 0.9, 0.3), speed);
+
+  var a: InstanceAttributes;
+  a.position = vec3f(p.position, 0.0);
+  a.size = 0.02;
+  a.direction = vec3f(p.velocity, 0.0);
+  a.color = vec4f(color, 0.8);
+  return a;
+}
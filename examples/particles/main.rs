@@ -0,0 +1,325 @@
+use std::error::Error;
+use std::time::Duration;
+
+use wgpu::util::DeviceExt;
+use winit::event::{ElementState, KeyEvent, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use wgsim::app::App;
+use wgsim::camera::{Camera, Camera2D, CameraBinding, CAMERA_WGSL};
+use wgsim::ctx::{ContextOptions, DrawingContext};
+use wgsim::instancing::{
+  IndirectDraw, InstanceShape, InstancedPipeline, InstancedPipelineBuilder,
+  INDIRECT_WGSL,
+};
+use wgsim::kernel::{
+  ComputeKernel, ComputeKernelBuilder, DispatchOptions, LINEAR_INDEX_WGSL,
+};
+use wgsim::primitive::Rect;
+use wgsim::render::Render;
+use wgsim::util::{BindGroupBuilder, BindGroupLayoutBuilder};
+
+const DEFAULT_PARTICLE_COUNT: u32 = 200_000;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Particle {
+  position: [f32; 2],
+  velocity: [f32; 2],
+}
+
+// C キーで画面外の粒子の間引きを切り替え、A キーで点と矢印を切り替える
+// 粒子の数は引数で変えられる (cargo run --example particles -- 10000000)
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let count = match std::env::args().nth(1) {
+    Some(arg) => arg.parse()?,
+    None => DEFAULT_PARTICLE_COUNT,
+  };
+  let initial = setup(count);
+
+  // 数百万個の粒子は既定のストレージバッファの上限 (128 MiB) を超える
+  let mut app: App<State> = App::new("particles", initial)
+    .with_context_options(ContextOptions::new().adapter_limits(true));
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup(count: u32) -> Initial {
+  // 線形合同法で円環の上にばらまく
+  let mut seed = 0x9e37_79b9_u32;
+  let mut random = move || {
+    seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+    (seed >> 8) as f32 / (1 << 24) as f32
+  };
+  let particles = (0..count)
+    .map(|_| {
+      let angle = random() * std::f32::consts::TAU;
+      let radius = 0.2 + random() * 0.9;
+      let (sin, cos) = angle.sin_cos();
+      Particle {
+        position: [cos * radius, sin * radius],
+        velocity: [-sin * 0.3, cos * 0.3],
+      }
+    })
+    .collect();
+
+  Initial { particles }
+}
+
+struct Initial {
+  particles: Vec<Particle>,
+}
+
+struct State {
+  count: u32,
+  step_kernel: ComputeKernel,
+  cull_kernel: ComputeKernel,
+  points: InstancedPipeline,
+  arrows: InstancedPipeline,
+  // 描画パイプラインから読むバインドグループ (全粒子 / 画面内の粒子)
+  all_instances: wgpu::BindGroup,
+  visible_instances: wgpu::BindGroup,
+  indirect: IndirectDraw,
+  camera: Camera2D,
+  camera_binding: CameraBinding,
+  culling: bool,
+  shape: InstanceShape,
+  shape_changed: bool,
+}
+
+impl State {
+  fn pipeline(&self) -> &InstancedPipeline {
+    match self.shape {
+      InstanceShape::Arrow => &self.arrows,
+      _ => &self.points,
+    }
+  }
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let device = &ctx.device;
+    let count = initial.particles.len() as u32;
+    let count_constants = [("COUNT", count as f64)];
+
+    let particles =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("particle buffer"),
+        contents: bytemuck::cast_slice(&initial.particles),
+        usage: wgpu::BufferUsages::STORAGE,
+      });
+    let visible = device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("visible particle buffer"),
+      size: particles.size(),
+      usage: wgpu::BufferUsages::STORAGE,
+      mapped_at_creation: false,
+    });
+    let indirect = IndirectDraw::new(device, InstanceShape::PointSprite);
+
+    let camera = Camera2D::fit(Rect::new(-1.2, -1.2, 2.4, 2.4), *ctx.size());
+    let camera_binding = CameraBinding::new(device, &camera);
+
+    // 粒子の位置と速度を進める
+    let step_source =
+      format!("{}\n{}", LINEAR_INDEX_WGSL, include_str!("./step.wgsl"));
+    let step_layout = BindGroupLayoutBuilder::new(device)
+      .label("step bind group layout")
+      .storage(0, wgpu::ShaderStages::COMPUTE, false)
+      .build();
+    let step_pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("step pipeline layout"),
+        bind_group_layouts: &[&step_layout.layout],
+        push_constant_ranges: &[],
+      });
+    let step_bind_group = BindGroupBuilder::new(device, &step_layout)
+      .label("step bind group")
+      .buffer(0, &particles)
+      .build();
+    let step_kernel =
+      ComputeKernelBuilder::new(device, &step_source, "cs_main")
+        .pipeline_layout(&step_pipeline_layout)
+        .constants(&count_constants)
+        .build()
+        .expect("Failed to build the step kernel")
+        .with_bind_group(0, step_bind_group);
+
+    // 画面内の粒子だけを visible に詰め、その数を indirect に書く
+    let cull_source = format!(
+      "{}\n{}\n{}\n{}",
+      CAMERA_WGSL,
+      INDIRECT_WGSL,
+      LINEAR_INDEX_WGSL,
+      include_str!("./cull.wgsl")
+    );
+    let cull_layout = BindGroupLayoutBuilder::new(device)
+      .label("cull bind group layout")
+      .storage(0, wgpu::ShaderStages::COMPUTE, true)
+      .storage(1, wgpu::ShaderStages::COMPUTE, false)
+      .storage(2, wgpu::ShaderStages::COMPUTE, false)
+      .build();
+    let cull_pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("cull pipeline layout"),
+        bind_group_layouts: &[camera_binding.layout(), &cull_layout.layout],
+        push_constant_ranges: &[],
+      });
+    let cull_bind_group = BindGroupBuilder::new(device, &cull_layout)
+      .label("cull bind group")
+      .buffer(0, &particles)
+      .buffer(1, &visible)
+      .buffer(2, indirect.buffer())
+      .build();
+    let cull_kernel =
+      ComputeKernelBuilder::new(device, &cull_source, "cs_main")
+        .pipeline_layout(&cull_pipeline_layout)
+        .constants(&count_constants)
+        .build()
+        .expect("Failed to build the cull kernel")
+        .with_bind_group(1, cull_bind_group);
+
+    let instance_wgsl = include_str!("./instance.wgsl");
+    let build = |shape| {
+      InstancedPipelineBuilder::new(
+        ctx,
+        shape,
+        instance_wgsl,
+        camera_binding.layout(),
+      )
+      .instance_type("Particle")
+      .blend(Some(wgpu::BlendState {
+        color: wgpu::BlendComponent {
+          src_factor: wgpu::BlendFactor::SrcAlpha,
+          dst_factor: wgpu::BlendFactor::One,
+          operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent::OVER,
+      }))
+      .build()
+    };
+    let points = build(InstanceShape::PointSprite);
+    let arrows = build(InstanceShape::Arrow);
+
+    // 同じバッファをコピーせずに描画側から読む
+    let all_instances = points.storage_bind_group(device, &particles);
+    let visible_instances = points.storage_bind_group(device, &visible);
+
+    Self {
+      count,
+      step_kernel,
+      cull_kernel,
+      points,
+      arrows,
+      all_instances,
+      visible_instances,
+      indirect,
+      camera,
+      camera_binding,
+      culling: true,
+      shape: InstanceShape::PointSprite,
+      shape_changed: false,
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    let WindowEvent::KeyboardInput {
+      event:
+        KeyEvent {
+          physical_key: PhysicalKey::Code(code),
+          state: ElementState::Pressed,
+          repeat: false,
+          ..
+        },
+      ..
+    } = event
+    else {
+      return self.camera.process_event(event);
+    };
+
+    match code {
+      KeyCode::KeyC => {
+        self.culling = !self.culling;
+        true
+      }
+      KeyCode::KeyA => {
+        self.shape = match self.shape {
+          InstanceShape::Arrow => InstanceShape::PointSprite,
+          _ => InstanceShape::Arrow,
+        };
+        self.shape_changed = true;
+        true
+      }
+      _ => self.camera.process_event(event),
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: Duration) {
+    self.camera.update(ctx);
+    self.camera_binding.update(&ctx.queue, &self.camera);
+    if std::mem::take(&mut self.shape_changed) {
+      self.indirect.set_shape(&ctx.queue, self.shape);
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self
+      .step_kernel
+      .dispatch_linear(encoder, self.count)
+      .expect("Too many particles for one dispatch");
+    if self.culling {
+      self.indirect.reset(encoder);
+      // カメラは CameraBinding のバインドグループをそのまま使う
+      self.cull_kernel.dispatch_workgroups(
+        encoder,
+        self
+          .cull_kernel
+          .workgroup_count_linear(self.count)
+          .expect("Too many particles for one dispatch"),
+        DispatchOptions::new().bind_group(0, self.camera_binding.bind_group()),
+      );
+    }
+
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("particle pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: render_target_view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    let camera = self.camera_binding.bind_group();
+    if self.culling {
+      self.pipeline().draw_indirect(
+        &mut render_pass,
+        camera,
+        &self.visible_instances,
+        &self.indirect,
+      );
+    } else {
+      self.pipeline().draw(
+        &mut render_pass,
+        camera,
+        &self.all_instances,
+        0..self.count,
+      );
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,31 @@
+override COUNT: u32;
+
+struct Particle {
+  position: vec2f,
+  velocity: vec2f,
+}
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+
+const DT: f32 = 1.0 / 60.0;
+
+// ワークグループ数の上限を超える COUNT は y, z に折り返してディスパッチする
+@compute @workgroup_size(64)
+fn cs_main(
+  @builtin(global_invocation_id) id: vec3u,
+  @builtin(num_workgroups) workgroups: vec3u,
+) {
+  let i = linear_index(id, workgroups, 64u);
+  if (i >= COUNT) {
+    return;
+  }
+
+  var p = particles[i];
+  // 原点の周りを回る渦と、原点へ引き戻す力
+  let r = length(p.position) + 1e-3;
+  let swirl = vec2f(-p.position.y, p.position.x) / r;
+  let pull = -p.position * (r - 0.6) * 2.0;
+  p.velocity = (p.velocity + (swirl * 0.8 + pull) * DT) * 0.995;
+  p.position += p.velocity * DT;
+  particles[i] = p;
+}
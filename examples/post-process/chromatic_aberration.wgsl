@@ -0,0 +1,18 @@
+struct Params {
+  offset: f32,
+}
+
+@group(2) @binding(0) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  // 画面中心から離れるほど色のずれを大きくする
+  let dir = (in.uv - vec2f(0.5)) * params.offset;
+
+  let r = textureSample(effect_input, effect_sampler, in.uv + dir).r;
+  let g = textureSample(effect_input, effect_sampler, in.uv).g;
+  let b = textureSample(effect_input, effect_sampler, in.uv - dir).b;
+  let a = textureSample(effect_input, effect_sampler, in.uv).a;
+
+  return vec4f(r, g, b, a);
+}
@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::time::Duration;
+
+use wgsim::app::App;
+use wgsim::ctx::DrawingContext;
+use wgsim::effect::{Effect, EffectChain, EffectChainBuilder};
+use wgsim::fullscreen::{FullscreenPass, FullscreenPassBuilder, ObjectFit};
+use wgsim::render::Render;
+use wgsim::texture::TextureBuilder;
+use wgsim::util::{BindGroupBuilder, BindGroupLayoutBuilder, UniformBuffer};
+
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("post-process", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  let img_bytes = include_bytes!("../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+
+  Initial { image }
+}
+
+struct Initial {
+  image: image::DynamicImage,
+}
+
+struct State {
+  render_result_bind_group: wgpu::BindGroup,
+  render_result_pass: FullscreenPass,
+  effect_chain: EffectChain,
+  aberration: UniformBuffer<[f32; 4]>,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let src_texture =
+      TextureBuilder::new(ctx).label("src texture").from_image(&initial.image);
+
+    let render_result_bind_group_layout =
+      BindGroupLayoutBuilder::new(&ctx.device)
+        .label("render result bind group layout")
+        .sampler(
+          0,
+          wgpu::ShaderStages::FRAGMENT,
+          wgpu::SamplerBindingType::Filtering,
+        )
+        .texture(
+          1,
+          wgpu::ShaderStages::FRAGMENT,
+          wgpu::TextureSampleType::Float { filterable: true },
+          wgpu::TextureViewDimension::D2,
+        )
+        .build();
+
+    let render_result_bind_group =
+      BindGroupBuilder::new(&ctx.device, &render_result_bind_group_layout)
+        .label("render result bind group")
+        .sampler(0, &src_texture.sampler)
+        .texture_view(1, &src_texture.view)
+        .build();
+
+    let aberration = UniformBuffer::new(&ctx.device, [0.0f32; 4]);
+    // 変えない値はバインドグループが持つバッファだけで足りる
+    let vignette = UniformBuffer::new(&ctx.device, [0.8f32, 0.3, 0.0, 0.0]);
+    let effect_chain = EffectChainBuilder::new(ctx)
+      .hdr()
+      .effect(
+        Effect::new(
+          "chromatic aberration",
+          include_str!("./chromatic_aberration.wgsl"),
+        )
+        .uniform(&aberration),
+      )
+      .effect(
+        Effect::new("vignette", include_str!("./vignette.wgsl"))
+          .uniform(&vignette),
+      )
+      .build();
+
+    // 元の画像はエフェクトチェーンの入力に描画する
+    let render_result_pass =
+      FullscreenPassBuilder::new(ctx, include_str!("./render.wgsl"))
+        .bind_group_layout(&render_result_bind_group_layout)
+        .fit(ObjectFit::Cover, src_texture.size())
+        .format(effect_chain.format())
+        .build();
+
+    Self {
+      render_result_bind_group,
+      render_result_pass,
+      effect_chain,
+      aberration,
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: Duration) {
+    let offset = 0.02 * (dt.as_secs_f32() * 2.0).sin().abs();
+    self.aberration.set([offset, 0.0, 0.0, 0.0]);
+    self.aberration.flush(&ctx.queue);
+
+    self.render_result_pass.update(ctx);
+    self.effect_chain.update(ctx);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self.render_result_pass.draw(
+      encoder,
+      self.effect_chain.input_view(),
+      &[&self.render_result_bind_group],
+    );
+
+    self.effect_chain.run(encoder, render_target_view);
+
+    Ok(())
+  }
+}
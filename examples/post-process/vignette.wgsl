@@ -0,0 +1,16 @@
+struct Params {
+  strength: f32,
+  radius: f32,
+}
+
+@group(2) @binding(0) var<uniform> params: Params;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let color = textureSample(effect_input, effect_sampler, in.uv);
+
+  let d = distance(in.uv, vec2f(0.5));
+  let vignette = 1.0 - params.strength * smoothstep(params.radius, 0.75, d);
+
+  return vec4f(color.rgb * vignette, color.a);
+}
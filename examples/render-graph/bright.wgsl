@@ -0,0 +1,21 @@
+@group(1) @binding(0) var hdr_texture: texture_2d<f32>;
+@group(1) @binding(1) var hdr_sampler: sampler;
+
+const THRESHOLD: f32 = 0.6;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let texel = 1.0 / vec2f(textureDimensions(hdr_texture));
+
+  // 明るい部分だけを取り出し、縮小しながら軽くぼかす
+  var sum = vec3f(0.0);
+  for (var y = -2; y <= 2; y++) {
+    for (var x = -2; x <= 2; x++) {
+      let offset = vec2f(f32(x), f32(y)) * texel * 2.0;
+      let color = textureSample(hdr_texture, hdr_sampler, in.uv + offset).rgb;
+      sum += max(color - vec3f(THRESHOLD), vec3f(0.0));
+    }
+  }
+
+  return vec4f(sum / 25.0, 1.0);
+}
@@ -0,0 +1,17 @@
+@group(1) @binding(0) var hdr_texture: texture_2d<f32>;
+@group(1) @binding(1) var bloom_texture: texture_2d<f32>;
+@group(1) @binding(2) var linear_sampler: sampler;
+
+const BLOOM_STRENGTH: f32 = 2.5;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let hdr = textureSample(hdr_texture, linear_sampler, in.uv).rgb;
+  let bloom = textureSample(bloom_texture, linear_sampler, in.uv).rgb;
+
+  // 足し合わせた分を簡単なトーンマッピングで収める
+  let color = hdr + bloom * BLOOM_STRENGTH;
+  let mapped = color / (1.0 + color) * 1.6;
+
+  return vec4f(mapped, 1.0);
+}
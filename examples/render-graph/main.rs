@@ -0,0 +1,229 @@
+use std::error::Error;
+use std::time::Duration;
+
+use wgsim::app::App;
+use wgsim::ctx::DrawingContext;
+use wgsim::fullscreen::{FullscreenPass, FullscreenPassBuilder, ObjectFit};
+use wgsim::graph::{RenderGraph, RenderGraphBuilder, TextureDesc};
+use wgsim::render::Render;
+use wgsim::texture::TextureBuilder;
+use wgsim::util::{BindGroupBuilder, BindGroupLayout, BindGroupLayoutBuilder};
+
+const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("render-graph", initial)
+    .with_stats_in_title()
+    .with_stats_overlay();
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  let img_bytes = include_bytes!("../assets/img/stained-glass_w600.png");
+  let image = image::load_from_memory(img_bytes).unwrap();
+
+  Initial { image }
+}
+
+struct Initial {
+  image: image::DynamicImage,
+}
+
+// パスから参照される、フレームをまたいで残るリソース
+struct Resources {
+  scene_pass: FullscreenPass,
+  scene_bind_group: wgpu::BindGroup,
+  bright_pass: FullscreenPass,
+  bright_bind_group_layout: BindGroupLayout,
+  composite_pass: FullscreenPass,
+  composite_bind_group_layout: BindGroupLayout,
+  sampler: wgpu::Sampler,
+}
+
+struct State {
+  graph: RenderGraph<Resources>,
+  resources: Resources,
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let src_texture =
+      TextureBuilder::new(ctx).label("src texture").from_image(&initial.image);
+
+    let scene_bind_group_layout = BindGroupLayoutBuilder::new(&ctx.device)
+      .label("scene bind group layout")
+      .sampler(
+        0,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::SamplerBindingType::Filtering,
+      )
+      .texture(
+        1,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .build();
+
+    let scene_bind_group =
+      BindGroupBuilder::new(&ctx.device, &scene_bind_group_layout)
+        .label("scene bind group")
+        .sampler(0, &src_texture.sampler)
+        .texture_view(1, &src_texture.view)
+        .build();
+
+    let bright_bind_group_layout = BindGroupLayoutBuilder::new(&ctx.device)
+      .label("bright bind group layout")
+      .texture(
+        0,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .sampler(
+        1,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::SamplerBindingType::Filtering,
+      )
+      .build();
+
+    let composite_bind_group_layout = BindGroupLayoutBuilder::new(&ctx.device)
+      .label("composite bind group layout")
+      .texture(
+        0,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .texture(
+        1,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .sampler(
+        2,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::SamplerBindingType::Filtering,
+      )
+      .build();
+
+    let sampler = ctx.device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("linear sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let scene_pass =
+      FullscreenPassBuilder::new(ctx, include_str!("./scene.wgsl"))
+        .bind_group_layout(&scene_bind_group_layout)
+        .fit(ObjectFit::Cover, src_texture.size())
+        .format(HDR_FORMAT)
+        .build();
+
+    let bright_pass =
+      FullscreenPassBuilder::new(ctx, include_str!("./bright.wgsl"))
+        .bind_group_layout(&bright_bind_group_layout)
+        .format(HDR_FORMAT)
+        .build();
+
+    let composite_pass =
+      FullscreenPassBuilder::new(ctx, include_str!("./composite.wgsl"))
+        .bind_group_layout(&composite_bind_group_layout)
+        .build();
+
+    let mut builder = RenderGraphBuilder::new();
+    let target = builder.target();
+    let hdr = builder.texture("hdr", TextureDesc::new(HDR_FORMAT));
+    let bloom =
+      builder.texture("bloom", TextureDesc::new(HDR_FORMAT).scale(0.25));
+
+    // 宣言の順序に関わらず、読み書きの関係から実行順が決まる
+    builder
+      .pass("composite")
+      .read(hdr)
+      .read(bloom)
+      .write(target)
+      .bind_groups(move |res: &Resources, device, textures| {
+        vec![
+          BindGroupBuilder::new(device, &res.composite_bind_group_layout)
+            .label("composite bind group")
+            .texture_view(0, textures.view(hdr))
+            .texture_view(1, textures.view(bloom))
+            .sampler(2, &res.sampler)
+            .build(),
+        ]
+      })
+      .execute(move |res, pass| {
+        let bind_group = pass.bind_group(0);
+        res.composite_pass.draw(pass.encoder, pass.view(target), &[bind_group]);
+      });
+
+    builder.pass("scene").write(hdr).execute(move |res, pass| {
+      res.scene_pass.draw(
+        pass.encoder,
+        pass.view(hdr),
+        &[&res.scene_bind_group],
+      );
+    });
+
+    builder
+      .pass("bright")
+      .read(hdr)
+      .write(bloom)
+      .bind_groups(move |res: &Resources, device, textures| {
+        vec![BindGroupBuilder::new(device, &res.bright_bind_group_layout)
+          .label("bright bind group")
+          .texture_view(0, textures.view(hdr))
+          .sampler(1, &res.sampler)
+          .build()]
+      })
+      .execute(move |res, pass| {
+        let bind_group = pass.bind_group(0);
+        res.bright_pass.draw(pass.encoder, pass.view(bloom), &[bind_group]);
+      });
+
+    let graph = builder.build().expect("Failed to build render graph");
+
+    Self {
+      graph,
+      resources: Resources {
+        scene_pass,
+        scene_bind_group,
+        bright_pass,
+        bright_bind_group_layout,
+        composite_pass,
+        composite_bind_group_layout,
+        sampler,
+      },
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, _dt: Duration) {
+    self.resources.scene_pass.update(ctx);
+    self.resources.bright_pass.update(ctx);
+    self.resources.composite_pass.update(ctx);
+
+    self.graph.update(ctx, &self.resources);
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self.graph.execute(&self.resources, encoder, render_target_view);
+
+    Ok(())
+  }
+}
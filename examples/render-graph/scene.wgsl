@@ -0,0 +1,7 @@
+@group(1) @binding(0) var scene_sampler: sampler;
+@group(1) @binding(1) var scene_texture: texture_2d<f32>;
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  return textureSample(scene_texture, scene_sampler, in.uv);
+}
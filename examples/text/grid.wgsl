@@ -0,0 +1,26 @@
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+// 1 ピクセル幅の線の濃さ
+fn line_coverage(coord: vec2f, step: f32) -> f32 {
+  let distance = abs(fract(coord / step + 0.5) - 0.5) * step;
+  let coverage = 1.0 - clamp(distance / fwidth(coord), vec2f(0.0), vec2f(1.0));
+  return max(coverage.x, coverage.y);
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let ndc = vec2f(in.uv.x * 2.0 - 1.0, 1.0 - in.uv.y * 2.0);
+  let p = camera.inv_view_proj * vec4f(ndc, 0.0, 1.0);
+  let world = p.xy / p.w;
+
+  // 100 ピクセル程度の間隔になる 10 の累乗 (main.rs の grid_step と揃える)
+  let world_per_pixel = fwidth(world).x;
+  let step = pow(10.0, floor(log2(world_per_pixel * 100.0) / log2(10.0)));
+
+  var color = vec3f(0.02, 0.02, 0.03);
+  color = mix(color, vec3f(0.07), line_coverage(world, step * 0.1));
+  color = mix(color, vec3f(0.3), line_coverage(world, step));
+  let axis = 1.0 - clamp(abs(world) / fwidth(world), vec2f(0.0), vec2f(1.0));
+  color = mix(color, vec3f(0.8, 0.4, 0.2), max(axis.x, axis.y));
+  return vec4f(color, 1.0);
+}
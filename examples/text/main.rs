@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::time::Duration;
+
+use winit::event::WindowEvent;
+
+use wgsim::app::App;
+use wgsim::camera::{Camera, Camera2D, CameraBinding, CAMERA_WGSL};
+use wgsim::ctx::DrawingContext;
+use wgsim::fullscreen::{FullscreenPass, FullscreenPassBuilder};
+use wgsim::primitive::{Color, Point, Rect, Size, Vec3};
+use wgsim::render::Render;
+use wgsim::text::{HorizontalAlign, Text, TextRenderer, VerticalAlign};
+
+// ドラッグでパン、ホイールで拡大縮小、R で元に戻す
+fn main() -> Result<(), Box<dyn Error>> {
+  env_logger::init();
+
+  let initial = setup();
+
+  let mut app: App<State> = App::new("text", initial);
+  app.run()?;
+
+  Ok(())
+}
+
+fn setup() -> Initial {
+  Initial {
+    view: Rect::new(-2.0, -2.0, 4.0, 4.0),
+  }
+}
+
+struct Initial {
+  view: Rect,
+}
+
+struct State {
+  camera: Camera2D,
+  camera_binding: CameraBinding,
+  grid: FullscreenPass,
+  text: TextRenderer,
+  frames: u64,
+}
+
+// 100 ピクセル程度の間隔になる 10 の累乗 (grid.wgsl と揃える)
+fn grid_step(zoom: f32) -> f32 {
+  10f32.powf((100.0 / zoom).log10().floor())
+}
+
+impl State {
+  // 画面に見えている範囲の目盛りにワールド座標でラベルを付ける
+  fn queue_axis_labels(&mut self, viewport: Size<f32>) {
+    let step = grid_step(self.camera.zoom);
+    let decimals = (-step.log10()).max(0.0) as usize;
+    let min = self.camera.screen_to_world(Point::new(0.0, viewport.height));
+    let max = self.camera.screen_to_world(Point::new(viewport.width, 0.0));
+    let size = step * 0.2;
+    let margin = step * 0.05;
+    let color = Color::srgb(0.8, 0.8, 0.8, 1.0);
+
+    let mut x = (min.x / step).ceil() * step;
+    while x <= max.x {
+      self.text.queue(
+        Text::new(format!("{:.*}", decimals, x))
+          .at_world(Vec3::new(x + margin, -margin, 0.0))
+          .size(size)
+          .color(color),
+      );
+      x += step;
+    }
+
+    let mut y = (min.y / step).ceil() * step;
+    while y <= max.y {
+      if y.abs() > step * 0.5 {
+        self.text.queue(
+          Text::new(format!("{:.*}", decimals, y))
+            .at_world(Vec3::new(-margin, y, 0.0))
+            .size(size)
+            .color(color)
+            .align(HorizontalAlign::Right)
+            .vertical_align(VerticalAlign::Middle),
+        );
+      }
+      y += step;
+    }
+  }
+}
+
+impl<'a> Render<'a> for State {
+  type Initial = Initial;
+
+  async fn new(ctx: &DrawingContext<'a>, initial: &Self::Initial) -> Self {
+    let camera = Camera2D::fit(initial.view, *ctx.size());
+    let camera_binding = CameraBinding::new(&ctx.device, &camera);
+
+    let grid_source =
+      format!("{}\n{}", CAMERA_WGSL, include_str!("./grid.wgsl"));
+    let grid = FullscreenPassBuilder::new(ctx, &grid_source)
+      .bind_group_layout(camera_binding.layout())
+      .build();
+
+    Self {
+      camera,
+      camera_binding,
+      grid,
+      text: TextRenderer::new(ctx),
+      frames: 0,
+    }
+  }
+
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    self.camera.process_event(event)
+  }
+
+  fn update(&mut self, ctx: &DrawingContext, dt: Duration) {
+    self.camera.update(ctx);
+    self.camera_binding.update(&ctx.queue, &self.camera);
+    self.grid.update(ctx);
+
+    self.frames += 1;
+    let viewport = ctx.size().to_f32();
+    self.queue_axis_labels(viewport);
+
+    // 画面に重ねる情報
+    let fps = self.frames as f64 / dt.as_secs_f64().max(1e-3);
+    self.text.queue(
+      Text::new(format!(
+        "frame {}\n{:.1} fps\nzoom {:.1} px/unit",
+        self.frames, fps, self.camera.zoom
+      ))
+      .at(12.0, 12.0)
+      .size(18.0),
+    );
+    self.text.queue(
+      Text::new("drag to pan, scroll to zoom, R to reset")
+        .at(viewport.width - 12.0, viewport.height - 12.0)
+        .size(14.0)
+        .color(Color::srgb(0.6, 0.6, 0.6, 1.0))
+        .align(HorizontalAlign::Right)
+        .vertical_align(VerticalAlign::Bottom),
+    );
+
+    if let Err(e) = self.text.prepare(ctx) {
+      eprintln!("{}", e);
+    }
+  }
+
+  fn draw(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    render_target_view: &wgpu::TextureView,
+    _sample_count: u32,
+  ) -> Result<(), wgpu::SurfaceError> {
+    self.grid.draw(
+      encoder,
+      render_target_view,
+      &[self.camera_binding.bind_group()],
+    );
+    self.text.draw(
+      encoder,
+      render_target_view,
+      Some(self.camera_binding.bind_group()),
+    );
+
+    Ok(())
+  }
+}
@@ -1,4 +1,4 @@
-use std::{error::Error, sync::Arc};
+use std::{error::Error, path::PathBuf, sync::Arc, time::Instant};
 
 use winit::{
   application::ApplicationHandler,
@@ -10,9 +10,21 @@ use winit::{
 };
 
 use crate::{
-  ctx::DrawingContext, render::Render, surface_cfg::SurfaceConfigBuilder,
+  capture::{CapturedFrame, FrameCapture},
+  ctx::{ContextOptions, DrawingContext},
+  profiler::GpuProfiler,
+  record::{RecordFormat, Recorder},
+  render::Render,
+  stats::{FrameStats, StatsOverlay},
+  surface_cfg::{SurfaceConfigBuilder, SurfaceConfigError},
 };
 
+#[cfg(feature = "egui")]
+use crate::ui::EguiLayer;
+
+const TITLE_UPDATE_INTERVAL: std::time::Duration =
+  std::time::Duration::from_millis(500);
+
 pub struct App<'a, R>
 where
   R: Render<'a>,
@@ -24,10 +36,36 @@ where
   ctx: Option<DrawingContext<'a>>,
   surface_cfg_builder: Option<&'a SurfaceConfigBuilder<'a>>,
   sample_count: u32,
+  context_options: ContextOptions,
   renderer: Option<R>,
   render_start_time: Option<std::time::Instant>,
   update_interval: Option<std::time::Duration>,
   need_redraw: bool,
+  stats: Option<FrameStats>,
+  stats_in_title: bool,
+  stats_overlay: bool,
+  stats_csv_path: Option<PathBuf>,
+  overlay: Option<StatsOverlay>,
+  frame_profiler: Option<GpuProfiler>,
+  title_updated_at: Option<Instant>,
+  capture: FrameCapture<CaptureTag>,
+  capture_requests: Vec<PathBuf>,
+  screenshot_key: Option<KeyCode>,
+  capture_dir: PathBuf,
+  recorder: Option<Recorder>,
+  record_format: RecordFormat,
+  record_key: Option<KeyCode>,
+  vsync_key: Option<KeyCode>,
+  #[cfg(feature = "egui")]
+  egui_enabled: bool,
+  #[cfg(feature = "egui")]
+  egui: Option<EguiLayer>,
+}
+
+// 読み戻したフレームの使い道
+struct CaptureTag {
+  screenshots: Vec<PathBuf>,
+  record: bool,
 }
 
 impl<'a, R> App<'a, R>
@@ -41,12 +79,32 @@ where
       window_size: None,
       initial,
       sample_count: 1,
+      context_options: ContextOptions::default(),
       ctx: None,
       surface_cfg_builder: None,
       renderer: None,
       render_start_time: None,
       update_interval: None,
       need_redraw: true,
+      stats: None,
+      stats_in_title: false,
+      stats_overlay: false,
+      stats_csv_path: None,
+      overlay: None,
+      frame_profiler: None,
+      title_updated_at: None,
+      capture: FrameCapture::new(),
+      capture_requests: Vec::new(),
+      screenshot_key: Some(KeyCode::F12),
+      capture_dir: PathBuf::from("."),
+      recorder: None,
+      record_format: RecordFormat::Gif,
+      record_key: None,
+      vsync_key: None,
+      #[cfg(feature = "egui")]
+      egui_enabled: false,
+      #[cfg(feature = "egui")]
+      egui: None,
     }
   }
 
@@ -65,6 +123,123 @@ where
     self
   }
 
+  // features はアダプタが対応している分だけ有効になる
+  pub fn with_context_options(mut self, options: ContextOptions) -> Self {
+    self.context_options = options;
+    self
+  }
+
+  pub fn with_gpu_profiling(self) -> Self {
+    let options = self.context_options.features(GpuProfiler::FEATURES);
+    self.with_context_options(options)
+  }
+
+  // GPU 時間は timestamp query が使える場合のみ記録される
+  pub fn with_frame_stats(mut self) -> Self {
+    if self.stats.is_none() {
+      self.stats = Some(FrameStats::new(self.update_interval));
+    }
+    self.with_gpu_profiling()
+  }
+
+  pub fn with_stats_in_title(mut self) -> Self {
+    self.stats_in_title = true;
+    self.with_frame_stats()
+  }
+
+  // Render::draw の後に、直近のフレーム間隔を棒グラフで重ねて描く
+  pub fn with_stats_overlay(mut self) -> Self {
+    self.stats_overlay = true;
+    self.with_frame_stats()
+  }
+
+  // 終了時に全フレームの記録を書き出す
+  pub fn with_stats_csv(mut self, path: impl Into<PathBuf>) -> Self {
+    self.stats_csv_path = Some(path.into());
+    let mut app = self.with_frame_stats();
+    app.stats = app.stats.take().map(FrameStats::with_log);
+    app
+  }
+
+  // Render::ui で作った UI を Render::draw の後に重ねて描く
+  // イベントは egui が先に受け取り、使わなかったものだけ Render::process_event に渡る
+  #[cfg(feature = "egui")]
+  pub fn with_egui(mut self) -> Self {
+    self.egui_enabled = true;
+    self
+  }
+
+  // F12 のスクリーンショットに加えて、F10 で録画の開始と停止、
+  // F9 で垂直同期の切り替えを有効にする
+  // (スクリーンショットと録画は with_capture_dir のディレクトリに書き出す)
+  pub fn with_default_hotkeys(self) -> Self {
+    self
+      .with_screenshot_key(Some(KeyCode::F12))
+      .with_record_key(Some(KeyCode::F10))
+      .with_vsync_key(Some(KeyCode::F9))
+  }
+
+  // 既定では F12 (None を渡すと無効にする)
+  pub fn with_screenshot_key(mut self, key: Option<KeyCode>) -> Self {
+    self.screenshot_key = key;
+    self
+  }
+
+  // スクリーンショットと録画の保存先
+  pub fn with_capture_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.capture_dir = dir.into();
+    self
+  }
+
+  // 既定では無効 (None を渡すと無効にする)
+  pub fn with_record_key(mut self, key: Option<KeyCode>) -> Self {
+    self.record_key = key;
+    self
+  }
+
+  pub fn with_record_format(mut self, format: RecordFormat) -> Self {
+    self.record_format = format;
+    self
+  }
+
+  // 表示したフレームをそのまま書き出す (PngSequence の場合 path はディレクトリ)
+  pub fn start_recording(&mut self, path: impl Into<PathBuf>) {
+    self.stop_recording();
+
+    let recorder = Recorder::start(path, self.record_format);
+    println!("Recording to {}", recorder.path().display());
+    self.recorder = Some(recorder);
+    self.need_redraw = true;
+  }
+
+  pub fn stop_recording(&mut self) {
+    // 読み戻し中のフレームも含めて書き出す
+    if let Some(ctx) = &self.ctx {
+      let frames = self.capture.flush(&ctx.device);
+      self.dispatch_captured_frames(frames);
+    }
+
+    if let Some(recorder) = self.recorder.take() {
+      recorder.finish_in_background();
+    }
+  }
+
+  pub fn is_recording(&self) -> bool {
+    self.recorder.is_some()
+  }
+
+  // 既定では無効 (None を渡すと無効にする)
+  pub fn with_vsync_key(mut self, key: Option<KeyCode>) -> Self {
+    self.vsync_key = key;
+    self
+  }
+
+  // 次に描画したフレームを PNG として保存する (書き出しは別スレッドで行う)
+  pub fn capture_next_frame(&mut self, path: impl Into<PathBuf>) {
+    self.capture_requests.push(path.into());
+    self.need_redraw = true;
+  }
+
   pub fn with_surface_cfg_builder(
     mut self,
     builder: &'a SurfaceConfigBuilder<'a>,
@@ -87,19 +262,226 @@ where
     }
   }
 
-  async fn init(&mut self, window: Arc<Window>) {
+  async fn init(
+    &mut self,
+    window: Arc<Window>,
+  ) -> Result<(), SurfaceConfigError> {
     let surface_cfg_builder = match self.surface_cfg_builder {
       Some(builder) => builder,
       None => &SurfaceConfigBuilder::new(),
     };
 
-    let ctx = DrawingContext::new_for_surface(window, &surface_cfg_builder)
-      .await
-      .with_sample_count(self.sample_count);
+    let ctx = DrawingContext::new_for_surface_with_options(
+      window,
+      surface_cfg_builder,
+      &self.context_options,
+    )
+    .await?
+    .with_sample_count(self.sample_count);
     self.ctx = Some(ctx);
 
-    let renderer = R::new(self.ctx.as_ref().unwrap(), &self.initial).await;
+    let ctx = self.ctx.as_ref().unwrap();
+    if self.stats.is_some() {
+      self.frame_profiler = Some(GpuProfiler::new(ctx));
+    }
+    if self.stats_overlay {
+      self.overlay = Some(StatsOverlay::new(ctx));
+    }
+    #[cfg(feature = "egui")]
+    if let (true, Some(window)) = (self.egui_enabled, &self.window) {
+      self.egui = Some(EguiLayer::new(ctx, window));
+    }
+
+    let renderer = R::new(ctx, &self.initial).await;
     self.renderer = Some(renderer);
+
+    Ok(())
+  }
+
+  fn toggle_vsync(&mut self) {
+    let Some(ctx) = &mut self.ctx else {
+      return;
+    };
+    let vsync = !matches!(
+      ctx.present_mode(),
+      Some(wgpu::PresentMode::Fifo | wgpu::PresentMode::FifoRelaxed)
+    );
+    match ctx.set_vsync(vsync) {
+      Ok(()) => println!("Present mode: {:?}", ctx.present_mode().unwrap()),
+      Err(e) => eprintln!("{}", e),
+    }
+  }
+
+  // PngSequence のように拡張子がない場合は suffix に "" を渡す
+  fn capture_path(&self, suffix: &str) -> PathBuf {
+    let millis = std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|d| d.as_millis())
+      .unwrap_or_default();
+    self.capture_dir.join(format!("{}-{}{}", self.window_title, millis, suffix))
+  }
+
+  fn dispatch_captured_frames(
+    &mut self,
+    frames: Vec<(CaptureTag, CapturedFrame)>,
+  ) {
+    for (tag, frame) in frames {
+      for path in tag.screenshots {
+        let frame = frame.clone();
+        std::thread::spawn(move || match frame.save(&path) {
+          Ok(()) => println!("Screenshot has been saved to {}", path.display()),
+          Err(e) => eprintln!("{:?}", e),
+        });
+      }
+      if let (true, Some(recorder)) = (tag.record, &mut self.recorder) {
+        recorder.push(frame);
+      }
+    }
+  }
+
+  fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+    if let Some(ctx) = &self.ctx {
+      let frames = self.capture.poll(&ctx.device);
+      self.dispatch_captured_frames(frames);
+    }
+
+    let (Some(renderer), Some(ctx)) = (&mut self.renderer, &mut self.ctx)
+    else {
+      return;
+    };
+
+    let surface = match ctx.surface() {
+      Some(surface) => surface,
+      None => {
+        eprintln!("Surface is not initialized");
+        return;
+      }
+    };
+
+    let frame_start = Instant::now();
+    let dt = frame_start - self.render_start_time.unwrap_or(frame_start);
+    // UI での変更を同じフレームの update で反映できるよう、先に組み立てる
+    #[cfg(feature = "egui")]
+    if let (Some(egui), Some(window)) = (&mut self.egui, &self.window) {
+      if egui.run(window, |egui_ctx| renderer.ui(egui_ctx)) {
+        self.need_redraw = true;
+      }
+    }
+    renderer.update(ctx, dt);
+    let update_time = frame_start.elapsed();
+
+    if let Some(profiler) = &mut self.frame_profiler {
+      profiler.update(ctx);
+    }
+    if let (Some(overlay), Some(stats)) = (&mut self.overlay, &self.stats) {
+      overlay.update(ctx, stats);
+    }
+
+    let mut command_encoder = ctx
+      .device
+      .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+    let mut draw_time = std::time::Duration::ZERO;
+    let mut submitted = None;
+
+    match surface.get_current_texture() {
+      Ok(frame) => {
+        let view =
+          frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let recording = self.recorder.is_some();
+        let capture_tag = (!self.capture_requests.is_empty() || recording)
+          .then(|| CaptureTag {
+            screenshots: std::mem::take(&mut self.capture_requests),
+            record: recording,
+          });
+        let draw_view = match capture_tag {
+          Some(_) => self.capture.prepare(ctx, &frame.texture),
+          None => None,
+        };
+
+        let draw_start = Instant::now();
+        let scope = self
+          .frame_profiler
+          .as_mut()
+          .and_then(|p| p.begin(&mut command_encoder, "frame"));
+        let result = renderer.draw(
+          &mut command_encoder,
+          draw_view.unwrap_or(&view),
+          self.sample_count,
+        );
+        draw_time = draw_start.elapsed();
+
+        match result {
+          Ok(_) => {
+            let capturing = capture_tag.is_some();
+            if let Some(tag) = capture_tag {
+              self.capture.finish(
+                ctx,
+                &mut command_encoder,
+                &frame.texture,
+                &view,
+                tag,
+              );
+            }
+            if let Some(overlay) = &self.overlay {
+              overlay.draw(&mut command_encoder, &view);
+            }
+            // スクリーンショットと録画には含めない
+            #[cfg(feature = "egui")]
+            if let Some(egui) = &mut self.egui {
+              egui.draw(ctx, &mut command_encoder, &view);
+            }
+            if let Some(profiler) = &mut self.frame_profiler {
+              profiler.end(&mut command_encoder, scope);
+              profiler.end_frame(&mut command_encoder);
+            }
+
+            renderer.submit(&ctx.queue, command_encoder, Some(frame));
+            submitted = Some(Instant::now());
+            if capturing {
+              self.capture.after_submit();
+            }
+          }
+          Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+            renderer.resize(ctx, *ctx.size())
+          }
+          Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
+          Err(e) => eprintln!("{:?}", e),
+        }
+      }
+      Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+        renderer.resize(ctx, *ctx.size())
+      }
+      Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
+      Err(e) => eprintln!("{:?}", e),
+    }
+
+    let Some(stats) = &mut self.stats else {
+      return;
+    };
+    stats.record(dt, update_time, draw_time, frame_start.elapsed(), submitted);
+    // GPU 時間は数フレーム遅れて解決されるので、計測したフレームに書き込む
+    // (プロファイラの update とこの record はどちらも 1 フレームに 1 回呼ばれる)
+    if let Some(profiler) = &self.frame_profiler {
+      for time in profiler.resolved_frames() {
+        stats.record_gpu(time.frame, time.ms);
+      }
+    }
+
+    let title_outdated = self
+      .title_updated_at
+      .is_none_or(|t| t.elapsed() >= TITLE_UPDATE_INTERVAL);
+    if self.stats_in_title && title_outdated {
+      if let Some(window) = &self.window {
+        window.set_title(&format!(
+          "{} | {}",
+          self.window_title,
+          stats.summary()
+        ));
+      }
+      self.title_updated_at = Some(Instant::now());
+    }
   }
 }
 
@@ -115,7 +497,23 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
     let window = event_loop.create_window(window_attributes).unwrap();
     self.window = Some(Arc::new(window));
 
-    pollster::block_on(self.init(self.window.as_ref().unwrap().clone()));
+    let window = self.window.as_ref().unwrap().clone();
+    if let Err(e) = pollster::block_on(self.init(window)) {
+      eprintln!("Failed to configure the surface: {}", e);
+      event_loop.exit();
+      return;
+    }
+
+    // update_interval がなければモニタのリフレッシュレートを基準にする
+    if let Some(stats) = &mut self.stats {
+      let refresh_interval = self
+        .window
+        .as_ref()
+        .and_then(|window| window.current_monitor())
+        .and_then(|monitor| monitor.refresh_rate_millihertz())
+        .map(|mhz| std::time::Duration::from_secs_f64(1000.0 / mhz as f64));
+      stats.set_expected_interval(self.update_interval.or(refresh_interval));
+    }
 
     self.render_start_time = Some(std::time::Instant::now());
     self.need_redraw = true;
@@ -136,6 +534,18 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
       return;
     }
 
+    #[cfg(feature = "egui")]
+    if let (Some(egui), Some(window)) = (&mut self.egui, &self.window) {
+      let response = egui.on_window_event(window, &event);
+      if response.repaint {
+        self.need_redraw = true;
+        window.request_redraw();
+      }
+      if response.consumed {
+        return;
+      }
+    }
+
     let renderer = match &mut self.renderer {
       Some(renderer) => renderer,
       None => return,
@@ -144,69 +554,17 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
       return;
     }
 
-    let mut ctx = match &mut self.ctx {
+    let ctx = match &mut self.ctx {
       Some(ctx) => ctx,
       None => return,
     };
 
     match event {
       WindowEvent::Resized(size) => {
-        renderer.resize(&mut ctx, size.into());
+        renderer.resize(ctx, size.into());
       }
       WindowEvent::RedrawRequested => {
-        let ctx = match &mut self.ctx {
-          Some(ctx) => ctx,
-          None => {
-            eprintln!("Context is not initialized");
-            return;
-          }
-        };
-
-        let surface = match ctx.surface() {
-          Some(surface) => surface,
-          None => {
-            eprintln!("Surface is not initialized");
-            return;
-          }
-        };
-
-        let now = std::time::Instant::now();
-        let dt = now - self.render_start_time.unwrap_or(now);
-        renderer.update(ctx, dt);
-
-        let mut command_encoder =
-          ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: None,
-          });
-
-        let frame = surface.get_current_texture();
-
-        match frame {
-          Ok(frame) => {
-            let view = frame
-              .texture
-              .create_view(&wgpu::TextureViewDescriptor::default());
-
-            let result =
-              renderer.draw(&mut command_encoder, &view, self.sample_count);
-
-            match result {
-              Ok(_) => {
-                renderer.submit(&ctx.queue, command_encoder, Some(frame))
-              }
-              Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                renderer.resize(ctx, *ctx.size())
-              }
-              Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-              Err(e) => eprintln!("{:?}", e),
-            }
-          }
-          Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-            renderer.resize(ctx, *ctx.size())
-          }
-          Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-          Err(e) => eprintln!("{:?}", e),
-        }
+        self.redraw(event_loop);
       }
       WindowEvent::CloseRequested => {
         event_loop.exit();
@@ -222,10 +580,76 @@ impl<'a, R: Render<'a>> ApplicationHandler for App<'a, R> {
       } => {
         event_loop.exit();
       }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(key),
+            state: ElementState::Pressed,
+            repeat: false,
+            ..
+          },
+        ..
+      } if Some(key) == self.screenshot_key => {
+        let path = self.capture_path(".png");
+        self.capture_next_frame(path);
+        if let Some(window) = &self.window {
+          window.request_redraw();
+        }
+      }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(key),
+            state: ElementState::Pressed,
+            repeat: false,
+            ..
+          },
+        ..
+      } if Some(key) == self.record_key => {
+        if self.is_recording() {
+          self.stop_recording();
+        } else {
+          let path = match self.record_format {
+            RecordFormat::Gif => self.capture_path(".gif"),
+            RecordFormat::PngSequence => self.capture_path(""),
+          };
+          self.start_recording(path);
+        }
+      }
+      WindowEvent::KeyboardInput {
+        event:
+          KeyEvent {
+            physical_key: PhysicalKey::Code(key),
+            state: ElementState::Pressed,
+            repeat: false,
+            ..
+          },
+        ..
+      } if Some(key) == self.vsync_key => {
+        self.toggle_vsync();
+      }
       _ => {}
     }
   }
 
+  fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+    if let Some(ctx) = &self.ctx {
+      let frames = self.capture.flush(&ctx.device);
+      self.dispatch_captured_frames(frames);
+    }
+    // 書き出しが終わるまで待つ
+    if let Some(recorder) = self.recorder.take() {
+      recorder.finish_and_report();
+    }
+
+    if let (Some(path), Some(stats)) = (&self.stats_csv_path, &self.stats) {
+      match stats.write_csv(path) {
+        Ok(()) => println!("Frame stats have been saved to {}", path.display()),
+        Err(e) => eprintln!("{:?}", e),
+      }
+    }
+  }
+
   fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: StartCause) {
     if let StartCause::ResumeTimeReached { .. } = cause {
       self.need_redraw = true;
@@ -0,0 +1,856 @@
+use std::f32::consts::FRAC_PI_2;
+use std::time::Instant;
+
+use winit::event::{
+  ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent,
+};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::ctx::DrawingContext;
+use crate::layout::ShaderType;
+use crate::primitive::{Mat4, Point, Rect, Size, Vec2, Vec3, Vec4};
+use crate::util::{
+  BindGroupBuilder, BindGroupLayout, BindGroupLayoutBuilder, UniformBuffer,
+};
+
+// シェーダ側の struct Camera の宣言
+pub const CAMERA_WGSL: &str = include_str!("./shader/camera.wgsl");
+
+// 真上・真下を向くと look_at が定まらないので少し手前で止める
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+// ホイール 1 段あたりの拡大率
+const ZOOM_STEP: f32 = 1.1;
+
+// PixelDelta (トラックパッド) をホイールの段数に換算する
+const PIXELS_PER_LINE: f32 = 50.0;
+
+pub trait Camera {
+  // 入力を使った場合は true を返す (Render::process_event から呼ぶ)
+  fn process_event(&mut self, event: &WindowEvent) -> bool;
+
+  // ctx の大きさにアスペクト比を合わせ、押されているキーに応じて動かす
+  // Render::update から毎フレーム呼ぶ
+  fn update(&mut self, ctx: &DrawingContext);
+
+  fn viewport(&self) -> Size<f32>;
+  fn view(&self) -> Mat4;
+  fn projection(&self) -> Mat4;
+  fn position(&self) -> Vec3;
+
+  fn view_projection(&self) -> Mat4 {
+    self.projection() * self.view()
+  }
+
+  fn uniform(&self) -> CameraUniform {
+    let view = self.view();
+    let proj = self.projection();
+    let view_proj = proj * view;
+    let viewport = self.viewport();
+
+    CameraUniform {
+      view_proj,
+      view,
+      proj,
+      inv_view_proj: view_proj.inverse().unwrap_or(Mat4::IDENTITY),
+      position: self.position().extend(1.0),
+      viewport: Vec4::new(
+        viewport.width,
+        viewport.height,
+        1.0 / viewport.width.max(1.0),
+        1.0 / viewport.height.max(1.0),
+      ),
+    }
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+  pub view_proj: Mat4,
+  pub view: Mat4,
+  pub proj: Mat4,
+  pub inv_view_proj: Mat4,
+  pub position: Vec4,
+  // width, height, 1 / width, 1 / height (物理ピクセル)
+  pub viewport: Vec4,
+}
+
+impl ShaderType for CameraUniform {
+  const ALIGN: usize = 16;
+  const SIZE: usize = std::mem::size_of::<Self>();
+  const WGSL_TYPE: &'static str = "Camera";
+}
+
+// カメラの uniform バッファと、それを @binding(0) に置くバインドグループ
+pub struct CameraBinding {
+  uniform: UniformBuffer<CameraUniform>,
+  layout: BindGroupLayout,
+  bind_group: wgpu::BindGroup,
+}
+
+impl CameraBinding {
+  pub fn new(device: &wgpu::Device, camera: &impl Camera) -> Self {
+    let uniform = UniformBuffer::new(device, camera.uniform());
+
+    let layout = camera_bind_group_layout(device);
+
+    let bind_group = BindGroupBuilder::new(device, &layout)
+      .label("[wgsim] camera bind group")
+      .entry(0, uniform.as_binding_resource())
+      .build();
+
+    Self {
+      uniform,
+      layout,
+      bind_group,
+    }
+  }
+
+  // 変更があった場合のみ書き込む
+  pub fn update(&mut self, queue: &wgpu::Queue, camera: &impl Camera) -> bool {
+    self.uniform.set(camera.uniform());
+    self.uniform.flush(queue)
+  }
+
+  pub fn layout(&self) -> &wgpu::BindGroupLayout {
+    &self.layout
+  }
+
+  pub fn bind_group(&self) -> &wgpu::BindGroup {
+    &self.bind_group
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    self.uniform.buffer()
+  }
+}
+
+// CameraBinding のバインドグループをそのまま使えるパイプラインを作るときに使う
+pub(crate) fn camera_bind_group_layout(
+  device: &wgpu::Device,
+) -> BindGroupLayout {
+  BindGroupLayoutBuilder::new(device)
+    .label("[wgsim] camera bind group layout")
+    .entry(
+      0,
+      wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+      UniformBuffer::<CameraUniform>::binding_type(),
+    )
+    .build()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perspective {
+  pub fov_y: f32,
+  pub near: f32,
+  pub far: f32,
+}
+
+impl Default for Perspective {
+  fn default() -> Self {
+    Self {
+      fov_y: 45f32.to_radians(),
+      near: 0.1,
+      far: 1000.0,
+    }
+  }
+}
+
+impl Perspective {
+  pub fn matrix(&self, viewport: Size<f32>) -> Mat4 {
+    let aspect = if viewport.height > 0.0 {
+      viewport.aspect_ratio()
+    } else {
+      1.0
+    };
+    Mat4::perspective_rh(self.fov_y, aspect, self.near, self.far)
+  }
+}
+
+// yaw 0, pitch 0 で -Z を向く
+fn direction(yaw: f32, pitch: f32) -> Vec3 {
+  let (sy, cy) = yaw.sin_cos();
+  let (sp, cp) = pitch.sin_cos();
+  Vec3::new(cp * sy, sp, -cp * cy)
+}
+
+fn key_state(event: &WindowEvent) -> Option<(KeyCode, bool)> {
+  match event {
+    WindowEvent::KeyboardInput {
+      event:
+        KeyEvent {
+          physical_key: PhysicalKey::Code(code),
+          state,
+          ..
+        },
+      ..
+    } => Some((*code, *state == ElementState::Pressed)),
+    _ => None,
+  }
+}
+
+fn scroll_lines(delta: &MouseScrollDelta) -> f32 {
+  match delta {
+    MouseScrollDelta::LineDelta(_, y) => *y,
+    MouseScrollDelta::PixelDelta(p) => p.y as f32 / PIXELS_PER_LINE,
+  }
+}
+
+// マウスの位置とボタンの状態
+#[derive(Debug, Clone, Copy, Default)]
+struct Pointer {
+  position: Option<Point>,
+  left: bool,
+  right: bool,
+  middle: bool,
+}
+
+impl Pointer {
+  // 押されたボタンが変わった場合は true
+  fn button(&mut self, button: MouseButton, state: ElementState) -> bool {
+    let pressed = state == ElementState::Pressed;
+    let slot = match button {
+      MouseButton::Left => &mut self.left,
+      MouseButton::Right => &mut self.right,
+      MouseButton::Middle => &mut self.middle,
+      _ => return false,
+    };
+    *slot = pressed;
+    true
+  }
+
+  // 前回の位置からの移動量 (最初の 1 回は 0)
+  fn moved(&mut self, position: Point) -> Vec2 {
+    let delta = self
+      .position
+      .map(|last| Vec2::new(position.x - last.x, position.y - last.y))
+      .unwrap_or(Vec2::ZERO);
+    self.position = Some(position);
+    delta
+  }
+
+  fn any_pressed(&self) -> bool {
+    self.left || self.right || self.middle
+  }
+}
+
+// 2D のパン・ズーム (ワールド座標は y 上向き)
+// 左ドラッグでパン、ホイールでカーソル位置を中心に拡大縮小、矢印キーでパン、
+// R で初期状態に戻す
+#[derive(Debug, Clone)]
+pub struct Camera2D {
+  pub center: Vec2,
+  // ワールド座標 1 あたりの物理ピクセル数
+  pub zoom: f32,
+  pub min_zoom: f32,
+  pub max_zoom: f32,
+  // 矢印キーで 1 秒間に動く画面幅の割合
+  pub key_pan_speed: f32,
+  viewport: Size<f32>,
+  home: (Vec2, f32),
+  pointer: Pointer,
+  keys: MoveKeys,
+  last_update: Option<Instant>,
+}
+
+impl Camera2D {
+  pub fn new(center: Vec2, zoom: f32) -> Self {
+    Self {
+      center,
+      zoom,
+      min_zoom: 1e-4,
+      max_zoom: 1e4,
+      key_pan_speed: 0.5,
+      viewport: Size::new(1.0, 1.0),
+      home: (center, zoom),
+      pointer: Pointer::default(),
+      keys: MoveKeys::default(),
+      last_update: None,
+    }
+  }
+
+  // rect 全体が viewport に収まるように合わせる
+  pub fn fit(rect: Rect, viewport: Size) -> Self {
+    let viewport = viewport.to_f32();
+    let zoom = (viewport.width / rect.size.width)
+      .min(viewport.height / rect.size.height);
+    let center = rect.center();
+    let mut camera = Self::new(Vec2::new(center.x, center.y), zoom);
+    camera.viewport = viewport;
+    camera
+  }
+
+  pub fn zoom_limits(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+    self.min_zoom = min_zoom;
+    self.max_zoom = max_zoom;
+    self.zoom = self.zoom.clamp(min_zoom, max_zoom);
+    self
+  }
+
+  pub fn set_viewport(&mut self, viewport: Size<f32>) {
+    self.viewport = viewport;
+  }
+
+  pub fn reset(&mut self) {
+    (self.center, self.zoom) = self.home;
+  }
+
+  // 画面上の物理ピクセル (左上原点) をワールド座標に変換する
+  pub fn screen_to_world(&self, screen: Point) -> Vec2 {
+    Vec2::new(
+      self.center.x + (screen.x - self.viewport.width * 0.5) / self.zoom,
+      self.center.y - (screen.y - self.viewport.height * 0.5) / self.zoom,
+    )
+  }
+
+  pub fn world_to_screen(&self, world: Vec2) -> Point {
+    Point::new(
+      (world.x - self.center.x) * self.zoom + self.viewport.width * 0.5,
+      self.viewport.height * 0.5 - (world.y - self.center.y) * self.zoom,
+    )
+  }
+
+  // screen の位置にあるワールド座標を動かさずに拡大縮小する
+  pub fn zoom_at(&mut self, screen: Point, factor: f32) {
+    let before = self.screen_to_world(screen);
+    self.zoom = (self.zoom * factor).clamp(self.min_zoom, self.max_zoom);
+    let after = self.screen_to_world(screen);
+    self.center += before - after;
+  }
+
+  pub fn pan_pixels(&mut self, delta: Vec2) {
+    self.center -= Vec2::new(delta.x, -delta.y) / self.zoom;
+  }
+}
+
+impl Camera for Camera2D {
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::MouseInput { button, state, .. } => {
+        self.pointer.button(*button, *state)
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let delta = self.pointer.moved(Point::from(position.cast::<f32>()));
+        if self.pointer.any_pressed() {
+          self.pan_pixels(delta);
+        }
+        true
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.pointer = Pointer::default();
+        false
+      }
+      WindowEvent::MouseWheel { delta, .. } => {
+        let center =
+          Point::new(self.viewport.width * 0.5, self.viewport.height * 0.5);
+        let at = self.pointer.position.unwrap_or(center);
+        self.zoom_at(at, ZOOM_STEP.powf(scroll_lines(delta)));
+        true
+      }
+      _ => match key_state(event) {
+        Some((KeyCode::KeyR, true)) => {
+          self.reset();
+          true
+        }
+        Some((code, pressed)) => self.keys.arrows(code, pressed),
+        None => false,
+      },
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext) {
+    self.viewport = ctx.size().to_f32();
+
+    let dt = elapsed_since(&mut self.last_update);
+    let axis = self.keys.axis();
+    if axis != Vec3::ZERO {
+      let speed = self.key_pan_speed * self.viewport.width * dt;
+      self.pan_pixels(Vec2::new(-axis.x, axis.z) * speed);
+    }
+  }
+
+  fn viewport(&self) -> Size<f32> {
+    self.viewport
+  }
+
+  // ワールド座標を中心からの物理ピクセルに変換する
+  fn view(&self) -> Mat4 {
+    Mat4::scale(Vec3::new(self.zoom, self.zoom, 1.0))
+      * Mat4::translation(-self.center.extend(0.0))
+  }
+
+  fn projection(&self) -> Mat4 {
+    let (w, h) = (self.viewport.width * 0.5, self.viewport.height * 0.5);
+    Mat4::orthographic_rh(-w, w, -h, h, -1.0, 1.0)
+  }
+
+  fn position(&self) -> Vec3 {
+    self.center.extend(0.0)
+  }
+}
+
+// target の周りを回る
+// 左ドラッグで回転、右 (または中) ドラッグでパン、ホイールで距離を変える、
+// R で初期状態に戻す
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+  pub target: Vec3,
+  pub distance: f32,
+  pub yaw: f32,
+  pub pitch: f32,
+  pub min_distance: f32,
+  pub max_distance: f32,
+  // 1 物理ピクセルあたりの回転 (ラジアン)
+  pub sensitivity: f32,
+  pub perspective: Perspective,
+  viewport: Size<f32>,
+  home: (Vec3, f32, f32, f32),
+  pointer: Pointer,
+}
+
+impl OrbitCamera {
+  pub fn new(target: Vec3, distance: f32) -> Self {
+    Self {
+      target,
+      distance,
+      yaw: 0.0,
+      pitch: 0.0,
+      min_distance: 1e-3,
+      max_distance: f32::MAX,
+      sensitivity: 0.005,
+      perspective: Perspective::default(),
+      viewport: Size::new(1.0, 1.0),
+      home: (target, distance, 0.0, 0.0),
+      pointer: Pointer::default(),
+    }
+  }
+
+  // 初期の向き (R で戻る先も変わる)
+  pub fn angles(mut self, yaw: f32, pitch: f32) -> Self {
+    self.yaw = yaw;
+    self.pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+    self.home = (self.target, self.distance, self.yaw, self.pitch);
+    self
+  }
+
+  pub fn distance_limits(
+    mut self,
+    min_distance: f32,
+    max_distance: f32,
+  ) -> Self {
+    self.min_distance = min_distance;
+    self.max_distance = max_distance;
+    self.distance = self.distance.clamp(min_distance, max_distance);
+    self
+  }
+
+  pub fn perspective(mut self, perspective: Perspective) -> Self {
+    self.perspective = perspective;
+    self
+  }
+
+  pub fn set_viewport(&mut self, viewport: Size<f32>) {
+    self.viewport = viewport;
+  }
+
+  pub fn reset(&mut self) {
+    (self.target, self.distance, self.yaw, self.pitch) = self.home;
+  }
+
+  pub fn rotate(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    self.yaw += delta_yaw;
+    self.pitch = (self.pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  // pitch は仰角なので、正のときは上から見下ろす
+  pub fn forward(&self) -> Vec3 {
+    direction(self.yaw, -self.pitch)
+  }
+
+  // 画面上の移動量と target の移動量が一致するように動かす
+  pub fn pan_pixels(&mut self, delta: Vec2) {
+    let forward = self.forward();
+    let right = forward.cross(Vec3::Y).normalize();
+    let up = right.cross(forward);
+    let world_per_pixel =
+      2.0 * self.distance * (self.perspective.fov_y * 0.5).tan()
+        / self.viewport.height.max(1.0);
+    self.target += (up * delta.y - right * delta.x) * world_per_pixel;
+  }
+
+  pub fn zoom(&mut self, factor: f32) {
+    self.distance =
+      (self.distance / factor).clamp(self.min_distance, self.max_distance);
+  }
+}
+
+impl Camera for OrbitCamera {
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::MouseInput { button, state, .. } => {
+        self.pointer.button(*button, *state)
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let delta = self.pointer.moved(Point::from(position.cast::<f32>()));
+        if self.pointer.left {
+          self.rotate(delta.x * self.sensitivity, delta.y * self.sensitivity);
+        } else if self.pointer.right || self.pointer.middle {
+          self.pan_pixels(delta);
+        }
+        true
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.pointer = Pointer::default();
+        false
+      }
+      WindowEvent::MouseWheel { delta, .. } => {
+        self.zoom(ZOOM_STEP.powf(scroll_lines(delta)));
+        true
+      }
+      _ => match key_state(event) {
+        Some((KeyCode::KeyR, true)) => {
+          self.reset();
+          true
+        }
+        _ => false,
+      },
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext) {
+    self.viewport = ctx.size().to_f32();
+  }
+
+  fn viewport(&self) -> Size<f32> {
+    self.viewport
+  }
+
+  fn view(&self) -> Mat4 {
+    Mat4::look_at_rh(self.position(), self.target, Vec3::Y)
+  }
+
+  fn projection(&self) -> Mat4 {
+    self.perspective.matrix(self.viewport)
+  }
+
+  fn position(&self) -> Vec3 {
+    self.target - self.forward() * self.distance
+  }
+}
+
+// 一人称視点で飛び回る
+// WASD で前後左右、E / Q で上下、Shift で加速、右ドラッグで向きを変える
+#[derive(Debug, Clone)]
+pub struct FlyCamera {
+  pub position: Vec3,
+  pub yaw: f32,
+  pub pitch: f32,
+  // 1 秒あたりの移動量
+  pub speed: f32,
+  pub boost: f32,
+  // 1 物理ピクセルあたりの回転 (ラジアン)
+  pub sensitivity: f32,
+  pub perspective: Perspective,
+  viewport: Size<f32>,
+  pointer: Pointer,
+  keys: MoveKeys,
+  last_update: Option<Instant>,
+}
+
+impl FlyCamera {
+  pub fn new(position: Vec3, yaw: f32, pitch: f32) -> Self {
+    Self {
+      position,
+      yaw,
+      pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+      speed: 2.0,
+      boost: 4.0,
+      sensitivity: 0.003,
+      perspective: Perspective::default(),
+      viewport: Size::new(1.0, 1.0),
+      pointer: Pointer::default(),
+      keys: MoveKeys::default(),
+      last_update: None,
+    }
+  }
+
+  // position から target を向く
+  pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+    let d = (target - position).normalize();
+    Self::new(position, d.x.atan2(-d.z), d.y.clamp(-1.0, 1.0).asin())
+  }
+
+  pub fn speed(mut self, speed: f32) -> Self {
+    self.speed = speed;
+    self
+  }
+
+  pub fn perspective(mut self, perspective: Perspective) -> Self {
+    self.perspective = perspective;
+    self
+  }
+
+  pub fn set_viewport(&mut self, viewport: Size<f32>) {
+    self.viewport = viewport;
+  }
+
+  pub fn forward(&self) -> Vec3 {
+    direction(self.yaw, self.pitch)
+  }
+
+  pub fn rotate(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    self.yaw += delta_yaw;
+    self.pitch = (self.pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+}
+
+impl Camera for FlyCamera {
+  fn process_event(&mut self, event: &WindowEvent) -> bool {
+    match event {
+      WindowEvent::MouseInput { button, state, .. } => {
+        self.pointer.button(*button, *state)
+      }
+      WindowEvent::CursorMoved { position, .. } => {
+        let delta = self.pointer.moved(Point::from(position.cast::<f32>()));
+        if self.pointer.right {
+          self.rotate(delta.x * self.sensitivity, -delta.y * self.sensitivity);
+        }
+        true
+      }
+      WindowEvent::CursorLeft { .. } => {
+        self.pointer = Pointer::default();
+        false
+      }
+      WindowEvent::Focused(false) => {
+        // フォーカスを失うと離したキーが届かないので止める
+        self.keys = MoveKeys::default();
+        false
+      }
+      _ => match key_state(event) {
+        Some((code, pressed)) => self.keys.wasd(code, pressed),
+        None => false,
+      },
+    }
+  }
+
+  fn update(&mut self, ctx: &DrawingContext) {
+    self.viewport = ctx.size().to_f32();
+
+    let dt = elapsed_since(&mut self.last_update);
+    let axis = self.keys.axis();
+    if axis == Vec3::ZERO {
+      return;
+    }
+
+    let forward = self.forward();
+    let right = forward.cross(Vec3::Y).normalize();
+    let speed = if self.keys.boost {
+      self.speed * self.boost
+    } else {
+      self.speed
+    };
+    let motion = right * axis.x + Vec3::Y * axis.y + forward * axis.z;
+    self.position += motion.normalize() * speed * dt;
+  }
+
+  fn viewport(&self) -> Size<f32> {
+    self.viewport
+  }
+
+  fn view(&self) -> Mat4 {
+    Mat4::look_at_rh(self.position, self.position + self.forward(), Vec3::Y)
+  }
+
+  fn projection(&self) -> Mat4 {
+    self.perspective.matrix(self.viewport)
+  }
+
+  fn position(&self) -> Vec3 {
+    self.position
+  }
+}
+
+// 押されている移動キー
+#[derive(Debug, Clone, Copy, Default)]
+struct MoveKeys {
+  forward: bool,
+  back: bool,
+  left: bool,
+  right: bool,
+  up: bool,
+  down: bool,
+  boost: bool,
+}
+
+impl MoveKeys {
+  fn wasd(&mut self, code: KeyCode, pressed: bool) -> bool {
+    let slot = match code {
+      KeyCode::KeyW => &mut self.forward,
+      KeyCode::KeyS => &mut self.back,
+      KeyCode::KeyA => &mut self.left,
+      KeyCode::KeyD => &mut self.right,
+      KeyCode::KeyE => &mut self.up,
+      KeyCode::KeyQ => &mut self.down,
+      KeyCode::ShiftLeft | KeyCode::ShiftRight => &mut self.boost,
+      _ => return false,
+    };
+    *slot = pressed;
+    true
+  }
+
+  fn arrows(&mut self, code: KeyCode, pressed: bool) -> bool {
+    let slot = match code {
+      KeyCode::ArrowUp => &mut self.forward,
+      KeyCode::ArrowDown => &mut self.back,
+      KeyCode::ArrowLeft => &mut self.left,
+      KeyCode::ArrowRight => &mut self.right,
+      _ => return false,
+    };
+    *slot = pressed;
+    true
+  }
+
+  // x: 右、y: 上、z: 前
+  fn axis(&self) -> Vec3 {
+    let axis = |positive: bool, negative: bool| {
+      f32::from(u8::from(positive)) - f32::from(u8::from(negative))
+    };
+    Vec3::new(
+      axis(self.right, self.left),
+      axis(self.up, self.down),
+      axis(self.forward, self.back),
+    )
+  }
+}
+
+// 前回呼ばれてからの秒数 (初回は 0)
+fn elapsed_since(last: &mut Option<Instant>) -> f32 {
+  let now = Instant::now();
+  let dt = last.map_or(0.0, |last| (now - last).as_secs_f32());
+  *last = Some(now);
+  // 止まっていた後に大きく飛ばないようにする
+  dt.min(0.1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[track_caller]
+  fn assert_vec2_near(a: Vec2, b: Vec2, eps: f32) {
+    assert!((a - b).length() <= eps, "{:?} != {:?}", a, b);
+  }
+
+  #[track_caller]
+  fn assert_vec3_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() <= 1e-4, "{:?} != {:?}", a, b);
+  }
+
+  fn camera_2d() -> Camera2D {
+    let mut camera = Camera2D::new(Vec2::new(3.0, -2.0), 40.0);
+    camera.set_viewport(Size::new(800.0, 600.0));
+    camera
+  }
+
+  #[test]
+  fn camera_2d_screen_world_round_trip() {
+    let camera = camera_2d();
+
+    // 画面の中心は center、y は上下が逆になる
+    assert_eq!(
+      camera.screen_to_world(Point::new(400.0, 300.0)),
+      Vec2::new(3.0, -2.0)
+    );
+    assert_eq!(
+      camera.screen_to_world(Point::new(440.0, 260.0)),
+      Vec2::new(4.0, -1.0)
+    );
+
+    for (x, y) in [(0.0, 0.0), (800.0, 600.0), (123.5, 456.25)] {
+      let screen = Point::new(x, y);
+      let back = camera.world_to_screen(camera.screen_to_world(screen));
+      assert_vec2_near(Vec2::new(back.x, back.y), Vec2::new(x, y), 1e-3);
+    }
+
+    // view_projection と同じ位置に写る
+    let world = Vec2::new(5.5, 1.25);
+    let screen = camera.world_to_screen(world);
+    let ndc = camera.view_projection().transform_point(world.extend(0.0));
+    assert_vec2_near(
+      Vec2::new(screen.x / 800.0 * 2.0 - 1.0, 1.0 - screen.y / 600.0 * 2.0),
+      ndc.truncate(),
+      1e-5,
+    );
+  }
+
+  #[test]
+  fn zoom_at_keeps_the_cursor_point_fixed() {
+    let mut camera = camera_2d();
+    let cursor = Point::new(620.0, 95.0);
+    let before = camera.screen_to_world(cursor);
+
+    camera.zoom_at(cursor, 2.5);
+    assert_eq!(camera.zoom, 100.0);
+    assert_vec2_near(camera.screen_to_world(cursor), before, 1e-4);
+
+    camera.zoom_at(cursor, 0.1);
+    assert_vec2_near(camera.screen_to_world(cursor), before, 1e-4);
+
+    // 上限で止まっても動かない
+    let mut camera = camera_2d().zoom_limits(1.0, 50.0);
+    camera.zoom_at(cursor, 10.0);
+    assert_eq!(camera.zoom, 50.0);
+    assert_vec2_near(camera.screen_to_world(cursor), before, 1e-4);
+  }
+
+  #[test]
+  fn fly_camera_looks_at_the_target() {
+    let position = Vec3::new(1.0, 2.0, 3.0);
+    let targets = [
+      Vec3::new(1.0, 2.0, -5.0),
+      Vec3::new(4.0, 0.0, 3.0),
+      Vec3::new(-2.0, 5.0, 7.0),
+      Vec3::new(0.0, -1.0, 0.0),
+    ];
+    for target in targets {
+      let camera = FlyCamera::looking_at(position, target);
+      let expected = (target - position).normalize();
+      assert_vec3_near(camera.forward(), expected);
+
+      // view は target を -Z 軸上に置く
+      let p = camera.view().transform_point(target);
+      assert_vec3_near(p, Vec3::new(0.0, 0.0, -(target - position).length()));
+    }
+
+    // 既定の向き (yaw = pitch = 0) は -Z
+    let camera = FlyCamera::new(position, 0.0, 0.0);
+    assert_vec3_near(camera.forward(), -Vec3::Z);
+  }
+
+  #[test]
+  fn orbit_camera_stays_at_distance() {
+    let target = Vec3::new(1.0, -2.0, 0.5);
+    let mut camera = OrbitCamera::new(target, 4.0).angles(0.8, 0.4);
+    for _ in 0..8 {
+      let position = camera.position();
+      assert!(((position - target).length() - 4.0).abs() < 1e-4);
+      assert_vec3_near(target - position, camera.forward() * 4.0);
+      // 正の pitch では上から見下ろす
+      assert!(position.y > target.y);
+      camera.rotate(1.1, 0.3);
+    }
+
+    // pitch は真上の手前で止まり、distance は範囲に収まる
+    assert!(camera.pitch < FRAC_PI_2);
+    let mut camera = camera.distance_limits(1.0, 3.0);
+    assert_eq!(camera.distance, 3.0);
+    camera.zoom(0.1);
+    assert_eq!(camera.distance, 3.0);
+    assert!(((camera.position() - target).length() - 3.0).abs() < 1e-4);
+
+    // reset で angles の向きに戻る
+    camera.reset();
+    assert_eq!((camera.yaw, camera.pitch), (0.8, 0.4));
+  }
+}
@@ -0,0 +1,512 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::ctx::DrawingContext;
+use crate::primitive::{linear_to_srgb, srgb_to_linear, Size};
+
+const MAP_PENDING: u8 = 0;
+const MAP_OK: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+// 使い回すために取っておく読み戻し用バッファの数
+const MAX_FREE_BUFFERS: usize = 3;
+
+// 読み戻したフレーム (行のパディングは取り除いてある)
+#[derive(Clone)]
+pub struct CapturedFrame {
+  pub size: Size,
+  pub format: wgpu::TextureFormat,
+  pub data: Vec<u8>,
+  pub presented_at: Instant,
+}
+
+impl CapturedFrame {
+  // 対応していないフォーマットの場合は None
+  pub fn to_rgba8(&self) -> Option<image::RgbaImage> {
+    let (values, linear) = self.decode()?;
+    let rgba = values
+      .chunks_exact(4)
+      .flat_map(|p| {
+        let encode = |v: f32| if linear { linear_to_srgb(v) } else { v };
+        [encode(p[0]), encode(p[1]), encode(p[2]), p[3]].map(unorm8)
+      })
+      .collect();
+
+    image::RgbaImage::from_raw(self.size.width, self.size.height, rgba)
+  }
+
+  pub fn save_png(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let image = self
+      .to_rgba8()
+      .ok_or_else(|| format!("{:?} cannot be saved as PNG", self.format))?;
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(())
+  }
+
+  // 浮動小数点のフォーマットは sRGB に変換し、1.0 を超える値は切り捨てる
+  pub fn to_rgba16(
+    &self,
+  ) -> Option<image::ImageBuffer<image::Rgba<u16>, Vec<u16>>> {
+    let (values, linear) = self.decode()?;
+    let rgba = values
+      .chunks_exact(4)
+      .flat_map(|p| {
+        let encode = |v: f32| if linear { linear_to_srgb(v) } else { v };
+        [encode(p[0]), encode(p[1]), encode(p[2]), p[3]].map(unorm16)
+      })
+      .collect();
+
+    image::ImageBuffer::from_raw(self.size.width, self.size.height, rgba)
+  }
+
+  // 線形の値 (浮動小数点のフォーマットは 1.0 を超える値も残る)
+  pub fn to_rgba32f(&self) -> Option<image::Rgba32FImage> {
+    let (values, linear) = self.decode()?;
+    let rgba = if linear {
+      values
+    } else {
+      values
+        .chunks_exact(4)
+        .flat_map(|p| {
+          [
+            srgb_to_linear(p[0]),
+            srgb_to_linear(p[1]),
+            srgb_to_linear(p[2]),
+            p[3],
+          ]
+        })
+        .collect()
+    };
+
+    image::Rgba32FImage::from_raw(self.size.width, self.size.height, rgba)
+  }
+
+  pub fn save_png16(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let image = self
+      .to_rgba16()
+      .ok_or_else(|| format!("{:?} cannot be saved as PNG", self.format))?;
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(())
+  }
+
+  pub fn save_exr(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let image = self
+      .to_rgba32f()
+      .ok_or_else(|| format!("{:?} cannot be saved as EXR", self.format))?;
+    image.save_with_format(path, image::ImageFormat::OpenExr)?;
+    Ok(())
+  }
+
+  // 拡張子で書き出し方を選ぶ (浮動小数点のフォーマットの PNG は 16bit になる)
+  pub fn save(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let path = path.as_ref();
+    let extension =
+      path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase());
+
+    match extension.as_deref() {
+      Some("exr") => self.save_exr(path),
+      Some("png") if is_float(self.format) => self.save_png16(path),
+      Some("png") => self.save_png(path),
+      _ => {
+        Err(format!("{} has an unsupported extension", path.display()).into())
+      }
+    }
+  }
+
+  // 各チャンネルを 0..1 の f32 にしたものと、それが線形の値かどうか
+  fn decode(&self) -> Option<(Vec<f32>, bool)> {
+    use wgpu::TextureFormat::*;
+
+    let channel8 = |v: u8| v as f32 / 255.0;
+    let values = match self.format {
+      Rgba8Unorm | Rgba8UnormSrgb => {
+        (self.data.iter().map(|&v| channel8(v)).collect(), false)
+      }
+      Bgra8Unorm | Bgra8UnormSrgb => (
+        self
+          .data
+          .chunks_exact(4)
+          .flat_map(|p| [p[2], p[1], p[0], p[3]].map(channel8))
+          .collect(),
+        false,
+      ),
+      Rgb10a2Unorm => (
+        self
+          .data
+          .chunks_exact(4)
+          .map(|p| u32::from_le_bytes([p[0], p[1], p[2], p[3]]))
+          .flat_map(|p| {
+            let channel = |shift: u32| (p >> shift & 0x3ff) as f32 / 1023.0;
+            [channel(0), channel(10), channel(20), (p >> 30) as f32 / 3.0]
+          })
+          .collect(),
+        false,
+      ),
+      Rgba16Float => (
+        self
+          .data
+          .chunks_exact(2)
+          .map(|p| half::f16::from_le_bytes([p[0], p[1]]).to_f32())
+          .collect(),
+        true,
+      ),
+      Rgba32Float => (
+        self
+          .data
+          .chunks_exact(4)
+          .map(|p| f32::from_le_bytes([p[0], p[1], p[2], p[3]]))
+          .collect(),
+        true,
+      ),
+      _ => return None,
+    };
+
+    Some(values)
+  }
+}
+
+fn is_float(format: wgpu::TextureFormat) -> bool {
+  matches!(
+    format,
+    wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgba32Float
+  )
+}
+
+fn unorm8(v: f32) -> u8 {
+  (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
+
+fn unorm16(v: f32) -> u16 {
+  (v.clamp(0.0, 1.0) * 65535.0 + 0.5) as u16
+}
+
+struct Offscreen {
+  texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+struct PendingReadback<T> {
+  tag: T,
+  buffer: wgpu::Buffer,
+  size: Size,
+  format: wgpu::TextureFormat,
+  padded_bytes_per_row: u32,
+  presented_at: Instant,
+  state: Arc<AtomicU8>,
+  map_requested: bool,
+}
+
+// 描画したフレームを止めずに読み戻す
+// サーフェスが COPY_SRC に対応していなければ、オフスクリーンに描いてからサーフェスへ写す
+pub struct FrameCapture<T> {
+  offscreen: Option<Offscreen>,
+  blit: Option<(wgpu::TextureFormat, wgpu::RenderPipeline, wgpu::Sampler)>,
+  pending: Vec<PendingReadback<T>>,
+  free_buffers: Vec<wgpu::Buffer>,
+}
+
+impl<T> Default for FrameCapture<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<T> FrameCapture<T> {
+  pub fn new() -> Self {
+    Self {
+      offscreen: None,
+      blit: None,
+      pending: Vec::new(),
+      free_buffers: Vec::new(),
+    }
+  }
+
+  pub fn pending_count(&self) -> usize {
+    self.pending.len()
+  }
+
+  // Render::draw の描画先 (None ならサーフェスにそのまま描いてよい)
+  pub fn prepare(
+    &mut self,
+    ctx: &DrawingContext,
+    surface_texture: &wgpu::Texture,
+  ) -> Option<&wgpu::TextureView> {
+    if surface_texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+      return None;
+    }
+
+    let size = surface_texture.size();
+    let outdated = self.offscreen.as_ref().is_none_or(|o| {
+      o.texture.size() != size || o.texture.format() != surface_texture.format()
+    });
+    if outdated {
+      let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("[wgsim] capture texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: surface_texture.format(),
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+          | wgpu::TextureUsages::TEXTURE_BINDING
+          | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+      });
+      let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+      self.offscreen = Some(Offscreen { texture, view });
+    }
+
+    self.offscreen.as_ref().map(|o| &o.view)
+  }
+
+  // 描画の後、submit の前に呼ぶ
+  pub fn finish(
+    &mut self,
+    ctx: &DrawingContext,
+    encoder: &mut wgpu::CommandEncoder,
+    surface_texture: &wgpu::Texture,
+    surface_view: &wgpu::TextureView,
+    tag: T,
+  ) {
+    let use_offscreen =
+      !surface_texture.usage().contains(wgpu::TextureUsages::COPY_SRC);
+    let source = match (&self.offscreen, use_offscreen) {
+      (Some(offscreen), true) => &offscreen.texture,
+      (None, true) => panic!("[wgsim] FrameCapture::prepare was not called"),
+      (_, false) => surface_texture,
+    };
+
+    let extent = source.size();
+    let format = source.format();
+    let block_size = format
+      .block_copy_size(None)
+      .expect("[wgsim] the frame format cannot be copied");
+    let unpadded_bytes_per_row = extent.width * block_size;
+    let padded_bytes_per_row = unpadded_bytes_per_row
+      .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer_size = (padded_bytes_per_row * extent.height) as u64;
+
+    // 大きさが変わったら古いバッファは使わないので捨てる
+    self.free_buffers.retain(|b| b.size() == buffer_size);
+    let buffer = match self.free_buffers.pop() {
+      Some(buffer) => buffer,
+      None => ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("[wgsim] capture buffer"),
+        size: buffer_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+      }),
+    };
+
+    encoder.copy_texture_to_buffer(
+      wgpu::ImageCopyTexture {
+        texture: source,
+        mip_level: 0,
+        origin: wgpu::Origin3d::ZERO,
+        aspect: wgpu::TextureAspect::All,
+      },
+      wgpu::ImageCopyBuffer {
+        buffer: &buffer,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(padded_bytes_per_row),
+          rows_per_image: Some(extent.height),
+        },
+      },
+      extent,
+    );
+
+    if use_offscreen {
+      self.blit_to_surface(ctx, encoder, surface_view, format);
+    }
+
+    self.pending.push(PendingReadback {
+      tag,
+      buffer,
+      size: Size::new(extent.width, extent.height),
+      format,
+      padded_bytes_per_row,
+      presented_at: Instant::now(),
+      state: Arc::new(AtomicU8::new(MAP_PENDING)),
+      map_requested: false,
+    });
+  }
+
+  // submit の後に呼ぶ
+  pub fn after_submit(&mut self) {
+    for pending in &mut self.pending {
+      if pending.map_requested {
+        continue;
+      }
+
+      let state = pending.state.clone();
+      pending.buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        let value = if result.is_ok() { MAP_OK } else { MAP_FAILED };
+        state.store(value, Ordering::Release);
+      });
+      pending.map_requested = true;
+      pending.presented_at = Instant::now();
+    }
+  }
+
+  // 読み戻しが終わったフレームを返す (待たない)
+  pub fn poll(&mut self, device: &wgpu::Device) -> Vec<(T, CapturedFrame)> {
+    self.poll_with(device, wgpu::Maintain::Poll)
+  }
+
+  // 終了時などに、残りの読み戻しをすべて待つ
+  pub fn flush(&mut self, device: &wgpu::Device) -> Vec<(T, CapturedFrame)> {
+    self.poll_with(device, wgpu::Maintain::Wait)
+  }
+
+  fn poll_with(
+    &mut self,
+    device: &wgpu::Device,
+    maintain: wgpu::Maintain,
+  ) -> Vec<(T, CapturedFrame)> {
+    if self.pending.is_empty() {
+      return Vec::new();
+    }
+    device.poll(maintain);
+
+    // 録画のためにフレームの順序を保つ
+    let mut frames = Vec::new();
+    while let Some(pending) = self.pending.first() {
+      let state = pending.state.load(Ordering::Acquire);
+      if state == MAP_PENDING {
+        break;
+      }
+
+      let pending = self.pending.remove(0);
+      if state == MAP_OK {
+        let unpadded_bytes_per_row = pending.size.width
+          * pending.format.block_copy_size(None).unwrap_or(4);
+        let mapped = pending.buffer.slice(..).get_mapped_range();
+        let data = mapped
+          .chunks(pending.padded_bytes_per_row as usize)
+          .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+          .copied()
+          .collect();
+        drop(mapped);
+        pending.buffer.unmap();
+
+        frames.push((
+          pending.tag,
+          CapturedFrame {
+            size: pending.size,
+            format: pending.format,
+            data,
+            presented_at: pending.presented_at,
+          },
+        ));
+        if self.free_buffers.len() < MAX_FREE_BUFFERS {
+          self.free_buffers.push(pending.buffer);
+        }
+      } else {
+        eprintln!("[wgsim] failed to read back a captured frame");
+      }
+    }
+
+    frames
+  }
+
+  fn blit_to_surface(
+    &mut self,
+    ctx: &DrawingContext,
+    encoder: &mut wgpu::CommandEncoder,
+    surface_view: &wgpu::TextureView,
+    format: wgpu::TextureFormat,
+  ) {
+    if self.blit.as_ref().is_none_or(|(f, _, _)| *f != format) {
+      let (pipeline, sampler) = create_blit_pipeline(&ctx.device, format);
+      self.blit = Some((format, pipeline, sampler));
+    }
+    let (_, pipeline, sampler) = self.blit.as_ref().unwrap();
+    let offscreen = self.offscreen.as_ref().unwrap();
+
+    let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some("[wgsim] capture blit bind group"),
+      layout: &pipeline.get_bind_group_layout(0),
+      entries: &[
+        wgpu::BindGroupEntry {
+          binding: 0,
+          resource: wgpu::BindingResource::TextureView(&offscreen.view),
+        },
+        wgpu::BindGroupEntry {
+          binding: 1,
+          resource: wgpu::BindingResource::Sampler(sampler),
+        },
+      ],
+    });
+
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("[wgsim] capture blit pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view: surface_view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      ..Default::default()
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, &bind_group, &[]);
+    pass.draw(0..3, 0..1);
+  }
+}
+
+fn create_blit_pipeline(
+  device: &wgpu::Device,
+  format: wgpu::TextureFormat,
+) -> (wgpu::RenderPipeline, wgpu::Sampler) {
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("[wgsim] capture blit shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("./shader/blit.wgsl").into()),
+  });
+
+  let pipeline =
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+      label: Some("[wgsim] capture blit pipeline"),
+      layout: None,
+      vertex: wgpu::VertexState {
+        module: &shader,
+        entry_point: Some("vs_main"),
+        buffers: &[],
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+      },
+      fragment: Some(wgpu::FragmentState {
+        module: &shader,
+        entry_point: Some("fs_main"),
+        targets: &[Some(format.into())],
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+      }),
+      primitive: wgpu::PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: wgpu::MultisampleState::default(),
+      multiview: None,
+      cache: None,
+    });
+
+  // 同じ大きさなので補間しない
+  let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+    label: Some("[wgsim] capture blit sampler"),
+    ..Default::default()
+  });
+
+  (pipeline, sampler)
+}
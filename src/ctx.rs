@@ -3,7 +3,10 @@ use std::sync::Arc;
 use winit::window::Window;
 
 use crate::primitive::Size;
-use crate::surface_cfg::SurfaceConfigBuilder;
+use crate::surface_cfg::{
+  resolve_present_mode, vsync_present_modes, OutputColorSpace,
+  SurfaceConfigBuilder, SurfaceConfigError,
+};
 
 #[derive(Debug)]
 pub struct SurfaceDrawingContext<'a> {
@@ -35,20 +38,94 @@ pub struct DrawingContext<'a> {
   pub sample_count: u32,
 }
 
+// デバイスを作るときの設定
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextOptions {
+  pub features: wgpu::Features,
+  pub power_preference: wgpu::PowerPreference,
+  pub force_fallback_adapter: bool,
+  // wgpu::Limits::default() ではなくアダプタの上限でデバイスを作る
+  pub adapter_limits: bool,
+}
+
+impl ContextOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // アダプタが対応していない機能は要求しない
+  pub fn features(mut self, features: wgpu::Features) -> Self {
+    self.features |= features;
+    self
+  }
+
+  pub fn power_preference(mut self, preference: wgpu::PowerPreference) -> Self {
+    self.power_preference = preference;
+    self
+  }
+
+  // GPU のない環境でもソフトウェア (lavapipe や WARP) で描画できる
+  pub fn force_fallback_adapter(mut self, force: bool) -> Self {
+    self.force_fallback_adapter = force;
+    self
+  }
+
+  // 128 MiB を超えるストレージバッファなど、既定の上限を超えて使う場合
+  pub fn adapter_limits(mut self, adapter_limits: bool) -> Self {
+    self.adapter_limits = adapter_limits;
+    self
+  }
+
+  fn required_limits(&self, adapter: &wgpu::Adapter) -> wgpu::Limits {
+    if self.adapter_limits {
+      adapter.limits()
+    } else {
+      wgpu::Limits::default()
+    }
+  }
+
+  fn adapter_options<'s>(
+    &self,
+    compatible_surface: Option<&'s wgpu::Surface<'_>>,
+  ) -> wgpu::RequestAdapterOptions<'s, 's> {
+    wgpu::RequestAdapterOptions {
+      power_preference: self.power_preference,
+      force_fallback_adapter: self.force_fallback_adapter,
+      compatible_surface,
+    }
+  }
+}
+
 impl<'a> DrawingContext<'a> {
   pub async fn new_for_texture(
     size: Size,
     format: wgpu::TextureFormat,
+  ) -> Self {
+    Self::new_for_texture_with_options(size, format, &ContextOptions::default())
+      .await
+  }
+
+  pub async fn new_for_texture_with_options(
+    size: Size,
+    format: wgpu::TextureFormat,
+    options: &ContextOptions,
   ) -> Self {
     let instance = wgpu::Instance::default();
 
     let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions::default())
+      .request_adapter(&options.adapter_options(None))
       .await
-      .unwrap();
+      .expect("Failed to find an appropriate adapter");
 
     let (device, queue) = adapter
-      .request_device(&wgpu::DeviceDescriptor::default(), None)
+      .request_device(
+        &wgpu::DeviceDescriptor {
+          required_features: options.features & adapter.features(),
+          required_limits: options.required_limits(&adapter),
+          ..Default::default()
+        },
+        None,
+      )
       .await
       .unwrap();
 
@@ -65,7 +142,20 @@ impl<'a> DrawingContext<'a> {
   pub async fn new_for_surface(
     window: Arc<Window>,
     cfg_builder: &SurfaceConfigBuilder<'a>,
-  ) -> Self {
+  ) -> Result<Self, SurfaceConfigError> {
+    Self::new_for_surface_with_options(
+      window,
+      cfg_builder,
+      &ContextOptions::default(),
+    )
+    .await
+  }
+
+  pub async fn new_for_surface_with_options(
+    window: Arc<Window>,
+    cfg_builder: &SurfaceConfigBuilder<'a>,
+    options: &ContextOptions,
+  ) -> Result<Self, SurfaceConfigError> {
     let size = window.inner_size();
     let dpi = window.scale_factor();
 
@@ -74,10 +164,7 @@ impl<'a> DrawingContext<'a> {
       instance.create_surface(window).expect("Failed to create surface");
 
     let adapter = instance
-      .request_adapter(&wgpu::RequestAdapterOptions {
-        compatible_surface: Some(&surface),
-        ..Default::default()
-      })
+      .request_adapter(&options.adapter_options(Some(&surface)))
       .await
       .expect("Failed to find an appropriate adapter");
 
@@ -85,7 +172,9 @@ impl<'a> DrawingContext<'a> {
       .request_device(
         &wgpu::DeviceDescriptor {
           required_features: wgpu::Features::default()
-            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
+            | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+            | (options.features & adapter.features()),
+          required_limits: options.required_limits(&adapter),
           ..Default::default()
         },
         None,
@@ -93,10 +182,11 @@ impl<'a> DrawingContext<'a> {
       .await
       .expect("Failed to create device");
 
-    let config = cfg_builder.build(&adapter, &surface, size.width, size.height);
+    let config =
+      cfg_builder.build(&adapter, &surface, size.width, size.height)?;
     surface.configure(&device, &config);
 
-    Self {
+    Ok(Self {
       instance,
       adapter,
       device,
@@ -108,7 +198,7 @@ impl<'a> DrawingContext<'a> {
         dpi: dpi as u32,
       }),
       sample_count: 1,
-    }
+    })
   }
 
   pub fn with_sample_count(mut self, sample_count: u32) -> Self {
@@ -123,7 +213,11 @@ impl<'a> DrawingContext<'a> {
     }
   }
 
-  pub fn surface(&self) -> Option<&wgpu::Surface> {
+  pub fn color_space(&self) -> OutputColorSpace {
+    OutputColorSpace::from_format(self.format())
+  }
+
+  pub fn surface(&self) -> Option<&wgpu::Surface<'_>> {
     match &self.ty {
       DrawingContextType::Surface(ctx) => Some(&ctx.surface),
       DrawingContextType::Texture(_) => None,
@@ -139,20 +233,42 @@ impl<'a> DrawingContext<'a> {
 
   pub fn resolution(&self) -> Size {
     match &self.ty {
-      DrawingContextType::Surface(ctx) => {
-        let logical_size = Size {
-          width: ctx.size.width / ctx.dpi,
-          height: ctx.size.height / ctx.dpi,
-        };
-        logical_size
-      }
+      DrawingContextType::Surface(ctx) => Size {
+        width: ctx.size.width / ctx.dpi,
+        height: ctx.size.height / ctx.dpi,
+      },
       DrawingContextType::Texture(ctx) => ctx.size,
     }
   }
 
   pub fn aspect_ratio(&self) -> f32 {
-    let Size { width, height } = self.size();
-    *width as f32 / *height as f32
+    self.size().aspect_ratio()
+  }
+
+  // 実行中に垂直同期を切り替える (テクスチャへの描画では何もしない)
+  pub fn set_vsync(&mut self, vsync: bool) -> Result<(), SurfaceConfigError> {
+    self.set_present_modes(vsync_present_modes(vsync))
+  }
+
+  // 先頭から順に試し、どれにも対応していなければ設定を変えずにエラーを返す
+  pub fn set_present_modes(
+    &mut self,
+    modes: &[wgpu::PresentMode],
+  ) -> Result<(), SurfaceConfigError> {
+    let DrawingContextType::Surface(ctx) = &mut self.ty else {
+      return Ok(());
+    };
+    let caps = ctx.surface.get_capabilities(&self.adapter);
+    ctx.config.present_mode = resolve_present_mode(&caps, modes)?;
+    ctx.surface.configure(&self.device, &ctx.config);
+    Ok(())
+  }
+
+  pub fn present_mode(&self) -> Option<wgpu::PresentMode> {
+    match &self.ty {
+      DrawingContextType::Surface(ctx) => Some(ctx.config.present_mode),
+      DrawingContextType::Texture(_) => None,
+    }
   }
 
   pub fn resize(&mut self, size: Size) {
@@ -0,0 +1,322 @@
+use crate::ctx::DrawingContext;
+use crate::fullscreen::{FullscreenPass, FullscreenPassBuilder};
+use crate::layout::ShaderType;
+use crate::primitive::Size;
+use crate::util::{
+  BindGroupBuilder, BindGroupLayout, BindGroupLayoutBuilder, UniformBuffer,
+};
+
+// エフェクトのフラグメントシェーダの前に連結される宣言
+// @group(1) に前段の結果、@group(2) にエフェクトごとの uniform が入る
+pub const EFFECT_WGSL: &str = "
+@group(1) @binding(0) var effect_input: texture_2d<f32>;
+@group(1) @binding(1) var effect_sampler: sampler;
+";
+
+const PASS_THROUGH_WGSL: &str = "
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  return textureSample(effect_input, effect_sampler, in.uv);
+}
+";
+
+pub struct Effect<'a> {
+  label: &'a str,
+  fs_source: &'a str,
+  fs_entry: &'a str,
+  uniform: Option<&'a wgpu::Buffer>,
+}
+
+impl<'a> Effect<'a> {
+  pub fn new(label: &'a str, fs_source: &'a str) -> Self {
+    Self {
+      label,
+      fs_source,
+      fs_entry: "fs_main",
+      uniform: None,
+    }
+  }
+
+  pub fn fs_entry(mut self, entry: &'a str) -> Self {
+    self.fs_entry = entry;
+    self
+  }
+
+  // @group(2) @binding(0) に置く uniform
+  // 値を変える場合は UniformBuffer::set と flush を呼ぶ
+  pub fn uniform<T: ShaderType>(
+    mut self,
+    uniform: &'a UniformBuffer<T>,
+  ) -> Self {
+    self.uniform = Some(uniform.buffer());
+    self
+  }
+}
+
+struct RenderTarget {
+  view: wgpu::TextureView,
+  bind_group: wgpu::BindGroup,
+}
+
+struct EffectPass {
+  pass: FullscreenPass,
+  uniform_bind_group: Option<wgpu::BindGroup>,
+}
+
+pub struct EffectChainBuilder<'a> {
+  ctx: &'a DrawingContext<'a>,
+  format: wgpu::TextureFormat,
+  output_format: wgpu::TextureFormat,
+  scale: f32,
+  effects: Vec<Effect<'a>>,
+}
+
+impl<'a> EffectChainBuilder<'a> {
+  pub fn new(ctx: &'a DrawingContext<'a>) -> Self {
+    Self {
+      ctx,
+      format: ctx.format(),
+      output_format: ctx.format(),
+      scale: 1.0,
+      effects: Vec::new(),
+    }
+  }
+
+  // 中間ターゲットのフォーマット
+  pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  pub fn hdr(self) -> Self {
+    self.format(wgpu::TextureFormat::Rgba16Float)
+  }
+
+  pub fn output_format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.output_format = format;
+    self
+  }
+
+  // DrawingContext::size() に対する中間ターゲットの倍率
+  pub fn scale(mut self, scale: f32) -> Self {
+    assert!(scale > 0.0, "[wgsim] effect chain scale must be positive");
+    self.scale = scale;
+    self
+  }
+
+  pub fn effect(mut self, effect: Effect<'a>) -> Self {
+    self.effects.push(effect);
+    self
+  }
+
+  pub fn build(self) -> EffectChain {
+    let device = &self.ctx.device;
+
+    let input_layout = BindGroupLayoutBuilder::new(device)
+      .label("[wgsim] effect input bind group layout")
+      .texture(
+        0,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .sampler(
+        1,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::SamplerBindingType::Filtering,
+      )
+      .build();
+
+    let uniform_layout = BindGroupLayoutBuilder::new(device)
+      .label("[wgsim] effect uniform bind group layout")
+      .uniform(0, wgpu::ShaderStages::FRAGMENT)
+      .build();
+
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("[wgsim] effect sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+
+    let mut effects = self.effects;
+    if effects.is_empty() {
+      effects.push(Effect::new("pass through", PASS_THROUGH_WGSL));
+    }
+
+    let size = scaled_size(*self.ctx.size(), self.scale);
+    let last = effects.len() - 1;
+    let passes = effects
+      .into_iter()
+      .enumerate()
+      .map(|(i, effect)| {
+        let format = if i == last {
+          self.output_format
+        } else {
+          self.format
+        };
+
+        let fs_source = format!("{}\n{}", EFFECT_WGSL, effect.fs_source);
+        let mut builder = FullscreenPassBuilder::new(self.ctx, &fs_source)
+          .fs_entry(effect.fs_entry)
+          .format(format)
+          .bind_group_layout(&input_layout);
+        if effect.uniform.is_some() {
+          builder = builder.bind_group_layout(&uniform_layout);
+        }
+        let mut pass = builder.build();
+        if i != last {
+          pass.resize(size);
+          pass.flush(&self.ctx.queue);
+        }
+
+        let uniform_bind_group = effect.uniform.map(|buffer| {
+          BindGroupBuilder::new(device, &uniform_layout)
+            .label(effect.label)
+            .buffer(0, buffer)
+            .build()
+        });
+
+        EffectPass {
+          pass,
+          uniform_bind_group,
+        }
+      })
+      .collect();
+
+    let targets = [0, 1].map(|_| {
+      create_render_target(device, &input_layout, &sampler, size, self.format)
+    });
+
+    EffectChain {
+      input_layout,
+      sampler,
+      targets,
+      passes,
+      format: self.format,
+      scale: self.scale,
+      size,
+    }
+  }
+}
+
+pub struct EffectChain {
+  input_layout: BindGroupLayout,
+  sampler: wgpu::Sampler,
+  targets: [RenderTarget; 2],
+  passes: Vec<EffectPass>,
+  format: wgpu::TextureFormat,
+  scale: f32,
+  size: Size,
+}
+
+impl EffectChain {
+  pub fn format(&self) -> wgpu::TextureFormat {
+    self.format
+  }
+
+  pub fn size(&self) -> Size {
+    self.size
+  }
+
+  // メインパスはここに描画する
+  pub fn input_view(&self) -> &wgpu::TextureView {
+    &self.targets[0].view
+  }
+
+  pub fn resize(&mut self, ctx: &DrawingContext) {
+    let size = scaled_size(*ctx.size(), self.scale);
+    if size.width == self.size.width && size.height == self.size.height {
+      return;
+    }
+
+    self.size = size;
+    self.targets = [0, 1].map(|_| {
+      create_render_target(
+        &ctx.device,
+        &self.input_layout,
+        &self.sampler,
+        size,
+        self.format,
+      )
+    });
+  }
+
+  // リサイズに追従する (1フレームに1回呼ぶ想定)
+  // uniform は各 UniformBuffer の flush で書き込む
+  pub fn update(&mut self, ctx: &DrawingContext) {
+    self.resize(ctx);
+
+    // 中間のパスは縮小したターゲット、最後のパスは出力先の大きさで描く
+    let last = self.passes.len() - 1;
+    for (i, effect) in self.passes.iter_mut().enumerate() {
+      let size = if i == last { *ctx.size() } else { self.size };
+      effect.pass.resize(size);
+      effect.pass.flush(&ctx.queue);
+    }
+  }
+
+  pub fn run(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+  ) {
+    let last = self.passes.len() - 1;
+
+    for (i, effect) in self.passes.iter().enumerate() {
+      let src = &self.targets[i % 2];
+      let dst = if i == last {
+        target
+      } else {
+        &self.targets[(i + 1) % 2].view
+      };
+
+      let mut bind_groups = vec![&src.bind_group];
+      if let Some(bind_group) = &effect.uniform_bind_group {
+        bind_groups.push(bind_group);
+      }
+
+      effect.pass.draw(encoder, dst, &bind_groups);
+    }
+  }
+}
+
+fn scaled_size(size: Size, scale: f32) -> Size {
+  Size::new(
+    ((size.width as f32 * scale).round() as u32).max(1),
+    ((size.height as f32 * scale).round() as u32).max(1),
+  )
+}
+
+fn create_render_target(
+  device: &wgpu::Device,
+  layout: &BindGroupLayout,
+  sampler: &wgpu::Sampler,
+  size: Size,
+  format: wgpu::TextureFormat,
+) -> RenderTarget {
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some("[wgsim] effect render target"),
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format,
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::TEXTURE_BINDING,
+    view_formats: &[],
+  });
+  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+  let bind_group = BindGroupBuilder::new(device, layout)
+    .label("[wgsim] effect input bind group")
+    .texture_view(0, &view)
+    .sampler(1, sampler)
+    .build();
+
+  RenderTarget { view, bind_group }
+}
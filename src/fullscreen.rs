@@ -0,0 +1,265 @@
+use crate::ctx::DrawingContext;
+use crate::layout::ShaderType;
+use crate::ppl::RenderPipelineBuilder;
+use crate::primitive::Size;
+use crate::util::{BindGroupBuilder, BindGroupLayoutBuilder, UniformBuffer};
+
+// フラグメントシェーダの前に連結される頂点シェーダ
+// @group(0) は FullscreenPass が使うため、利用側のバインドグループは @group(1) 以降に置く
+pub const FULLSCREEN_WGSL: &str = include_str!("./shader/fullscreen.wgsl");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjectFit {
+  #[default]
+  Fill,
+  Contain,
+  Cover,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct FullscreenUniforms {
+  resolution: [f32; 2],
+  content_size: [f32; 2],
+  fit: u32,
+  // WGSL の struct は vec2f に合わせて 8 の倍数 (24 バイト) になる
+  _padding: u32,
+}
+
+impl ShaderType for FullscreenUniforms {
+  const ALIGN: usize = 8;
+  const SIZE: usize = 24;
+  const WGSL_TYPE: &'static str = "FullscreenUniforms";
+}
+
+pub struct FullscreenPassBuilder<'a> {
+  ctx: &'a DrawingContext<'a>,
+  fs_source: &'a str,
+  fs_entry: &'a str,
+  bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+  format: wgpu::TextureFormat,
+  blend: Option<wgpu::BlendState>,
+  fit: ObjectFit,
+  content_size: Option<Size>,
+  constants: Vec<(&'a str, f64)>,
+}
+
+impl<'a> FullscreenPassBuilder<'a> {
+  pub fn new(ctx: &'a DrawingContext<'a>, fs_source: &'a str) -> Self {
+    Self {
+      ctx,
+      fs_source,
+      fs_entry: "fs_main",
+      bind_group_layouts: Vec::new(),
+      format: ctx.format(),
+      blend: None,
+      fit: ObjectFit::Fill,
+      content_size: None,
+      constants: Vec::new(),
+    }
+  }
+
+  pub fn fs_entry(mut self, entry: &'a str) -> Self {
+    self.fs_entry = entry;
+    self
+  }
+
+  // @group(1) から順に割り当てられる
+  pub fn bind_group_layout(
+    mut self,
+    layout: &'a wgpu::BindGroupLayout,
+  ) -> Self {
+    self.bind_group_layouts.push(layout);
+    self
+  }
+
+  pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  pub fn blend(mut self, blend: wgpu::BlendState) -> Self {
+    self.blend = Some(blend);
+    self
+  }
+
+  pub fn fit(mut self, fit: ObjectFit, content_size: Size) -> Self {
+    self.fit = fit;
+    self.content_size = Some(content_size);
+    self
+  }
+
+  pub fn constant(mut self, name: &'a str, value: f64) -> Self {
+    self.constants.push((name, value));
+    self
+  }
+
+  pub fn build(&self) -> FullscreenPass {
+    let device = &self.ctx.device;
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("[wgsim] fullscreen shader"),
+      source: wgpu::ShaderSource::Wgsl(
+        format!("{}\n{}", FULLSCREEN_WGSL, self.fs_source).into(),
+      ),
+    });
+
+    let uniforms_layout = BindGroupLayoutBuilder::new(device)
+      .label("[wgsim] fullscreen bind group layout")
+      .entry(
+        0,
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        UniformBuffer::<FullscreenUniforms>::binding_type(),
+      )
+      .build();
+
+    let resolution = self.ctx.size();
+    let content_size = self.content_size.unwrap_or(*resolution);
+    let uniforms = UniformBuffer::new(
+      device,
+      FullscreenUniforms {
+        resolution: [resolution.width as f32, resolution.height as f32],
+        content_size: [content_size.width as f32, content_size.height as f32],
+        fit: self.fit as u32,
+        _padding: 0,
+      },
+    );
+
+    let uniforms_bind_group = BindGroupBuilder::new(device, &uniforms_layout)
+      .label("[wgsim] fullscreen bind group")
+      .entry(0, uniforms.as_binding_resource())
+      .build();
+
+    let mut bind_group_layouts = vec![&uniforms_layout.layout];
+    bind_group_layouts.extend(self.bind_group_layouts.iter().copied());
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("[wgsim] fullscreen pipeline layout"),
+        bind_group_layouts: &bind_group_layouts,
+        push_constant_ranges: &[],
+      });
+
+    let pipeline = RenderPipelineBuilder::new(self.ctx)
+      .vs_shader(&shader, "fullscreen_vs")
+      .fs_shader(&shader, self.fs_entry)
+      .pipeline_layout(&pipeline_layout)
+      .targets(vec![Some(wgpu::ColorTargetState {
+        format: self.format,
+        blend: self.blend,
+        write_mask: wgpu::ColorWrites::ALL,
+      })])
+      .sample_count(1)
+      .constants(&self.constants)
+      .build();
+
+    FullscreenPass {
+      pipeline,
+      uniforms,
+      uniforms_bind_group,
+    }
+  }
+}
+
+pub struct FullscreenPass {
+  pipeline: wgpu::RenderPipeline,
+  uniforms: UniformBuffer<FullscreenUniforms>,
+  uniforms_bind_group: wgpu::BindGroup,
+}
+
+impl FullscreenPass {
+  pub fn set_fit(&mut self, fit: ObjectFit, content_size: Size) {
+    self.uniforms.update(|uniforms| {
+      uniforms.fit = fit as u32;
+      uniforms.content_size =
+        [content_size.width as f32, content_size.height as f32];
+    });
+  }
+
+  pub fn resize(&mut self, size: Size) {
+    self.uniforms.update(|uniforms| {
+      uniforms.resolution = [size.width as f32, size.height as f32];
+    });
+  }
+
+  // 描画先のサイズを追従させ、変更があれば書き込む
+  pub fn update(&mut self, ctx: &DrawingContext) {
+    self.resize(*ctx.size());
+    self.flush(&ctx.queue);
+  }
+
+  // resize や set_fit の変更を書き込む
+  pub fn flush(&mut self, queue: &wgpu::Queue) {
+    self.uniforms.flush(queue);
+  }
+
+  pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+    &self.pipeline
+  }
+
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    bind_groups: &[&wgpu::BindGroup],
+  ) {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("[wgsim] fullscreen pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: target,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    self.draw_in_pass(&mut render_pass, bind_groups);
+  }
+
+  pub fn draw_in_pass(
+    &self,
+    render_pass: &mut wgpu::RenderPass,
+    bind_groups: &[&wgpu::BindGroup],
+  ) {
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.uniforms_bind_group, &[]);
+    for (i, bind_group) in bind_groups.iter().enumerate() {
+      render_pass.set_bind_group(i as u32 + 1, *bind_group, &[]);
+    }
+    render_pass.draw(0..6, 0..1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn uniforms_match_the_wgsl_struct() {
+    let module = naga::front::wgsl::parse_str(FULLSCREEN_WGSL).unwrap();
+    let mut layouter = naga::proc::Layouter::default();
+    layouter.update(module.to_ctx()).unwrap();
+
+    let (handle, ty) = module
+      .types
+      .iter()
+      .find(|(_, ty)| ty.name.as_deref() == Some("FullscreenUniforms"))
+      .unwrap();
+    assert_eq!(
+      ty.inner.size(module.to_ctx()) as usize,
+      FullscreenUniforms::SIZE
+    );
+    assert_eq!(
+      Some(layouter[handle].alignment),
+      naga::proc::Alignment::new(FullscreenUniforms::ALIGN as u32)
+    );
+    assert_eq!(
+      std::mem::size_of::<FullscreenUniforms>(),
+      FullscreenUniforms::SIZE
+    );
+  }
+}
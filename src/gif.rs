@@ -2,8 +2,11 @@ use std::error::Error;
 
 use indicatif::{ProgressBar, ProgressStyle};
 
+use crate::capture::CapturedFrame;
+use crate::ctx::{ContextOptions, DrawingContext};
 use crate::primitive::Size;
-use crate::{ctx::DrawingContext, render::Render};
+use crate::readback::TextureRegion;
+use crate::render::Render;
 
 pub struct Gif<'a, R>
 where
@@ -20,11 +23,30 @@ where
   R: Render<'a>,
 {
   pub async fn new(size: u32, initial: R::Initial, msaa: bool) -> Self {
+    Self::new_with_options(
+      size,
+      initial,
+      msaa,
+      wgpu::TextureFormat::Rgba8UnormSrgb,
+      &ContextOptions::default(),
+    )
+    .await
+  }
+
+  // Rgba16Float などを指定すると、capture_frame で HDR のまま読み戻せる
+  pub async fn new_with_options(
+    size: u32,
+    initial: R::Initial,
+    msaa: bool,
+    format: wgpu::TextureFormat,
+    options: &ContextOptions,
+  ) -> Self {
     let sample_count = if msaa { 4 } else { 1 };
 
-    let ctx = DrawingContext::new_for_texture(
+    let ctx = DrawingContext::new_for_texture_with_options(
       Size::new(size, size),
-      wgpu::TextureFormat::Rgba8UnormSrgb,
+      format,
+      options,
     )
     .await
     .with_sample_count(sample_count);
@@ -42,7 +64,7 @@ where
   fn save_gif(
     &self,
     file_path: &str,
-    frames: &mut Vec<Vec<u8>>,
+    frames: &[CapturedFrame],
     speed: i32,
     size: u16,
   ) -> Result<(), Box<dyn Error>> {
@@ -52,9 +74,13 @@ where
     let mut encoder = Encoder::new(&mut image, size, size, &[])?;
     encoder.set_repeat(Repeat::Infinite)?;
 
-    for mut frame in frames {
+    for frame in frames {
+      let mut rgba = frame
+        .to_rgba8()
+        .ok_or_else(|| format!("{:?} cannot be saved as GIF", frame.format))?
+        .into_raw();
       encoder
-        .write_frame(&Frame::from_rgba_speed(size, size, &mut frame, speed))?;
+        .write_frame(&Frame::from_rgba_speed(size, size, &mut rgba, speed))?;
     }
 
     Ok(())
@@ -66,6 +92,30 @@ where
     scene_count: usize,
     speed: i32,
   ) -> Result<(), Box<dyn Error>> {
+    let frames = self.render_frames(scene_count, true).await?;
+
+    self.save_gif(file_path, &frames, speed, self.size as u16)?;
+
+    println!("Gif has been saved to {}", file_path);
+
+    Ok(())
+  }
+
+  // scene_count 回描画し、最後のフレームを返す (save_exr / save_png16 で書き出せる)
+  pub async fn capture_frame(
+    &mut self,
+    scene_count: usize,
+  ) -> Result<CapturedFrame, Box<dyn Error>> {
+    let frames = self.render_frames(scene_count, false).await?;
+    frames.into_iter().last().ok_or_else(|| "no frame was rendered".into())
+  }
+
+  // keep_all が false の場合は最後のフレームだけを残す
+  async fn render_frames(
+    &mut self,
+    scene_count: usize,
+    keep_all: bool,
+  ) -> Result<Vec<CapturedFrame>, Box<dyn Error>> {
     let progress = ProgressBar::new(scene_count as u64);
     progress.set_style(
       ProgressStyle::with_template(
@@ -91,26 +141,12 @@ where
       view_formats: &[],
     };
     let texture = self.ctx.device.create_texture(&texture_desc);
-
-    let pixel_size = std::mem::size_of::<[u8; 4]>() as u32;
-    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-    let unpadded_bytes_per_row = pixel_size * self.size;
-    let padding = (align - unpadded_bytes_per_row % align) % align;
-    let padded_bytes_per_row = unpadded_bytes_per_row + padding;
-
-    let buffer_size = (padded_bytes_per_row * self.size) as wgpu::BufferAddress;
-    let buffer_desc = wgpu::BufferDescriptor {
-      size: buffer_size,
-      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-      label: Some("Output Buffer"),
-      mapped_at_creation: false,
-    };
-    let output_buffer = self.ctx.device.create_buffer(&buffer_desc);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
     let mut frames = Vec::new();
     let render_start_time = std::time::Instant::now();
 
-    for _ in 0..scene_count {
+    for i in 0..scene_count {
       let mut command_encoder = self.ctx.device.create_command_encoder(
         &wgpu::CommandEncoderDescriptor { label: None },
       );
@@ -119,53 +155,20 @@ where
       let dt = now - render_start_time;
       self.renderer.update(&self.ctx, dt);
 
-      self.renderer.draw(
-        &mut command_encoder,
-        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
-        self.sample_count,
-      )?;
-
-      command_encoder.copy_texture_to_buffer(
-        wgpu::ImageCopyTexture {
-          texture: &texture,
-          mip_level: 0,
-          origin: wgpu::Origin3d::ZERO,
-          aspect: wgpu::TextureAspect::All,
-        },
-        wgpu::ImageCopyBuffer {
-          buffer: &output_buffer,
-          layout: wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(padded_bytes_per_row),
-            rows_per_image: Some(self.size),
-          },
-        },
-        texture_desc.size,
-      );
+      self.renderer.draw(&mut command_encoder, &view, self.sample_count)?;
 
       self.renderer.submit(&self.ctx.queue, command_encoder, None);
 
-      let buffer_slice = output_buffer.slice(..);
-      let (tx, rx) = futures_intrusive::channel::shared::oneshot_channel();
-      buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        tx.send(result).unwrap();
-      });
-      self.ctx.device.poll(wgpu::Maintain::Wait);
-
-      match rx.receive().await {
-        Some(Ok(())) => {
-          let padded_data = buffer_slice.get_mapped_range();
-          let data = padded_data
-            .chunks(padded_bytes_per_row as _)
-            .map(|chunk| &chunk[..unpadded_bytes_per_row as _])
-            .flatten()
-            .map(|x| *x)
-            .collect::<Vec<_>>();
-          drop(padded_data);
-          output_buffer.unmap();
-          frames.push(data);
-        }
-        _ => eprintln!("Something went wrong"),
+      // 最後のフレームだけが必要な場合は途中を読み戻さない
+      if keep_all || i + 1 == scene_count {
+        let data =
+          self.ctx.read_texture::<u8>(&texture, TextureRegion::default())?;
+        frames.push(CapturedFrame {
+          size: Size::new(self.size, self.size),
+          format: self.ctx.format(),
+          data,
+          presented_at: std::time::Instant::now(),
+        });
       }
 
       progress.inc(1);
@@ -173,10 +176,6 @@ where
 
     progress.finish_with_message("All scenes have been rendered 🎉");
 
-    self.save_gif(file_path, &mut frames, speed, self.size as u16)?;
-
-    println!("Gif has been saved to {}", file_path);
-
-    Ok(())
+    Ok(frames)
   }
 }
@@ -0,0 +1,757 @@
+use std::collections::BTreeSet;
+
+use crate::ctx::DrawingContext;
+use crate::primitive::Size;
+
+// 描画先 (Render::draw に渡される target) は常に 0 番のリソース
+const TARGET: ResourceHandle = ResourceHandle(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResourceHandle(usize);
+
+#[derive(Debug, Clone, Copy)]
+pub enum TextureSize {
+  // DrawingContext::size() に対する倍率
+  Relative(f32),
+  Fixed(Size),
+}
+
+impl TextureSize {
+  fn resolve(&self, size: Size) -> Size {
+    match self {
+      Self::Relative(scale) => Size::new(
+        ((size.width as f32 * scale).round() as u32).max(1),
+        ((size.height as f32 * scale).round() as u32).max(1),
+      ),
+      Self::Fixed(size) => *size,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextureDesc {
+  pub format: wgpu::TextureFormat,
+  pub size: TextureSize,
+  pub usage: wgpu::TextureUsages,
+  pub sample_count: u32,
+}
+
+impl TextureDesc {
+  pub fn new(format: wgpu::TextureFormat) -> Self {
+    Self {
+      format,
+      size: TextureSize::Relative(1.0),
+      usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+        | wgpu::TextureUsages::TEXTURE_BINDING,
+      sample_count: 1,
+    }
+  }
+
+  pub fn scale(mut self, scale: f32) -> Self {
+    assert!(scale > 0.0, "[wgsim] texture scale must be positive");
+    self.size = TextureSize::Relative(scale);
+    self
+  }
+
+  pub fn fixed_size(mut self, size: Size) -> Self {
+    self.size = TextureSize::Fixed(size);
+    self
+  }
+
+  // RENDER_ATTACHMENT | TEXTURE_BINDING に追加する usage
+  pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+    self.usage |= usage;
+    self
+  }
+
+  pub fn sample_count(mut self, sample_count: u32) -> Self {
+    self.sample_count = sample_count;
+    self
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderGraphError {
+  NoOutput,
+  Cycle(Vec<String>),
+  InvalidHandle { pass: String },
+}
+
+impl std::fmt::Display for RenderGraphError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NoOutput => {
+        write!(f, "no pass writes to the target or an external resource")
+      }
+      Self::Cycle(passes) => {
+        write!(f, "passes form a cycle: {}", passes.join(", "))
+      }
+      Self::InvalidHandle { pass } => {
+        write!(f, "pass '{}' uses a handle from another graph", pass)
+      }
+    }
+  }
+}
+
+impl std::error::Error for RenderGraphError {}
+
+enum ResourceKind {
+  Target,
+  // 利用側の状態 (S) が持つバッファやテクスチャ
+  // 実体は持たず、パスの順序付けにだけ使う
+  External,
+  Transient(TextureDesc),
+}
+
+struct ResourceNode {
+  name: String,
+  kind: ResourceKind,
+}
+
+type ExecuteFn<S> = Box<dyn Fn(&S, &mut PassContext)>;
+type BindGroupsFn<S> =
+  Box<dyn Fn(&S, &wgpu::Device, &GraphResources) -> Vec<wgpu::BindGroup>>;
+
+struct PassNode<S> {
+  name: String,
+  reads: Vec<ResourceHandle>,
+  writes: Vec<ResourceHandle>,
+  bind_groups: Option<BindGroupsFn<S>>,
+  execute: ExecuteFn<S>,
+}
+
+pub struct RenderGraphBuilder<S> {
+  resources: Vec<ResourceNode>,
+  passes: Vec<PassNode<S>>,
+}
+
+impl<S> Default for RenderGraphBuilder<S> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<S> RenderGraphBuilder<S> {
+  pub fn new() -> Self {
+    Self {
+      resources: vec![ResourceNode {
+        name: "target".to_string(),
+        kind: ResourceKind::Target,
+      }],
+      passes: Vec::new(),
+    }
+  }
+
+  pub fn target(&self) -> ResourceHandle {
+    TARGET
+  }
+
+  // フレーム内だけで使われるテクスチャ
+  // 寿命が重ならず記述子が一致するものは同じ実体を共有する
+  pub fn texture(&mut self, name: &str, desc: TextureDesc) -> ResourceHandle {
+    self.add_resource(name, ResourceKind::Transient(desc))
+  }
+
+  pub fn external(&mut self, name: &str) -> ResourceHandle {
+    self.add_resource(name, ResourceKind::External)
+  }
+
+  pub fn pass(&mut self, name: &str) -> PassBuilder<'_, S> {
+    PassBuilder {
+      graph: self,
+      name: name.to_string(),
+      reads: Vec::new(),
+      writes: Vec::new(),
+      bind_groups: None,
+    }
+  }
+
+  pub fn build(self) -> Result<RenderGraph<S>, RenderGraphError> {
+    let resource_count = self.resources.len();
+    for pass in &self.passes {
+      if pass.reads.iter().chain(&pass.writes).any(|h| h.0 >= resource_count) {
+        return Err(RenderGraphError::InvalidHandle {
+          pass: pass.name.clone(),
+        });
+      }
+    }
+
+    let deps = dependencies(&self.passes, resource_count);
+
+    // 描画先か外部リソースに書き込むパスから遡り、到達しないパスは間引く
+    let mut kept = vec![false; self.passes.len()];
+    let mut stack: Vec<usize> = (0..self.passes.len())
+      .filter(|&i| {
+        self.passes[i].writes.iter().any(|h| {
+          matches!(
+            self.resources[h.0].kind,
+            ResourceKind::Target | ResourceKind::External
+          )
+        })
+      })
+      .collect();
+    if stack.is_empty() {
+      return Err(RenderGraphError::NoOutput);
+    }
+    while let Some(i) = stack.pop() {
+      if kept[i] {
+        continue;
+      }
+      kept[i] = true;
+      stack.extend(deps[i].iter().copied());
+    }
+
+    let order = topological_order(&deps, &kept).map_err(|rest| {
+      RenderGraphError::Cycle(
+        rest.into_iter().map(|i| self.passes[i].name.clone()).collect(),
+      )
+    })?;
+
+    // 順序付け後のパス位置で各テクスチャの寿命を求める
+    let mut lifetimes = vec![None; resource_count];
+    for (step, &i) in order.iter().enumerate() {
+      let pass = &self.passes[i];
+      for handle in pass.reads.iter().chain(&pass.writes) {
+        let lifetime: &mut Option<(usize, usize)> = &mut lifetimes[handle.0];
+        *lifetime = match *lifetime {
+          Some((first, _)) => Some((first, step)),
+          None => Some((step, step)),
+        };
+      }
+    }
+
+    let bind_groups = self.passes.iter().map(|_| Vec::new()).collect();
+
+    Ok(RenderGraph {
+      resources: self.resources,
+      passes: self.passes,
+      order,
+      lifetimes,
+      physical: Vec::new(),
+      assignment: vec![None; resource_count],
+      bind_groups,
+      size: None,
+      invalidated: true,
+    })
+  }
+
+  fn add_resource(&mut self, name: &str, kind: ResourceKind) -> ResourceHandle {
+    self.resources.push(ResourceNode {
+      name: name.to_string(),
+      kind,
+    });
+    ResourceHandle(self.resources.len() - 1)
+  }
+}
+
+pub struct PassBuilder<'a, S> {
+  graph: &'a mut RenderGraphBuilder<S>,
+  name: String,
+  reads: Vec<ResourceHandle>,
+  writes: Vec<ResourceHandle>,
+  bind_groups: Option<BindGroupsFn<S>>,
+}
+
+impl<S> PassBuilder<'_, S> {
+  pub fn read(mut self, handle: ResourceHandle) -> Self {
+    self.reads.push(handle);
+    self
+  }
+
+  pub fn write(mut self, handle: ResourceHandle) -> Self {
+    self.writes.push(handle);
+    self
+  }
+
+  // テクスチャの確保し直しのたびに呼ばれ、結果は PassContext::bind_group() で参照できる
+  pub fn bind_groups(
+    mut self,
+    f: impl Fn(&S, &wgpu::Device, &GraphResources) -> Vec<wgpu::BindGroup> + 'static,
+  ) -> Self {
+    self.bind_groups = Some(Box::new(f));
+    self
+  }
+
+  pub fn execute(self, f: impl Fn(&S, &mut PassContext) + 'static) {
+    self.graph.passes.push(PassNode {
+      name: self.name,
+      reads: self.reads,
+      writes: self.writes,
+      bind_groups: self.bind_groups,
+      execute: Box::new(f),
+    });
+  }
+}
+
+struct PhysicalTexture {
+  texture: wgpu::Texture,
+  view: wgpu::TextureView,
+}
+
+pub struct GraphResources<'a> {
+  resources: &'a [ResourceNode],
+  assignment: &'a [Option<usize>],
+  physical: &'a [PhysicalTexture],
+  target: Option<&'a wgpu::TextureView>,
+  size: Size,
+}
+
+impl<'a> GraphResources<'a> {
+  pub fn view(&self, handle: ResourceHandle) -> &'a wgpu::TextureView {
+    if handle == TARGET {
+      return self
+        .target
+        .expect("[wgsim] the target is only available while executing");
+    }
+    &self.physical_texture(handle).view
+  }
+
+  pub fn texture(&self, handle: ResourceHandle) -> &'a wgpu::Texture {
+    &self.physical_texture(handle).texture
+  }
+
+  pub fn size(&self, handle: ResourceHandle) -> Size {
+    match &self.resources[handle.0].kind {
+      ResourceKind::Transient(desc) => desc.size.resolve(self.size),
+      _ => self.size,
+    }
+  }
+
+  fn physical_texture(&self, handle: ResourceHandle) -> &'a PhysicalTexture {
+    let node = &self.resources[handle.0];
+    let index = self.assignment[handle.0].unwrap_or_else(|| {
+      panic!("[wgsim] '{}' is not a texture used by the graph", node.name)
+    });
+    &self.physical[index]
+  }
+}
+
+pub struct PassContext<'a> {
+  pub encoder: &'a mut wgpu::CommandEncoder,
+  pub resources: GraphResources<'a>,
+  bind_groups: &'a [wgpu::BindGroup],
+}
+
+// 返す参照は PassContext 自体を借用しないため、encoder と同時に使える
+impl<'a> PassContext<'a> {
+  pub fn view(&self, handle: ResourceHandle) -> &'a wgpu::TextureView {
+    self.resources.view(handle)
+  }
+
+  pub fn size(&self, handle: ResourceHandle) -> Size {
+    self.resources.size(handle)
+  }
+
+  pub fn bind_group(&self, index: usize) -> &'a wgpu::BindGroup {
+    &self.bind_groups[index]
+  }
+
+  pub fn bind_groups(&self) -> &'a [wgpu::BindGroup] {
+    self.bind_groups
+  }
+}
+
+pub struct RenderGraph<S> {
+  resources: Vec<ResourceNode>,
+  passes: Vec<PassNode<S>>,
+  order: Vec<usize>,
+  lifetimes: Vec<Option<(usize, usize)>>,
+  physical: Vec<PhysicalTexture>,
+  assignment: Vec<Option<usize>>,
+  bind_groups: Vec<Vec<wgpu::BindGroup>>,
+  size: Option<Size>,
+  invalidated: bool,
+}
+
+impl<S> RenderGraph<S> {
+  // 実行順に並べた、間引かれなかったパスの名前
+  pub fn passes(&self) -> impl Iterator<Item = &str> {
+    self.order.iter().map(|&i| self.passes[i].name.as_str())
+  }
+
+  pub fn culled_passes(&self) -> impl Iterator<Item = &str> {
+    self
+      .passes
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| !self.order.contains(i))
+      .map(|(_, pass)| pass.name.as_str())
+  }
+
+  // 共有された分を除いた、実際に確保しているテクスチャの数
+  pub fn texture_count(&self) -> usize {
+    self.physical.len()
+  }
+
+  // 次の update() でバインドグループを作り直す
+  pub fn invalidate(&mut self) {
+    self.invalidated = true;
+  }
+
+  // サイズの変化に合わせてテクスチャを確保し直す (1フレームに1回、draw の前に呼ぶ想定)
+  pub fn update(&mut self, ctx: &DrawingContext, state: &S) {
+    let size = *ctx.size();
+    let resized = self
+      .size
+      .is_none_or(|s| s.width != size.width || s.height != size.height);
+
+    if resized {
+      self.allocate(&ctx.device, size);
+      self.size = Some(size);
+      self.invalidated = true;
+    }
+
+    if self.invalidated {
+      let resources = GraphResources {
+        resources: &self.resources,
+        assignment: &self.assignment,
+        physical: &self.physical,
+        target: None,
+        size,
+      };
+      for &i in &self.order {
+        if let Some(f) = &self.passes[i].bind_groups {
+          self.bind_groups[i] = f(state, &ctx.device, &resources);
+        }
+      }
+      self.invalidated = false;
+    }
+  }
+
+  pub fn execute(
+    &self,
+    state: &S,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+  ) {
+    let size = self
+      .size
+      .expect("[wgsim] RenderGraph::update must be called before execute");
+
+    for &i in &self.order {
+      let mut pass_ctx = PassContext {
+        encoder,
+        resources: GraphResources {
+          resources: &self.resources,
+          assignment: &self.assignment,
+          physical: &self.physical,
+          target: Some(target),
+          size,
+        },
+        bind_groups: &self.bind_groups[i],
+      };
+      (self.passes[i].execute)(state, &mut pass_ctx);
+    }
+  }
+
+  fn allocate(&mut self, device: &wgpu::Device, size: Size) {
+    let (textures, assignment) = self.alias_plan(size);
+
+    self.physical = textures
+      .iter()
+      .map(|(key, i)| create_texture(device, &self.resources[*i].name, key))
+      .collect();
+    self.assignment = assignment;
+  }
+
+  // 記述子が一致し、寿命が終わったテクスチャを使い回す
+  // 実体ごとの (記述子, 最初に割り当てたリソース) と、リソースごとの実体の番号を返す
+  fn alias_plan(
+    &self,
+    size: Size,
+  ) -> (Vec<(TextureKey, usize)>, Vec<Option<usize>>) {
+    let mut transients: Vec<(usize, (usize, usize), TextureDesc)> = self
+      .resources
+      .iter()
+      .enumerate()
+      .filter_map(|(i, node)| match (&node.kind, self.lifetimes[i]) {
+        (ResourceKind::Transient(desc), Some(lifetime)) => {
+          Some((i, lifetime, *desc))
+        }
+        _ => None,
+      })
+      .collect();
+    transients.sort_by_key(|(_, (first, _), _)| *first);
+
+    let mut textures: Vec<(TextureKey, usize, usize)> = Vec::new();
+    let mut assignment = vec![None; self.resources.len()];
+
+    for (i, (first, last), desc) in transients {
+      let key = TextureKey::new(&desc, size);
+      let reusable = textures
+        .iter()
+        .position(|(k, _, busy_until)| *k == key && *busy_until < first);
+
+      let index = match reusable {
+        Some(index) => {
+          textures[index].2 = last;
+          index
+        }
+        None => {
+          textures.push((key, i, last));
+          textures.len() - 1
+        }
+      };
+      assignment[i] = Some(index);
+    }
+
+    let textures = textures.into_iter().map(|(k, i, _)| (k, i)).collect();
+    (textures, assignment)
+  }
+}
+
+#[derive(PartialEq, Eq)]
+struct TextureKey {
+  width: u32,
+  height: u32,
+  format: wgpu::TextureFormat,
+  usage: wgpu::TextureUsages,
+  sample_count: u32,
+}
+
+impl TextureKey {
+  fn new(desc: &TextureDesc, size: Size) -> Self {
+    let size = desc.size.resolve(size);
+    Self {
+      width: size.width,
+      height: size.height,
+      format: desc.format,
+      usage: desc.usage,
+      sample_count: desc.sample_count,
+    }
+  }
+}
+
+fn create_texture(
+  device: &wgpu::Device,
+  name: &str,
+  key: &TextureKey,
+) -> PhysicalTexture {
+  let texture = device.create_texture(&wgpu::TextureDescriptor {
+    label: Some(&format!("[wgsim] graph texture ({})", name)),
+    size: wgpu::Extent3d {
+      width: key.width,
+      height: key.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: key.sample_count,
+    dimension: wgpu::TextureDimension::D2,
+    format: key.format,
+    usage: key.usage,
+    view_formats: &[],
+  });
+  let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+  PhysicalTexture { texture, view }
+}
+
+// 同じリソースへの書き込みは宣言順に並べ、読み込みはすべての書き込みの後に置く
+fn dependencies<S>(
+  passes: &[PassNode<S>],
+  resource_count: usize,
+) -> Vec<BTreeSet<usize>> {
+  let mut writers = vec![Vec::new(); resource_count];
+  for (i, pass) in passes.iter().enumerate() {
+    for handle in &pass.writes {
+      if writers[handle.0].last() != Some(&i) {
+        writers[handle.0].push(i);
+      }
+    }
+  }
+
+  let mut deps = vec![BTreeSet::new(); passes.len()];
+  for (i, pass) in passes.iter().enumerate() {
+    for handle in &pass.writes {
+      let earlier = writers[handle.0].iter().take_while(|&&w| w != i);
+      deps[i].extend(earlier);
+    }
+    for handle in &pass.reads {
+      if pass.writes.contains(handle) {
+        continue;
+      }
+      deps[i].extend(writers[handle.0].iter().filter(|&&w| w != i));
+    }
+  }
+
+  deps
+}
+
+// 実行可能なパスのうち宣言順で最も早いものから並べる
+// 循環がある場合は並べられなかったパスを返す
+fn topological_order(
+  deps: &[BTreeSet<usize>],
+  kept: &[bool],
+) -> Result<Vec<usize>, Vec<usize>> {
+  let mut done = vec![false; deps.len()];
+  let mut order = Vec::new();
+
+  loop {
+    let next = (0..deps.len())
+      .find(|&i| kept[i] && !done[i] && deps[i].iter().all(|&d| done[d]));
+    match next {
+      Some(i) => {
+        done[i] = true;
+        order.push(i);
+      }
+      None => break,
+    }
+  }
+
+  let rest: Vec<usize> =
+    (0..deps.len()).filter(|&i| kept[i] && !done[i]).collect();
+  if rest.is_empty() {
+    Ok(order)
+  } else {
+    Err(rest)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+  fn pass(
+    graph: &mut RenderGraphBuilder<()>,
+    name: &str,
+    reads: &[ResourceHandle],
+    writes: &[ResourceHandle],
+  ) {
+    let mut builder = graph.pass(name);
+    for &handle in reads {
+      builder = builder.read(handle);
+    }
+    for &handle in writes {
+      builder = builder.write(handle);
+    }
+    builder.execute(|_, _| {});
+  }
+
+  fn names<'a>(passes: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    passes.collect()
+  }
+
+  #[test]
+  fn orders_passes_by_dependency() {
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+    let color = graph.texture("color", TextureDesc::new(FORMAT));
+    let bloom = graph.texture("bloom", TextureDesc::new(FORMAT).scale(0.5));
+
+    // 宣言順と実行順が逆になるように並べる
+    pass(&mut graph, "composite", &[color, bloom], &[target]);
+    pass(&mut graph, "bloom", &[color], &[bloom]);
+    pass(&mut graph, "scene", &[], &[color]);
+
+    let graph = graph.build().unwrap();
+    assert_eq!(names(graph.passes()), ["scene", "bloom", "composite"]);
+    assert!(names(graph.culled_passes()).is_empty());
+  }
+
+  #[test]
+  fn keeps_declaration_order_for_writes_to_the_same_resource() {
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+
+    pass(&mut graph, "clear", &[], &[target]);
+    pass(&mut graph, "scene", &[], &[target]);
+    pass(&mut graph, "overlay", &[], &[target]);
+
+    let graph = graph.build().unwrap();
+    assert_eq!(names(graph.passes()), ["clear", "scene", "overlay"]);
+  }
+
+  #[test]
+  fn reports_cycles() {
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+    let a = graph.texture("a", TextureDesc::new(FORMAT));
+    let b = graph.texture("b", TextureDesc::new(FORMAT));
+
+    pass(&mut graph, "first", &[b], &[a]);
+    pass(&mut graph, "second", &[a], &[b]);
+    pass(&mut graph, "output", &[a], &[target]);
+
+    let error = graph.build().err().unwrap();
+    assert_eq!(
+      error,
+      RenderGraphError::Cycle(vec![
+        "first".to_string(),
+        "second".to_string(),
+        "output".to_string(),
+      ])
+    );
+  }
+
+  #[test]
+  fn culls_passes_that_do_not_reach_an_output() {
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+    let history = graph.external("history");
+    let color = graph.texture("color", TextureDesc::new(FORMAT));
+    let debug = graph.texture("debug", TextureDesc::new(FORMAT));
+
+    pass(&mut graph, "scene", &[], &[color]);
+    pass(&mut graph, "debug view", &[color], &[debug]);
+    pass(&mut graph, "present", &[color], &[target]);
+    // 外部リソースへの書き込みは出力として残る
+    pass(&mut graph, "accumulate", &[color], &[history]);
+
+    let graph = graph.build().unwrap();
+    assert_eq!(names(graph.passes()), ["scene", "present", "accumulate"]);
+    assert_eq!(names(graph.culled_passes()), ["debug view"]);
+  }
+
+  #[test]
+  fn rejects_graphs_without_output_and_foreign_handles() {
+    let mut graph = RenderGraphBuilder::<()>::new();
+    let color = graph.texture("color", TextureDesc::new(FORMAT));
+    pass(&mut graph, "scene", &[], &[color]);
+    assert_eq!(graph.build().err(), Some(RenderGraphError::NoOutput));
+
+    let mut other = RenderGraphBuilder::<()>::new();
+    let foreign = other.texture("color", TextureDesc::new(FORMAT));
+
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+    pass(&mut graph, "scene", &[foreign], &[target]);
+    assert_eq!(
+      graph.build().err(),
+      Some(RenderGraphError::InvalidHandle {
+        pass: "scene".to_string()
+      })
+    );
+  }
+
+  #[test]
+  fn aliases_textures_whose_lifetimes_do_not_overlap() {
+    let mut graph = RenderGraphBuilder::new();
+    let target = graph.target();
+    let a = graph.texture("a", TextureDesc::new(FORMAT));
+    let b = graph.texture("b", TextureDesc::new(FORMAT));
+    let c = graph.texture("c", TextureDesc::new(FORMAT));
+    let half = graph.texture("half", TextureDesc::new(FORMAT).scale(0.5));
+
+    // a -> b -> c -> target: a は b を書いた後に使われないので c と共有できる
+    pass(&mut graph, "1", &[], &[a]);
+    pass(&mut graph, "2", &[a], &[b]);
+    pass(&mut graph, "3", &[b], &[c]);
+    pass(&mut graph, "4", &[c], &[half]);
+    pass(&mut graph, "5", &[half], &[target]);
+
+    let graph = graph.build().unwrap();
+    let (textures, assignment) = graph.alias_plan(Size::new(64, 32));
+
+    // a と c が同じ実体、b は a と寿命が重なるので別、half は記述子が違うので別
+    assert_eq!(textures.len(), 3);
+    assert_eq!(assignment[a.0], assignment[c.0]);
+    assert_ne!(assignment[a.0], assignment[b.0]);
+    assert_ne!(assignment[c.0], assignment[half.0]);
+    assert_eq!(assignment[target.0], None);
+
+    let half_key = &textures[assignment[half.0].unwrap()].0;
+    assert_eq!((half_key.width, half_key.height), (32, 16));
+  }
+}
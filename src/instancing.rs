@@ -0,0 +1,381 @@
+use std::ops::Range;
+
+use wgpu::util::DeviceExt;
+
+use crate::camera::CAMERA_WGSL;
+use crate::ctx::DrawingContext;
+use crate::ppl::RenderPipelineBuilder;
+use crate::util::{BindGroupBuilder, BindGroupLayout, BindGroupLayoutBuilder};
+
+const INSTANCING_WGSL: &str = include_str!("./shader/instancing.wgsl");
+
+// コンピュートシェーダで描画数を数えるときの引数の並び
+// instance_count を atomicAdd で増やし、draw_indirect に渡す
+pub const INDIRECT_WGSL: &str = "
+struct DrawIndirectArgs {
+  vertex_count: u32,
+  instance_count: atomic<u32>,
+  first_vertex: u32,
+  first_instance: u32,
+}
+";
+
+// 頂点バッファを使わず、vertex_index から形を作る
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceShape {
+  // XY 平面上の正方形 (direction で回転する)
+  Quad,
+  // カメラを向いた円
+  #[default]
+  PointSprite,
+  // direction を向いた矢印
+  Arrow,
+}
+
+impl InstanceShape {
+  pub fn vertex_count(&self) -> u32 {
+    match self {
+      Self::Quad | Self::PointSprite => 6,
+      Self::Arrow => 9,
+    }
+  }
+
+  fn id(&self) -> u32 {
+    match self {
+      Self::Quad => 0,
+      Self::PointSprite => 1,
+      Self::Arrow => 2,
+    }
+  }
+}
+
+// draw_indirect に渡す引数のバッファ
+pub struct IndirectDraw {
+  buffer: wgpu::Buffer,
+}
+
+impl IndirectDraw {
+  // instance_count は 0 から始まる
+  pub fn new(device: &wgpu::Device, shape: InstanceShape) -> Self {
+    let args = wgpu::util::DrawIndirectArgs {
+      vertex_count: shape.vertex_count(),
+      instance_count: 0,
+      first_vertex: 0,
+      first_instance: 0,
+    };
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("[wgsim] indirect draw buffer"),
+      contents: args.as_bytes(),
+      usage: wgpu::BufferUsages::INDIRECT
+        | wgpu::BufferUsages::STORAGE
+        | wgpu::BufferUsages::COPY_DST
+        | wgpu::BufferUsages::COPY_SRC,
+    });
+
+    Self { buffer }
+  }
+
+  // 数え直す前に毎フレーム呼ぶ
+  pub fn reset(&self, encoder: &mut wgpu::CommandEncoder) {
+    let offset = std::mem::size_of::<u32>() as wgpu::BufferAddress;
+    encoder.clear_buffer(&self.buffer, offset, Some(offset));
+  }
+
+  // 別の形のパイプラインで描くときは vertex_count を書き換える
+  pub fn set_shape(&self, queue: &wgpu::Queue, shape: InstanceShape) {
+    queue.write_buffer(
+      &self.buffer,
+      0,
+      bytemuck::bytes_of(&shape.vertex_count()),
+    );
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    &self.buffer
+  }
+
+  pub fn as_binding_resource(&self) -> wgpu::BindingResource<'_> {
+    self.buffer.as_entire_binding()
+  }
+}
+
+// Quad / PointSprite / Arrow を大量に描くパイプライン
+// @group(0) にカメラ (CameraBinding)、@group(1) @binding(0) にインスタンスの
+// 配列 (storage_bind_group で作る) が入る
+pub struct InstancedPipeline {
+  pipeline: wgpu::RenderPipeline,
+  storage_layout: BindGroupLayout,
+  shape: InstanceShape,
+}
+
+impl InstancedPipeline {
+  pub fn shape(&self) -> InstanceShape {
+    self.shape
+  }
+
+  pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+    &self.pipeline
+  }
+
+  // コンピュートシェーダが書き込むバッファをコピーせずにそのまま読む
+  // (バッファには STORAGE が必要)
+  pub fn storage_bind_group(
+    &self,
+    device: &wgpu::Device,
+    buffer: &wgpu::Buffer,
+  ) -> wgpu::BindGroup {
+    BindGroupBuilder::new(device, &self.storage_layout)
+      .label("[wgsim] instance storage bind group")
+      .buffer(0, buffer)
+      .build()
+  }
+
+  pub fn draw(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: &wgpu::BindGroup,
+    range: Range<u32>,
+  ) {
+    self.bind(pass, camera, instances);
+    pass.draw(0..self.shape.vertex_count(), range);
+  }
+
+  // 描く数は indirect の instance_count (コンピュートシェーダで数える)
+  pub fn draw_indirect(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: &wgpu::BindGroup,
+    indirect: &IndirectDraw,
+  ) {
+    self.bind(pass, camera, instances);
+    pass.draw_indirect(indirect.buffer(), 0);
+  }
+
+  fn bind(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: &wgpu::BindGroup,
+  ) {
+    pass.set_pipeline(&self.pipeline);
+    pass.set_bind_group(0, camera, &[]);
+    pass.set_bind_group(1, instances, &[]);
+  }
+}
+
+// インスタンスを頂点バッファ (Instance ステップ) から読むパイプライン
+// InstancedPipelineBuilder::build_vertex で作る
+pub struct VertexInstancedPipeline {
+  pipeline: wgpu::RenderPipeline,
+  shape: InstanceShape,
+}
+
+impl VertexInstancedPipeline {
+  pub fn shape(&self) -> InstanceShape {
+    self.shape
+  }
+
+  pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+    &self.pipeline
+  }
+
+  pub fn draw(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: wgpu::BufferSlice,
+    range: Range<u32>,
+  ) {
+    self.bind(pass, camera, instances);
+    pass.draw(0..self.shape.vertex_count(), range);
+  }
+
+  pub fn draw_indirect(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: wgpu::BufferSlice,
+    indirect: &IndirectDraw,
+  ) {
+    self.bind(pass, camera, instances);
+    pass.draw_indirect(indirect.buffer(), 0);
+  }
+
+  fn bind(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: &wgpu::BindGroup,
+    instances: wgpu::BufferSlice,
+  ) {
+    pass.set_pipeline(&self.pipeline);
+    pass.set_bind_group(0, camera, &[]);
+    pass.set_vertex_buffer(0, instances);
+  }
+}
+
+// instance_wgsl にはインスタンスの型と、それを InstanceAttributes に変換する関数
+// fn instance_attributes(instance: Instance) -> InstanceAttributes を書く
+// (Camera と InstanceAttributes の宣言は自動で連結される)
+pub struct InstancedPipelineBuilder<'a> {
+  ctx: &'a DrawingContext<'a>,
+  shape: InstanceShape,
+  instance_wgsl: &'a str,
+  instance_type: &'a str,
+  camera_layout: &'a wgpu::BindGroupLayout,
+  fs_entry: &'a str,
+  format: wgpu::TextureFormat,
+  blend: Option<wgpu::BlendState>,
+  depth: bool,
+}
+
+impl<'a> InstancedPipelineBuilder<'a> {
+  pub fn new(
+    ctx: &'a DrawingContext<'a>,
+    shape: InstanceShape,
+    instance_wgsl: &'a str,
+    camera_layout: &'a wgpu::BindGroupLayout,
+  ) -> Self {
+    Self {
+      ctx,
+      shape,
+      instance_wgsl,
+      instance_type: "Instance",
+      camera_layout,
+      fs_entry: "fs_main",
+      format: ctx.format(),
+      blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+      depth: false,
+    }
+  }
+
+  // instance_wgsl で宣言した型の名前 (既定は Instance)
+  pub fn instance_type(mut self, name: &'a str) -> Self {
+    self.instance_type = name;
+    self
+  }
+
+  // instance_wgsl に書いたフラグメントシェーダを使う (入力は InstanceOutput)
+  pub fn fs_entry(mut self, entry: &'a str) -> Self {
+    self.fs_entry = entry;
+    self
+  }
+
+  pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  pub fn blend(mut self, blend: Option<wgpu::BlendState>) -> Self {
+    self.blend = blend;
+    self
+  }
+
+  // Depth24Plus の深度バッファを使う
+  pub fn depth(mut self) -> Self {
+    self.depth = true;
+    self
+  }
+
+  // インスタンスをストレージバッファから読む
+  pub fn build(self) -> InstancedPipeline {
+    let storage_layout = BindGroupLayoutBuilder::new(&self.ctx.device)
+      .label("[wgsim] instance storage bind group layout")
+      .storage(0, wgpu::ShaderStages::VERTEX, true)
+      .build();
+
+    let entry = format!(
+      "
+@group(1) @binding(0) var<storage, read> instances: array<{ty}>;
+
+@vertex
+fn vs_main(
+  @builtin(vertex_index) v: u32,
+  @builtin(instance_index) i: u32,
+) -> InstanceOutput {{
+  return instance_vertex(v, instance_attributes(instances[i]));
+}}
+",
+      ty = self.instance_type
+    );
+
+    InstancedPipeline {
+      pipeline: self.render_pipeline(&entry, Some(&storage_layout.layout), &[]),
+      storage_layout,
+      shape: self.shape,
+    }
+  }
+
+  // ストレージバッファではなく頂点バッファ (Instance ステップ) から読む
+  // 型のフィールドには @location を付ける (#[derive(Vertex)] の wgsl_struct が使える)
+  pub fn build_vertex(
+    self,
+    layout: wgpu::VertexBufferLayout,
+  ) -> VertexInstancedPipeline {
+    let entry = format!(
+      "
+@vertex
+fn vs_main(@builtin(vertex_index) v: u32, instance: {ty}) -> InstanceOutput {{
+  return instance_vertex(v, instance_attributes(instance));
+}}
+",
+      ty = self.instance_type
+    );
+    let layout = wgpu::VertexBufferLayout {
+      step_mode: wgpu::VertexStepMode::Instance,
+      ..layout
+    };
+
+    VertexInstancedPipeline {
+      pipeline: self.render_pipeline(&entry, None, &[layout]),
+      shape: self.shape,
+    }
+  }
+
+  fn render_pipeline(
+    &self,
+    entry: &str,
+    storage_layout: Option<&wgpu::BindGroupLayout>,
+    vertex_buffer_layout: &[wgpu::VertexBufferLayout],
+  ) -> wgpu::RenderPipeline {
+    let device = &self.ctx.device;
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("[wgsim] instancing shader"),
+      source: wgpu::ShaderSource::Wgsl(
+        format!(
+          "{}\n{}\n{}\n{}",
+          CAMERA_WGSL, INSTANCING_WGSL, self.instance_wgsl, entry
+        )
+        .into(),
+      ),
+    });
+
+    let mut bind_group_layouts = vec![self.camera_layout];
+    bind_group_layouts.extend(storage_layout);
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("[wgsim] instancing pipeline layout"),
+        bind_group_layouts: &bind_group_layouts,
+        push_constant_ranges: &[],
+      });
+
+    let mut builder = RenderPipelineBuilder::new(self.ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, self.fs_entry)
+      .pipeline_layout(&pipeline_layout)
+      .vertex_buffer_layout(vertex_buffer_layout)
+      .targets(vec![Some(wgpu::ColorTargetState {
+        format: self.format,
+        blend: self.blend,
+        write_mask: wgpu::ColorWrites::ALL,
+      })])
+      .constant("SHAPE", self.shape.id() as f64);
+    if self.depth {
+      builder = builder.enable_depth_stencil(None);
+    }
+
+    builder.build()
+  }
+}
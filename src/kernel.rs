@@ -0,0 +1,446 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::ppl::ComputePipelineBuilder;
+use crate::shader::{self, ShaderError};
+
+// dispatch_linear で y, z に折り返した場合も含めた通し番号
+// workgroup_size_x はエントリポイントの @workgroup_size の x
+pub const LINEAR_INDEX_WGSL: &str = "
+fn linear_index(
+  global_id: vec3u,
+  num_workgroups: vec3u,
+  workgroup_size_x: u32,
+) -> u32 {
+  let row = num_workgroups.x * workgroup_size_x;
+  return global_id.x + (global_id.y + global_id.z * num_workgroups.y) * row;
+}
+";
+
+#[derive(Debug)]
+pub enum KernelError {
+  Shader(ShaderError),
+  Parse(naga::front::wgsl::ParseError),
+  EntryPointNotFound(String),
+  // 1 次元あたりのワークグループ数が max_compute_workgroups_per_dimension を超えた
+  TooManyWorkgroups { workgroups: [u64; 3], limit: u32 },
+  // dispatch_linear は @workgroup_size(x, 1, 1) のカーネルにだけ使える
+  NotLinear([u32; 3]),
+}
+
+impl std::fmt::Display for KernelError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Shader(e) => write!(f, "{}", e),
+      Self::Parse(e) => write!(f, "failed to parse WGSL: {}", e),
+      Self::EntryPointNotFound(entry) => {
+        write!(f, "no compute entry point named '{}'", entry)
+      }
+      Self::TooManyWorkgroups { workgroups, limit } => write!(
+        f,
+        "{:?} workgroups exceed the limit of {} per dimension",
+        workgroups, limit
+      ),
+      Self::NotLinear(size) => write!(
+        f,
+        "dispatch_linear needs @workgroup_size(x, 1, 1), got {:?}",
+        size
+      ),
+    }
+  }
+}
+
+impl std::error::Error for KernelError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Shader(e) => Some(e),
+      Self::Parse(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<ShaderError> for KernelError {
+  fn from(e: ShaderError) -> Self {
+    Self::Shader(e)
+  }
+}
+
+// エントリポイントの @workgroup_size を読み取る
+// override を含む場合は specialize 済みのソースを渡す必要がある
+pub fn reflect_workgroup_size(
+  source: &str,
+  entry: &str,
+) -> Result<[u32; 3], KernelError> {
+  let module =
+    naga::front::wgsl::parse_str(source).map_err(KernelError::Parse)?;
+
+  module
+    .entry_points
+    .iter()
+    .find(|ep| ep.name == entry && ep.stage == naga::ShaderStage::Compute)
+    .map(|ep| ep.workgroup_size)
+    .ok_or_else(|| KernelError::EntryPointNotFound(entry.to_string()))
+}
+
+// WGSL のソースからパイプラインを作り、同じソースから @workgroup_size を読み取る
+// 定数は specialize で埋め込むので、@workgroup_size に override を使える
+pub struct ComputeKernelBuilder<'a> {
+  device: &'a wgpu::Device,
+  source: &'a str,
+  entry: &'a str,
+  pipeline_layout: Option<&'a wgpu::PipelineLayout>,
+  constants: HashMap<String, f64>,
+}
+
+impl<'a> ComputeKernelBuilder<'a> {
+  pub fn new(
+    device: &'a wgpu::Device,
+    source: &'a str,
+    entry: &'a str,
+  ) -> Self {
+    Self {
+      device,
+      source,
+      entry,
+      pipeline_layout: None,
+      constants: HashMap::new(),
+    }
+  }
+
+  pub fn pipeline_layout(mut self, layout: &'a wgpu::PipelineLayout) -> Self {
+    self.pipeline_layout = Some(layout);
+    self
+  }
+
+  pub fn constant(mut self, name: &str, value: f64) -> Self {
+    self.constants.insert(name.to_string(), value);
+    self
+  }
+
+  pub fn constants(mut self, constants: &[(&str, f64)]) -> Self {
+    for (name, value) in constants {
+      self.constants.insert(name.to_string(), *value);
+    }
+    self
+  }
+
+  pub fn build(&self) -> Result<ComputeKernel, KernelError> {
+    let source = match self.constants.is_empty() {
+      true => Cow::Borrowed(self.source),
+      false => Cow::Owned(shader::specialize(self.source, &self.constants)?),
+    };
+    let workgroup_size = reflect_workgroup_size(&source, self.entry)?;
+
+    let module =
+      self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("[wgsim] compute kernel shader"),
+        source: wgpu::ShaderSource::Wgsl(source),
+      });
+    let mut builder =
+      ComputePipelineBuilder::new(self.device).cs_shader(&module, self.entry);
+    if let Some(layout) = self.pipeline_layout {
+      builder = builder.pipeline_layout(layout);
+    }
+
+    Ok(ComputeKernel::new(
+      self.device,
+      builder.build(),
+      workgroup_size,
+    ))
+  }
+}
+
+// total 個のワークグループを各次元 limit 以下に収まるよう x, y, z に分ける
+fn fold_workgroups(total: u64, limit: u64) -> [u64; 3] {
+  let z = total.div_ceil(limit * limit).max(1);
+  let y = total.div_ceil(limit * z).max(1);
+  let x = total.div_ceil(y * z);
+  [x, y, z]
+}
+
+// dispatch_workgroups ごとの設定
+#[derive(Default)]
+pub struct DispatchOptions<'a> {
+  bind_groups: Vec<(u32, &'a wgpu::BindGroup)>,
+  timestamp_writes: Option<wgpu::ComputePassTimestampWrites<'a>>,
+}
+
+impl<'a> DispatchOptions<'a> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // カーネルが保持しているバインドグループに加えて差し込む
+  pub fn bind_group(
+    mut self,
+    index: u32,
+    bind_group: &'a wgpu::BindGroup,
+  ) -> Self {
+    self.bind_groups.push((index, bind_group));
+    self
+  }
+
+  // GpuProfiler::compute_pass_timestamps() の結果を渡すとパスの時間を計測できる
+  pub fn timestamp_writes(
+    mut self,
+    timestamp_writes: Option<wgpu::ComputePassTimestampWrites<'a>>,
+  ) -> Self {
+    self.timestamp_writes = timestamp_writes;
+    self
+  }
+}
+
+pub struct ComputeKernel {
+  pipeline: Arc<wgpu::ComputePipeline>,
+  workgroup_size: [u32; 3],
+  max_workgroups: u32,
+  bind_groups: Vec<Option<wgpu::BindGroup>>,
+}
+
+impl ComputeKernel {
+  // workgroup_size はパイプラインのものと一致している必要がある
+  // WGSL から作る場合は ComputeKernelBuilder を使う
+  pub fn new(
+    device: &wgpu::Device,
+    pipeline: impl Into<Arc<wgpu::ComputePipeline>>,
+    workgroup_size: [u32; 3],
+  ) -> Self {
+    assert!(
+      workgroup_size.iter().all(|&n| n > 0),
+      "[wgsim] workgroup size must be non-zero: {:?}",
+      workgroup_size
+    );
+
+    Self {
+      pipeline: pipeline.into(),
+      workgroup_size,
+      max_workgroups: device.limits().max_compute_workgroups_per_dimension,
+      bind_groups: Vec::new(),
+    }
+  }
+
+  // 自動レイアウト、定数なしの場合の省略形
+  pub fn from_wgsl(
+    device: &wgpu::Device,
+    source: &str,
+    entry: &str,
+  ) -> Result<Self, KernelError> {
+    ComputeKernelBuilder::new(device, source, entry).build()
+  }
+
+  pub fn with_bind_group(
+    mut self,
+    index: u32,
+    bind_group: wgpu::BindGroup,
+  ) -> Self {
+    self.set_bind_group(index, bind_group);
+    self
+  }
+
+  pub fn set_bind_group(&mut self, index: u32, bind_group: wgpu::BindGroup) {
+    let index = index as usize;
+    if self.bind_groups.len() <= index {
+      self.bind_groups.resize_with(index + 1, || None);
+    }
+    self.bind_groups[index] = Some(bind_group);
+  }
+
+  pub fn pipeline(&self) -> &wgpu::ComputePipeline {
+    &self.pipeline
+  }
+
+  pub fn workgroup_size(&self) -> [u32; 3] {
+    self.workgroup_size
+  }
+
+  pub fn workgroup_count(
+    &self,
+    problem_size: [u32; 3],
+  ) -> Result<[u32; 3], KernelError> {
+    let workgroups =
+      [0, 1, 2].map(|i| problem_size[i].div_ceil(self.workgroup_size[i]));
+    self.check_workgroups(workgroups.map(u64::from))?;
+    Ok(workgroups)
+  }
+
+  // n 個を 1 次元で処理するワークグループ数
+  // x が上限を超える分は y, z に折り返すので、シェーダー側は
+  // LINEAR_INDEX_WGSL の linear_index で通し番号を求め、n 以上を弾く
+  pub fn workgroup_count_linear(
+    &self,
+    n: u32,
+  ) -> Result<[u32; 3], KernelError> {
+    let [size_x, size_y, size_z] = self.workgroup_size;
+    if size_y != 1 || size_z != 1 {
+      return Err(KernelError::NotLinear(self.workgroup_size));
+    }
+
+    let workgroups =
+      fold_workgroups(n.div_ceil(size_x) as u64, self.max_workgroups as u64);
+    self.check_workgroups(workgroups)?;
+
+    Ok(workgroups.map(|n| n as u32))
+  }
+
+  fn check_workgroups(&self, workgroups: [u64; 3]) -> Result<(), KernelError> {
+    if workgroups.iter().any(|&n| n > self.max_workgroups as u64) {
+      return Err(KernelError::TooManyWorkgroups {
+        workgroups,
+        limit: self.max_workgroups,
+      });
+    }
+    Ok(())
+  }
+
+  // シェーダーが global_invocation_id.x を添字にしている場合
+  // 上限 (通常 65535 ワークグループ) を超えるとエラーになる
+  pub fn dispatch_1d(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    n: u32,
+  ) -> Result<(), KernelError> {
+    self.dispatch_3d(encoder, [n, 1, 1])
+  }
+
+  // 上限を超える n も扱える 1 次元のディスパッチ (workgroup_count_linear を参照)
+  pub fn dispatch_linear(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    n: u32,
+  ) -> Result<(), KernelError> {
+    let workgroups = self.workgroup_count_linear(n)?;
+    self.dispatch_workgroups(encoder, workgroups, DispatchOptions::new());
+    Ok(())
+  }
+
+  pub fn dispatch_2d(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    problem_size: [u32; 2],
+  ) -> Result<(), KernelError> {
+    self.dispatch_3d(encoder, [problem_size[0], problem_size[1], 1])
+  }
+
+  pub fn dispatch_3d(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    problem_size: [u32; 3],
+  ) -> Result<(), KernelError> {
+    let workgroups = self.workgroup_count(problem_size)?;
+    self.dispatch_workgroups(encoder, workgroups, DispatchOptions::new());
+    Ok(())
+  }
+
+  pub fn dispatch_indirect(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    indirect_buffer: &wgpu::Buffer,
+    indirect_offset: wgpu::BufferAddress,
+  ) {
+    let mut pass = self.begin_pass(encoder, &[], None);
+    pass.dispatch_workgroups_indirect(indirect_buffer, indirect_offset);
+  }
+
+  // 0 を含む場合は何もしない
+  pub fn dispatch_workgroups(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    workgroups: [u32; 3],
+    options: DispatchOptions,
+  ) {
+    if workgroups.contains(&0) {
+      return;
+    }
+
+    let mut pass =
+      self.begin_pass(encoder, &options.bind_groups, options.timestamp_writes);
+    pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+  }
+
+  fn begin_pass<'e>(
+    &self,
+    encoder: &'e mut wgpu::CommandEncoder,
+    bind_groups: &[(u32, &wgpu::BindGroup)],
+    timestamp_writes: Option<wgpu::ComputePassTimestampWrites>,
+  ) -> wgpu::ComputePass<'e> {
+    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+      label: Some("[wgsim] compute pass"),
+      timestamp_writes,
+    });
+
+    pass.set_pipeline(&self.pipeline);
+    for (index, bind_group) in self.bind_groups.iter().enumerate() {
+      if let Some(bind_group) = bind_group {
+        pass.set_bind_group(index as u32, bind_group, &[]);
+      }
+    }
+    for (index, bind_group) in bind_groups {
+      pass.set_bind_group(*index, *bind_group, &[]);
+    }
+
+    pass
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SOURCE: &str = "
+    @compute @workgroup_size(64, 2)
+    fn cs_main() {}
+
+    @vertex
+    fn vs_main() -> @builtin(position) vec4f {
+      return vec4f();
+    }
+  ";
+
+  #[test]
+  fn reflects_workgroup_size() {
+    assert_eq!(
+      reflect_workgroup_size(SOURCE, "cs_main").unwrap(),
+      [64, 2, 1]
+    );
+  }
+
+  #[test]
+  fn reports_missing_compute_entry_point() {
+    for entry in ["missing", "vs_main"] {
+      let error = reflect_workgroup_size(SOURCE, entry).unwrap_err();
+      assert!(
+        matches!(error, KernelError::EntryPointNotFound(e) if e == entry)
+      );
+    }
+  }
+
+  #[test]
+  fn reports_parse_error() {
+    let error =
+      reflect_workgroup_size("fn cs_main( {}", "cs_main").unwrap_err();
+    assert!(matches!(error, KernelError::Parse(_)));
+    assert!(error.to_string().starts_with("failed to parse WGSL"));
+  }
+
+  #[test]
+  fn fold_keeps_small_counts_in_x() {
+    assert_eq!(fold_workgroups(0, 65535), [0, 1, 1]);
+    assert_eq!(fold_workgroups(1000, 65535), [1000, 1, 1]);
+    assert_eq!(fold_workgroups(65535, 65535), [65535, 1, 1]);
+  }
+
+  #[test]
+  fn fold_covers_every_workgroup_within_limit() {
+    // 10M 個を 64 スレッドずつ処理する場合
+    let total = 10_000_000u64.div_ceil(64);
+    for (total, limit) in [(total, 65535), (65536, 65535), (1 << 40, 65535)] {
+      let [x, y, z] = fold_workgroups(total, limit);
+      assert!(x <= limit && y <= limit && z <= limit);
+      assert!(x * y * z >= total);
+      // 折り返した最後の行以外は空にならない
+      assert!(x * y * z - total < x * y);
+    }
+  }
+}
@@ -0,0 +1,233 @@
+use crate::primitive::{Color, Mat3, Mat4, Point, Size, Vec2, Vec3, Vec4};
+
+// derive マクロが生成するコードから参照する
+#[doc(hidden)]
+pub mod __private {
+  pub use wgpu;
+}
+
+// 頂点バッファの並びをパイプラインに伝える
+// #[derive(Vertex)] (derive フィーチャー) で実装できる
+pub trait VertexLayout: bytemuck::Pod {
+  const ATTRIBUTES: &'static [wgpu::VertexAttribute];
+  const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+
+  fn layout() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+      array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+      step_mode: Self::STEP_MODE,
+      attributes: Self::ATTRIBUTES,
+    }
+  }
+}
+
+// 頂点属性にできるフィールドの型
+pub trait VertexAttributeType {
+  const FORMAT: wgpu::VertexFormat;
+}
+
+macro_rules! impl_vertex_attribute_type {
+  ($($t:ty => $format:ident),* $(,)?) => {
+    $(
+      impl VertexAttributeType for $t {
+        const FORMAT: wgpu::VertexFormat = wgpu::VertexFormat::$format;
+      }
+    )*
+  };
+}
+
+// u8 / u16 は整数として読む (正規化する場合は #[vertex(format = "Unorm8x4")])
+impl_vertex_attribute_type!(
+  f32 => Float32,
+  [f32; 2] => Float32x2,
+  [f32; 3] => Float32x3,
+  [f32; 4] => Float32x4,
+  u32 => Uint32,
+  [u32; 2] => Uint32x2,
+  [u32; 3] => Uint32x3,
+  [u32; 4] => Uint32x4,
+  i32 => Sint32,
+  [i32; 2] => Sint32x2,
+  [i32; 3] => Sint32x3,
+  [i32; 4] => Sint32x4,
+  [u16; 2] => Uint16x2,
+  [u16; 4] => Uint16x4,
+  [u8; 2] => Uint8x2,
+  [u8; 4] => Uint8x4,
+  Vec2 => Float32x2,
+  Vec3 => Float32x3,
+  Vec4 => Float32x4,
+  Point<f32> => Float32x2,
+  Size<f32> => Float32x2,
+  Color => Float32x4,
+);
+
+// シェーダで受け取るときの WGSL の型
+pub fn vertex_format_wgsl(format: wgpu::VertexFormat) -> &'static str {
+  use wgpu::VertexFormat as F;
+
+  match format {
+    F::Float32 => "f32",
+    F::Uint32 => "u32",
+    F::Sint32 => "i32",
+    F::Float32x2
+    | F::Float16x2
+    | F::Unorm8x2
+    | F::Snorm8x2
+    | F::Unorm16x2
+    | F::Snorm16x2 => "vec2f",
+    F::Float32x3 => "vec3f",
+    F::Float32x4
+    | F::Float16x4
+    | F::Unorm8x4
+    | F::Snorm8x4
+    | F::Unorm16x4
+    | F::Snorm16x4
+    | F::Unorm10_10_10_2 => "vec4f",
+    F::Uint32x2 | F::Uint8x2 | F::Uint16x2 => "vec2u",
+    F::Uint32x3 => "vec3u",
+    F::Uint32x4 | F::Uint8x4 | F::Uint16x4 => "vec4u",
+    F::Sint32x2 | F::Sint8x2 | F::Sint16x2 => "vec2i",
+    F::Sint32x3 => "vec3i",
+    F::Sint32x4 | F::Sint8x4 | F::Sint16x4 => "vec4i",
+    // WGSL には f64 がない
+    F::Float64 | F::Float64x2 | F::Float64x3 | F::Float64x4 => panic!(
+      "[wgsim] {:?} cannot be read by a WGSL vertex shader, use a Float32 \
+       format instead",
+      format
+    ),
+  }
+}
+
+// WGSL のメモリレイアウト (アラインメントとサイズ) が分かる型
+// #[derive(ShaderType)] (derive フィーチャー) で構造体にも実装できる
+pub trait ShaderType: bytemuck::Pod {
+  const ALIGN: usize;
+  const SIZE: usize;
+  const WGSL_TYPE: &'static str;
+  // uniform アドレス空間でメンバーにする場合に必要なオフセットの倍数
+  // (構造体は 16 に切り上げる)
+  const UNIFORM_ALIGN: usize = Self::ALIGN;
+  // uniform アドレス空間の追加の規則も満たすか
+  const UNIFORM_COMPATIBLE: bool = true;
+}
+
+macro_rules! impl_shader_type {
+  ($($t:ty => ($align:literal, $size:literal, $wgsl:literal)),* $(,)?) => {
+    $(
+      impl ShaderType for $t {
+        const ALIGN: usize = $align;
+        const SIZE: usize = $size;
+        const WGSL_TYPE: &'static str = $wgsl;
+      }
+    )*
+  };
+}
+
+impl_shader_type!(
+  f32 => (4, 4, "f32"),
+  u32 => (4, 4, "u32"),
+  i32 => (4, 4, "i32"),
+  [f32; 2] => (8, 8, "vec2f"),
+  [f32; 3] => (16, 12, "vec3f"),
+  [f32; 4] => (16, 16, "vec4f"),
+  [u32; 2] => (8, 8, "vec2u"),
+  [u32; 3] => (16, 12, "vec3u"),
+  [u32; 4] => (16, 16, "vec4u"),
+  [i32; 2] => (8, 8, "vec2i"),
+  [i32; 3] => (16, 12, "vec3i"),
+  [i32; 4] => (16, 16, "vec4i"),
+  [[f32; 4]; 4] => (16, 64, "mat4x4f"),
+  Vec2 => (8, 8, "vec2f"),
+  Vec3 => (16, 12, "vec3f"),
+  Vec4 => (16, 16, "vec4f"),
+  Point<f32> => (8, 8, "vec2f"),
+  Size<f32> => (8, 8, "vec2f"),
+  Size<u32> => (8, 8, "vec2u"),
+  Color => (16, 16, "vec4f"),
+  Mat3 => (16, 48, "mat3x3f"),
+  Mat4 => (16, 64, "mat4x4f"),
+);
+
+// WGSL の struct 宣言を文字列で返す (シェーダのソースに連結して使う)
+pub trait WgslStruct {
+  fn wgsl_struct() -> String;
+}
+
+// UniformBuffer<T> にそのまま書き込めるか
+pub const fn is_uniform_layout<T: ShaderType>() -> bool {
+  T::UNIFORM_COMPATIBLE && T::SIZE == std::mem::size_of::<T>()
+}
+
+// StorageBuffer<T> の要素 (WGSL の配列の要素) にできるか
+pub const fn is_storage_layout<T: ShaderType>() -> bool {
+  round_up(T::SIZE, T::ALIGN) == std::mem::size_of::<T>()
+}
+
+pub const fn round_up(offset: usize, align: usize) -> usize {
+  offset.div_ceil(align) * align
+}
+
+pub const fn max_align(a: usize, b: usize) -> usize {
+  if a > b {
+    a
+  } else {
+    b
+  }
+}
+
+#[doc(hidden)]
+pub fn vertex_wgsl_struct(
+  name: &str,
+  fields: &[&str],
+  attributes: &[wgpu::VertexAttribute],
+) -> String {
+  let mut wgsl = format!("struct {} {{\n", name);
+  for (field, attribute) in fields.iter().zip(attributes) {
+    wgsl.push_str(&format!(
+      "  @location({}) {}: {},\n",
+      attribute.shader_location,
+      field,
+      vertex_format_wgsl(attribute.format)
+    ));
+  }
+  wgsl.push_str("}\n");
+  wgsl
+}
+
+// フィールドは (名前, WGSL の型, バイト数)、padding は型を None にして渡す
+// padding は直前のメンバーの @size に含める
+// (array<u32, N> は uniform では stride が 16 の倍数でないため使えず、
+// u32 のメンバーにすると入れ子の構造体の直後に置けない)
+#[doc(hidden)]
+pub fn shader_wgsl_struct(
+  name: &str,
+  fields: &[(&str, Option<&str>, usize)],
+) -> String {
+  // (名前, 型, サイズ, 後ろの padding)
+  let mut members: Vec<(&str, &str, usize, usize)> = Vec::new();
+  let mut leading = Vec::new();
+  for &(field, ty, size) in fields {
+    match (ty, members.last_mut()) {
+      (Some(ty), _) => members.push((field, ty, size, 0)),
+      (None, Some(last)) => last.3 += size,
+      // 先頭の padding だけは u32 のメンバーとして書き出す
+      (None, None) => leading.extend((0..size / 4).map(|i| (field, i))),
+    }
+  }
+
+  let mut wgsl = format!("struct {} {{\n", name);
+  for (field, i) in leading {
+    wgsl.push_str(&format!("  {}_{}: u32,\n", field, i));
+  }
+  for (field, ty, size, padding) in members {
+    if padding > 0 {
+      wgsl.push_str(&format!("  @size({}) ", size + padding));
+    } else {
+      wgsl.push_str("  ");
+    }
+    wgsl.push_str(&format!("{}: {},\n", field, ty));
+  }
+  wgsl.push_str("}\n");
+  wgsl
+}
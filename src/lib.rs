@@ -1,8 +1,37 @@
 pub mod app;
+pub mod camera;
+pub mod capture;
 pub mod ctx;
+pub mod effect;
+pub mod fullscreen;
 pub mod gif;
+pub mod graph;
+pub mod instancing;
+pub mod kernel;
+pub mod layout;
+pub mod mesh;
+pub mod ping_pong;
 pub mod ppl;
 pub mod primitive;
+pub mod profiler;
+pub mod readback;
+pub mod record;
 pub mod render;
+pub mod shader;
+pub mod snapshot;
+pub mod stats;
 pub mod surface_cfg;
+pub mod text;
+pub mod texture;
+pub mod tonemap;
+#[cfg(feature = "egui")]
+pub mod ui;
 pub mod util;
+pub mod verify;
+
+#[cfg(feature = "derive")]
+pub use wgsim_derive::{ShaderType, Vertex};
+
+// Render::ui で使う egui (バージョンを揃えるため wgsim から使う)
+#[cfg(feature = "egui")]
+pub use egui;
@@ -0,0 +1,765 @@
+use std::f32::consts::{PI, TAU};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use wgpu::util::DeviceExt;
+
+pub use crate::layout::VertexLayout;
+use crate::primitive::{Mat3, Mat4, Vec2, Vec3};
+
+// @location(0) position, @location(1) normal, @location(2) uv
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MeshVertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+  pub uv: [f32; 2],
+}
+
+impl MeshVertex {
+  pub fn new(position: Vec3, normal: Vec3, uv: Vec2) -> Self {
+    Self {
+      position: position.into(),
+      normal: normal.into(),
+      uv: uv.into(),
+    }
+  }
+}
+
+impl VertexLayout for MeshVertex {
+  const ATTRIBUTES: &'static [wgpu::VertexAttribute] =
+    &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+  Obj(PathBuf, tobj::LoadError),
+  Gltf(PathBuf, gltf::Error),
+  // 頂点座標を持たないプリミティブ
+  MissingPositions(String),
+  UnsupportedExtension(PathBuf),
+}
+
+impl std::fmt::Display for MeshError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Obj(path, e) => {
+        write!(f, "failed to load {}: {}", path.display(), e)
+      }
+      Self::Gltf(path, e) => {
+        write!(f, "failed to load {}: {}", path.display(), e)
+      }
+      Self::MissingPositions(name) => {
+        write!(f, "mesh '{}' has no vertex positions", name)
+      }
+      Self::UnsupportedExtension(path) => write!(
+        f,
+        "{} is not an OBJ (.obj) or glTF (.gltf, .glb) file",
+        path.display()
+      ),
+    }
+  }
+}
+
+impl std::error::Error for MeshError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Obj(_, e) => Some(e),
+      Self::Gltf(_, e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+// CPU 側の三角形リスト (反時計回りが表)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+  pub name: String,
+  pub vertices: Vec<MeshVertex>,
+  pub indices: Vec<u32>,
+}
+
+impl MeshData {
+  pub fn new(name: &str, vertices: Vec<MeshVertex>, indices: Vec<u32>) -> Self {
+    Self {
+      name: name.to_string(),
+      vertices,
+      indices,
+    }
+  }
+
+  // 拡張子で OBJ か glTF かを選ぶ
+  pub fn load(path: impl AsRef<Path>) -> Result<Vec<Self>, MeshError> {
+    let path = path.as_ref();
+    let ext = path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .map(str::to_ascii_lowercase);
+
+    match ext.as_deref() {
+      Some("obj") => Self::load_obj(path),
+      Some("gltf" | "glb") => Self::load_gltf(path),
+      _ => Err(MeshError::UnsupportedExtension(path.to_path_buf())),
+    }
+  }
+
+  // オブジェクト・グループごとに 1 つの MeshData になる
+  // マテリアルは読まない
+  pub fn load_obj(path: impl AsRef<Path>) -> Result<Vec<Self>, MeshError> {
+    let path = path.as_ref();
+    let (models, _materials) = tobj::load_obj(
+      path,
+      &tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true,
+      },
+    )
+    .map_err(|e| MeshError::Obj(path.to_path_buf(), e))?;
+
+    models
+      .into_iter()
+      .map(|model| {
+        let mesh = model.mesh;
+        if mesh.positions.is_empty() {
+          return Err(MeshError::MissingPositions(model.name));
+        }
+
+        let has_normals = !mesh.normals.is_empty();
+        let vertices = (0..mesh.positions.len() / 3)
+          .map(|i| MeshVertex {
+            position: [
+              mesh.positions[i * 3],
+              mesh.positions[i * 3 + 1],
+              mesh.positions[i * 3 + 2],
+            ],
+            normal: if has_normals {
+              [
+                mesh.normals[i * 3],
+                mesh.normals[i * 3 + 1],
+                mesh.normals[i * 3 + 2],
+              ]
+            } else {
+              [0.0; 3]
+            },
+            // OBJ の v は下から上なので、wgpu に合わせて反転する
+            uv: match mesh.texcoords.get(i * 2..i * 2 + 2) {
+              Some(&[u, v]) => [u, 1.0 - v],
+              _ => [0.0; 2],
+            },
+          })
+          .collect();
+
+        let mut data = Self::new(&model.name, vertices, mesh.indices);
+        if !has_normals {
+          data.compute_normals();
+        }
+        Ok(data)
+      })
+      .collect()
+  }
+
+  // 既定のシーン (なければ最初のシーン) のノードを辿り、
+  // 三角形のプリミティブごとにノードの変換を適用した MeshData を作る
+  pub fn load_gltf(path: impl AsRef<Path>) -> Result<Vec<Self>, MeshError> {
+    let path = path.as_ref();
+    let (document, buffers, _images) =
+      gltf::import(path).map_err(|e| MeshError::Gltf(path.to_path_buf(), e))?;
+
+    let mut meshes = Vec::new();
+    let scene = document.default_scene().or_else(|| document.scenes().next());
+    let mut stack: Vec<(gltf::Node, Mat4)> = scene
+      .iter()
+      .flat_map(|scene| scene.nodes())
+      .map(|node| (node, Mat4::IDENTITY))
+      .collect();
+
+    while let Some((node, parent)) = stack.pop() {
+      let transform = parent
+        * Mat4 {
+          cols: node.transform().matrix(),
+        };
+      stack.extend(node.children().map(|child| (child, transform)));
+
+      let Some(mesh) = node.mesh() else {
+        continue;
+      };
+      let mesh_name = mesh
+        .name()
+        .or(node.name())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("mesh{}", mesh.index()));
+
+      for (i, primitive) in mesh.primitives().enumerate() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+          continue;
+        }
+        let name = if mesh.primitives().len() > 1 {
+          format!("{}.{}", mesh_name, i)
+        } else {
+          mesh_name.clone()
+        };
+
+        let reader =
+          primitive.reader(|buffer| Some(&buffers[buffer.index()].0[..]));
+        let positions: Vec<[f32; 3]> = reader
+          .read_positions()
+          .ok_or_else(|| MeshError::MissingPositions(name.clone()))?
+          .collect();
+        let normals: Option<Vec<[f32; 3]>> =
+          reader.read_normals().map(|normals| normals.collect());
+        let uvs: Option<Vec<[f32; 2]>> =
+          reader.read_tex_coords(0).map(|uvs| uvs.into_f32().collect());
+        let indices: Vec<u32> = match reader.read_indices() {
+          Some(indices) => indices.into_u32().collect(),
+          None => (0..positions.len() as u32).collect(),
+        };
+
+        let vertices = positions
+          .iter()
+          .enumerate()
+          .map(|(i, &position)| MeshVertex {
+            position,
+            normal: normals.as_ref().map_or([0.0; 3], |n| n[i]),
+            uv: uvs.as_ref().map_or([0.0; 2], |uv| uv[i]),
+          })
+          .collect();
+
+        let mut data = Self::new(&name, vertices, indices);
+        data.transform(&transform);
+        if normals.is_none() {
+          data.compute_normals();
+        }
+        meshes.push(data);
+      }
+    }
+
+    Ok(meshes)
+  }
+
+  // 面の法線を頂点ごとに面積で重み付けして平均する
+  pub fn compute_normals(&mut self) {
+    let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+    for triangle in self.indices.chunks_exact(3) {
+      let [a, b, c] = [0, 1, 2]
+        .map(|i| Vec3::from(self.vertices[triangle[i] as usize].position));
+      let normal = (b - a).cross(c - a);
+      for &i in triangle {
+        normals[i as usize] += normal;
+      }
+    }
+
+    for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+      vertex.normal = normal.normalize().into();
+    }
+  }
+
+  // 裏返る変換 (行列式が負) の場合は三角形の向きも入れ替える
+  pub fn transform(&mut self, transform: &Mat4) {
+    let normal_matrix = Mat3::normal_matrix(transform);
+    for vertex in &mut self.vertices {
+      vertex.position =
+        transform.transform_point(vertex.position.into()).into();
+      vertex.normal =
+        (normal_matrix * Vec3::from(vertex.normal)).normalize().into();
+    }
+
+    if Mat3::from(*transform).determinant() < 0.0 {
+      for triangle in self.indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+      }
+    }
+  }
+
+  // (最小, 最大)、頂点がない場合は None
+  pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+    let mut positions = self.vertices.iter().map(|v| Vec3::from(v.position));
+    let first = positions.next()?;
+    Some(
+      positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p))),
+    )
+  }
+
+  // XZ 平面上の width x depth の格子 (上向き)
+  pub fn grid(width: f32, depth: f32, columns: u32, rows: u32) -> Self {
+    let (columns, rows) = (columns.max(1), rows.max(1));
+    let mut vertices =
+      Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+    for z in 0..=rows {
+      for x in 0..=columns {
+        let uv = Vec2::new(x as f32 / columns as f32, z as f32 / rows as f32);
+        let position =
+          Vec3::new((uv.x - 0.5) * width, 0.0, (uv.y - 0.5) * depth);
+        vertices.push(MeshVertex::new(position, Vec3::Y, uv));
+      }
+    }
+
+    let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+    for z in 0..rows {
+      for x in 0..columns {
+        let i = z * (columns + 1) + x;
+        let below = i + columns + 1;
+        indices.extend_from_slice(&[i, below, i + 1, i + 1, below, below + 1]);
+      }
+    }
+
+    Self::new("grid", vertices, indices)
+  }
+
+  // XZ 平面上の size x size の正方形
+  pub fn plane(size: f32) -> Self {
+    Self {
+      name: "plane".to_string(),
+      ..Self::grid(size, size, 1, 1)
+    }
+  }
+
+  // 原点を中心とした一辺 size の立方体 (面ごとに頂点を分ける)
+  pub fn cube(size: f32) -> Self {
+    let h = size * 0.5;
+    let faces = [
+      (Vec3::X, Vec3::Y),
+      (-Vec3::X, Vec3::Y),
+      (Vec3::Y, -Vec3::Z),
+      (-Vec3::Y, Vec3::Z),
+      (Vec3::Z, Vec3::Y),
+      (-Vec3::Z, Vec3::Y),
+    ];
+
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+    for (normal, up) in faces {
+      let right = up.cross(normal);
+      let base = vertices.len() as u32;
+      for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+        let position =
+          (normal + right * (u * 2.0 - 1.0) + up * (1.0 - v * 2.0)) * h;
+        vertices.push(MeshVertex::new(position, normal, Vec2::new(u, v)));
+      }
+      indices.extend_from_slice(&[
+        base,
+        base + 3,
+        base + 1,
+        base + 1,
+        base + 3,
+        base + 2,
+      ]);
+    }
+
+    Self::new("cube", vertices, indices)
+  }
+
+  // 経度方向に sectors、緯度方向に stacks 分割した UV 球
+  pub fn sphere(radius: f32, sectors: u32, stacks: u32) -> Self {
+    let (sectors, stacks) = (sectors.max(3), stacks.max(2));
+    let mut vertices =
+      Vec::with_capacity(((sectors + 1) * (stacks + 1)) as usize);
+    for stack in 0..=stacks {
+      let v = stack as f32 / stacks as f32;
+      let (sin_phi, cos_phi) = (v * PI).sin_cos();
+      for sector in 0..=sectors {
+        let u = sector as f32 / sectors as f32;
+        let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+        let normal =
+          Vec3::new(sin_phi * sin_theta, cos_phi, sin_phi * cos_theta);
+        vertices.push(MeshVertex::new(
+          normal * radius,
+          normal,
+          Vec2::new(u, v),
+        ));
+      }
+    }
+
+    let mut indices = Vec::with_capacity((sectors * stacks * 6) as usize);
+    for stack in 0..stacks {
+      for sector in 0..sectors {
+        let i = stack * (sectors + 1) + sector;
+        let below = i + sectors + 1;
+        // 極では三角形が潰れるので片方だけ
+        if stack != 0 {
+          indices.extend_from_slice(&[i, below, i + 1]);
+        }
+        if stack != stacks - 1 {
+          indices.extend_from_slice(&[i + 1, below, below + 1]);
+        }
+      }
+    }
+
+    Self::new("sphere", vertices, indices)
+  }
+}
+
+// GPU 上の頂点・インデックスバッファ
+#[derive(Debug)]
+pub struct Mesh {
+  pub name: String,
+  pub vertex_buffer: wgpu::Buffer,
+  pub index_buffer: wgpu::Buffer,
+  pub index_count: u32,
+}
+
+impl Mesh {
+  pub fn new(device: &wgpu::Device, data: &MeshData) -> Self {
+    Self::with_usage(device, data, wgpu::BufferUsages::empty())
+  }
+
+  // コンピュートシェーダで頂点を書き換える場合などに usage を足す
+  pub fn with_usage(
+    device: &wgpu::Device,
+    data: &MeshData,
+    usage: wgpu::BufferUsages,
+  ) -> Self {
+    let vertex_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("[wgsim] {} vertex buffer", data.name)),
+        contents: bytemuck::cast_slice(&data.vertices),
+        usage: wgpu::BufferUsages::VERTEX | usage,
+      });
+    let index_buffer =
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("[wgsim] {} index buffer", data.name)),
+        contents: bytemuck::cast_slice(&data.indices),
+        usage: wgpu::BufferUsages::INDEX | usage,
+      });
+
+    Self {
+      name: data.name.clone(),
+      vertex_buffer,
+      index_buffer,
+      index_count: data.indices.len() as u32,
+    }
+  }
+
+  pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+    MeshVertex::layout()
+  }
+
+  // 頂点バッファをスロット 0 に置いて描く
+  pub fn draw(&self, pass: &mut wgpu::RenderPass) {
+    self.draw_instanced(pass, 0..1);
+  }
+
+  pub fn draw_instanced(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    instances: Range<u32>,
+  ) {
+    pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+    pass
+      .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    pass.draw_indexed(0..self.index_count, 0, instances);
+  }
+}
+
+// ファイルから読み込んだメッシュの集まり
+#[derive(Debug)]
+pub struct Model {
+  pub meshes: Vec<Mesh>,
+  pub bounds: Option<(Vec3, Vec3)>,
+}
+
+impl Model {
+  pub fn load(
+    device: &wgpu::Device,
+    path: impl AsRef<Path>,
+  ) -> Result<Self, MeshError> {
+    Ok(Self::from_data(device, &MeshData::load(path)?))
+  }
+
+  pub fn from_data(device: &wgpu::Device, data: &[MeshData]) -> Self {
+    let bounds = data.iter().filter_map(MeshData::bounds).reduce(
+      |(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)),
+    );
+
+    Self {
+      meshes: data
+        .iter()
+        .filter(|data| !data.indices.is_empty())
+        .map(|data| Mesh::new(device, data))
+        .collect(),
+      bounds,
+    }
+  }
+
+  pub fn draw(&self, pass: &mut wgpu::RenderPass) {
+    for mesh in &self.meshes {
+      mesh.draw(pass);
+    }
+  }
+
+  pub fn draw_instanced(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    instances: Range<u32>,
+  ) {
+    for mesh in &self.meshes {
+      mesh.draw_instanced(pass, instances.clone());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_vec3_near(a: Vec3, b: Vec3) {
+    assert!((a - b).length() <= 1e-5, "{:?} != {:?}", a, b);
+  }
+
+  // 三角形ごとに、頂点の法線の側から見て反時計回りかを確かめる
+  fn assert_outward_ccw(mesh: &MeshData) {
+    assert_eq!(mesh.indices.len() % 3, 0);
+    for triangle in mesh.indices.chunks_exact(3) {
+      let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+      let [pa, pb, pc] = [a, b, c].map(|v| Vec3::from(v.position));
+      let face = (pb - pa).cross(pc - pa);
+      let normal =
+        [a, b, c].iter().fold(Vec3::ZERO, |sum, v| sum + Vec3::from(v.normal));
+      assert!(
+        face.dot(normal) > 0.0,
+        "{}: triangle {:?} is clockwise",
+        mesh.name,
+        triangle
+      );
+    }
+  }
+
+  fn assert_unit_normals(mesh: &MeshData) {
+    for vertex in &mesh.vertices {
+      let length = Vec3::from(vertex.normal).length();
+      assert!((length - 1.0).abs() < 1e-5, "{:?}", vertex);
+    }
+  }
+
+  #[test]
+  fn cube_faces_point_outward() {
+    let cube = MeshData::cube(2.0);
+    assert_eq!(cube.vertices.len(), 24);
+    assert_eq!(cube.indices.len(), 36);
+    assert_outward_ccw(&cube);
+    assert_unit_normals(&cube);
+
+    // 法線は面の中心から外を向く
+    for vertex in &cube.vertices {
+      let normal = Vec3::from(vertex.normal);
+      assert_eq!(Vec3::from(vertex.position).dot(normal), 1.0);
+    }
+  }
+
+  #[test]
+  fn sphere_faces_point_outward() {
+    let sphere = MeshData::sphere(2.0, 8, 4);
+    assert_eq!(sphere.vertices.len(), 9 * 5);
+    // 極の周りは 1 つずつ三角形が少ない
+    assert_eq!(sphere.indices.len(), (8 * 4 * 2 - 8 * 2) * 3);
+    assert_outward_ccw(&sphere);
+    assert_unit_normals(&sphere);
+
+    for vertex in &sphere.vertices {
+      let position = Vec3::from(vertex.position);
+      assert_vec3_near(position, Vec3::from(vertex.normal) * 2.0);
+    }
+
+    // 分割数には下限がある
+    let sphere = MeshData::sphere(1.0, 0, 0);
+    assert_eq!(sphere.vertices.len(), 4 * 3);
+    assert_outward_ccw(&sphere);
+  }
+
+  #[test]
+  fn grid_faces_up() {
+    let grid = MeshData::grid(4.0, 2.0, 3, 2);
+    assert_eq!(grid.vertices.len(), 4 * 3);
+    assert_eq!(grid.indices.len(), 3 * 2 * 6);
+    assert_outward_ccw(&grid);
+    assert_unit_normals(&grid);
+    assert_eq!(
+      grid.bounds(),
+      Some((Vec3::new(-2.0, 0.0, -1.0), Vec3::new(2.0, 0.0, 1.0)))
+    );
+
+    let plane = MeshData::plane(1.0);
+    assert_eq!(plane.name, "plane");
+    assert_eq!(plane.indices.len(), 6);
+  }
+
+  #[test]
+  fn compute_normals_matches_faces() {
+    let cube = MeshData::cube(1.0);
+    let mut computed = cube.clone();
+    for vertex in &mut computed.vertices {
+      vertex.normal = [0.0; 3];
+    }
+    computed.compute_normals();
+    for (a, b) in computed.vertices.iter().zip(&cube.vertices) {
+      assert_vec3_near(a.normal.into(), b.normal.into());
+    }
+
+    // 共有する頂点では面の法線が面積で重み付けされる
+    let mut mesh = MeshData::new(
+      "fold",
+      [
+        Vec3::ZERO,
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(0.0, 0.0, -2.0),
+        Vec3::new(0.0, 1.0, 0.0),
+      ]
+      .map(|p| MeshVertex::new(p, Vec3::ZERO, Vec2::ZERO))
+      .to_vec(),
+      vec![0, 1, 2, 0, 1, 3],
+    );
+    mesh.compute_normals();
+    assert_vec3_near(
+      mesh.vertices[0].normal.into(),
+      Vec3::new(0.0, 2.0, 1.0).normalize(),
+    );
+    assert_vec3_near(mesh.vertices[2].normal.into(), Vec3::Y);
+    assert_vec3_near(mesh.vertices[3].normal.into(), Vec3::Z);
+  }
+
+  #[test]
+  fn mirrored_transform_flips_winding() {
+    let cube = MeshData::cube(2.0);
+    let mut mirrored = cube.clone();
+    mirrored.transform(&Mat4::scale(Vec3::new(-1.0, 1.0, 1.0)));
+    assert_ne!(mirrored.indices, cube.indices);
+    assert_outward_ccw(&mirrored);
+    assert_unit_normals(&mirrored);
+
+    // 裏返らない変換では順番を変えない
+    let mut moved = cube.clone();
+    let transform = Mat4::translation(Vec3::new(1.0, 2.0, 3.0))
+      * Mat4::rotation_y(0.5)
+      * Mat4::scale(Vec3::new(2.0, 1.0, 1.0));
+    moved.transform(&transform);
+    assert_eq!(moved.indices, cube.indices);
+    assert_outward_ccw(&moved);
+    assert_unit_normals(&moved);
+  }
+
+  #[test]
+  fn bounds_cover_all_vertices() {
+    assert_eq!(MeshData::default().bounds(), None);
+
+    let mut cube = MeshData::cube(2.0);
+    assert_eq!(cube.bounds(), Some((-Vec3::ONE, Vec3::ONE)));
+    cube.transform(&Mat4::translation(Vec3::new(1.0, 0.0, -1.0)));
+    let (min, max) = cube.bounds().unwrap();
+    assert_vec3_near(min, Vec3::new(0.0, -1.0, -2.0));
+    assert_vec3_near(max, Vec3::new(2.0, 1.0, 0.0));
+  }
+
+  // テストごとに空のディレクトリを用意する
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+      "wgsim-mesh-{}-{}",
+      name,
+      std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn load_rejects_unknown_extensions() {
+    for path in ["model.fbx", "model"] {
+      match MeshData::load(path) {
+        Err(MeshError::UnsupportedExtension(p)) => {
+          assert_eq!(p, Path::new(path))
+        }
+        result => panic!("unexpected result: {:?}", result),
+      }
+    }
+  }
+
+  #[test]
+  fn load_obj_computes_missing_normals() {
+    let dir = temp_dir("obj");
+    let path = dir.join("quad.OBJ");
+    std::fs::write(
+      &path,
+      "o quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3 4/4
+",
+    )
+    .unwrap();
+
+    let meshes = MeshData::load(&path).unwrap();
+    assert_eq!(meshes.len(), 1);
+    let quad = &meshes[0];
+    assert_eq!(quad.name, "quad");
+    assert_eq!(quad.vertices.len(), 4);
+    assert_eq!(quad.indices.len(), 6);
+    assert_outward_ccw(quad);
+    for vertex in &quad.vertices {
+      assert_vec3_near(vertex.normal.into(), Vec3::Z);
+    }
+    // v は上下反転する
+    assert_eq!(quad.vertices[0].uv, [0.0, 1.0]);
+    assert_eq!(quad.vertices[2].uv, [1.0, 0.0]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn load_gltf_applies_node_transforms() {
+    let dir = temp_dir("gltf");
+    let positions: [[f32; 3]; 3] =
+      [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    std::fs::write(dir.join("triangle.bin"), bytemuck::cast_slice(&positions))
+      .unwrap();
+    // 親で x を反転し、子で z に移動する
+    let path = dir.join("triangle.gltf");
+    std::fs::write(
+      &path,
+      r#"{
+  "asset": { "version": "2.0" },
+  "scene": 0,
+  "scenes": [{ "nodes": [0] }],
+  "nodes": [
+    { "name": "mirror", "scale": [-1, 1, 1], "children": [1] },
+    { "name": "moved", "translation": [0, 0, 2], "mesh": 0 }
+  ],
+  "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+  "buffers": [{ "uri": "triangle.bin", "byteLength": 36 }],
+  "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+  "accessors": [{
+    "bufferView": 0,
+    "componentType": 5126,
+    "count": 3,
+    "type": "VEC3",
+    "min": [0, 0, 0],
+    "max": [1, 1, 0]
+  }]
+}"#,
+    )
+    .unwrap();
+
+    let meshes = MeshData::load(&path).unwrap();
+    assert_eq!(meshes.len(), 1);
+    let triangle = &meshes[0];
+    // メッシュに名前がなければノードの名前を使う
+    assert_eq!(triangle.name, "moved");
+    assert_eq!(triangle.indices, [0, 2, 1]);
+    assert_outward_ccw(triangle);
+    assert_eq!(
+      triangle.bounds(),
+      Some((Vec3::new(-1.0, 0.0, 2.0), Vec3::new(0.0, 1.0, 2.0)))
+    );
+    for vertex in &triangle.vertices {
+      assert_vec3_near(vertex.normal.into(), Vec3::Z);
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,180 @@
+use crate::kernel::{ComputeKernel, DispatchOptions, KernelError};
+use crate::primitive::Size;
+use crate::util;
+
+pub trait PingPongResource {
+  fn binding_resource(&self) -> wgpu::BindingResource<'_>;
+}
+
+impl PingPongResource for wgpu::Buffer {
+  fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+    self.as_entire_binding()
+  }
+}
+
+pub struct StorageTexture {
+  pub texture: wgpu::Texture,
+  pub view: wgpu::TextureView,
+}
+
+impl StorageTexture {
+  pub fn new(
+    device: &wgpu::Device,
+    size: Size,
+    format: wgpu::TextureFormat,
+  ) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("[wgsim] ping-pong storage texture"),
+      size: wgpu::Extent3d {
+        width: size.width,
+        height: size.height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage: wgpu::TextureUsages::STORAGE_BINDING
+        | wgpu::TextureUsages::TEXTURE_BINDING
+        | wgpu::TextureUsages::COPY_DST
+        | wgpu::TextureUsages::COPY_SRC,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    Self { texture, view }
+  }
+}
+
+impl PingPongResource for StorageTexture {
+  fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+    wgpu::BindingResource::TextureView(&self.view)
+  }
+}
+
+// 2つのリソースを交互に読み書きする
+// 計算用のバインドグループは binding 0 が読み込み元(最新)、binding 1 が書き込み先
+// 描画用のバインドグループは binding 0 に最新の状態を持つ
+pub struct PingPong<T> {
+  resources: [T; 2],
+  bind_groups: [wgpu::BindGroup; 2],
+  read_bind_groups: Option<[wgpu::BindGroup; 2]>,
+  current: usize,
+}
+
+impl<T: PingPongResource> PingPong<T> {
+  pub fn new(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    resources: [T; 2],
+  ) -> Self {
+    let bind_groups = [0, 1].map(|i| {
+      util::create_bind_group(
+        device,
+        layout,
+        &[
+          resources[i].binding_resource(),
+          resources[1 - i].binding_resource(),
+        ],
+      )
+    });
+
+    Self {
+      resources,
+      bind_groups,
+      read_bind_groups: None,
+      current: 0,
+    }
+  }
+
+  pub fn with_read_layout(
+    mut self,
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+  ) -> Self {
+    self.read_bind_groups = Some([0, 1].map(|i| {
+      util::create_bind_group(
+        device,
+        layout,
+        &[self.resources[i].binding_resource()],
+      )
+    }));
+    self
+  }
+
+  pub fn current(&self) -> &T {
+    &self.resources[self.current]
+  }
+
+  pub fn next(&self) -> &T {
+    &self.resources[1 - self.current]
+  }
+
+  pub fn bind_group(&self) -> &wgpu::BindGroup {
+    &self.bind_groups[self.current]
+  }
+
+  pub fn read_bind_group(&self) -> &wgpu::BindGroup {
+    let read_bind_groups = self
+      .read_bind_groups
+      .as_ref()
+      .expect("Read layout is not set. Call with_read_layout() first");
+    &read_bind_groups[self.current]
+  }
+
+  pub fn swap(&mut self) {
+    self.current = 1 - self.current;
+  }
+
+  // 現在のバインドグループを group に差し込んでディスパッチし、入れ替える
+  pub fn step(
+    &mut self,
+    encoder: &mut wgpu::CommandEncoder,
+    kernel: &ComputeKernel,
+    group: u32,
+    problem_size: [u32; 3],
+    options: DispatchOptions,
+  ) -> Result<(), KernelError> {
+    kernel.dispatch_workgroups(
+      encoder,
+      kernel.workgroup_count(problem_size)?,
+      options.bind_group(group, self.bind_group()),
+    );
+    self.swap();
+    Ok(())
+  }
+}
+
+impl PingPong<wgpu::Buffer> {
+  pub fn buffers(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    contents: &[u8],
+    usage: wgpu::BufferUsages,
+  ) -> Self {
+    use wgpu::util::DeviceExt;
+
+    let buffers = [0, 1].map(|_| {
+      device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("[wgsim] ping-pong buffer"),
+        contents,
+        usage: usage | wgpu::BufferUsages::STORAGE,
+      })
+    });
+
+    Self::new(device, layout, buffers)
+  }
+}
+
+impl PingPong<StorageTexture> {
+  pub fn storage_textures(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    size: Size,
+    format: wgpu::TextureFormat,
+  ) -> Self {
+    let textures = [0, 1].map(|_| StorageTexture::new(device, size, format));
+
+    Self::new(device, layout, textures)
+  }
+}
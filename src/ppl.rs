@@ -1,4 +1,9 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use crate::ctx::DrawingContext;
+use crate::kernel::{self, ComputeKernel, KernelError};
+use crate::shader;
 
 pub struct RenderPipelineBuilder<'a> {
   ctx: &'a DrawingContext<'a>,
@@ -15,6 +20,9 @@ pub struct RenderPipelineBuilder<'a> {
   targets: Vec<Option<wgpu::ColorTargetState>>,
 
   primitive: wgpu::PrimitiveState,
+  sample_count: u32,
+
+  constants: HashMap<String, f64>,
 }
 
 impl<'a> RenderPipelineBuilder<'a> {
@@ -30,6 +38,8 @@ impl<'a> RenderPipelineBuilder<'a> {
       vertex_buffer_layout: &[],
       targets: vec![Some(ctx.format().into())],
       primitive: wgpu::PrimitiveState::default(),
+      sample_count: ctx.sample_count,
+      constants: HashMap::new(),
     }
   }
 
@@ -89,26 +99,56 @@ impl<'a> RenderPipelineBuilder<'a> {
     self
   }
 
+  pub fn targets(
+    mut self,
+    targets: Vec<Option<wgpu::ColorTargetState>>,
+  ) -> Self {
+    self.targets = targets;
+    self
+  }
+
+  pub fn sample_count(mut self, sample_count: u32) -> Self {
+    self.sample_count = sample_count;
+    self
+  }
+
+  pub fn constant(mut self, name: &str, value: f64) -> Self {
+    self.constants.insert(name.to_string(), value);
+    self
+  }
+
+  pub fn constants(mut self, constants: &[(&str, f64)]) -> Self {
+    for (name, value) in constants {
+      self.constants.insert(name.to_string(), *value);
+    }
+    self
+  }
+
   pub fn build(&self) -> wgpu::RenderPipeline {
+    let compilation_options = wgpu::PipelineCompilationOptions {
+      constants: &self.constants,
+      ..Default::default()
+    };
+
     self.ctx.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
       label: Some("[wgsim] render pipeline"),
       layout: self.pipeline_layout,
       vertex: wgpu::VertexState {
-        module: &self.vs_shader.unwrap(),
-        entry_point: Some(&self.vs_entry),
-        buffers: &self.vertex_buffer_layout,
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        module: self.vs_shader.unwrap(),
+        entry_point: Some(self.vs_entry),
+        buffers: self.vertex_buffer_layout,
+        compilation_options: compilation_options.clone(),
       },
       fragment: self.fs_shader.map(|fs_shader| wgpu::FragmentState {
         module: fs_shader,
         entry_point: Some(self.fs_entry),
         targets: self.targets.as_slice(),
-        compilation_options: wgpu::PipelineCompilationOptions::default(),
+        compilation_options: compilation_options.clone(),
       }),
       primitive: self.primitive,
       depth_stencil: self.depth_stencil.clone(),
       multisample: wgpu::MultisampleState {
-        count: self.ctx.sample_count,
+        count: self.sample_count,
         ..Default::default()
       },
       multiview: None,
@@ -124,6 +164,8 @@ pub struct ComputePipelineBuilder<'a> {
 
   cs_shader: Option<&'a wgpu::ShaderModule>,
   cs_entry: &'a str,
+
+  constants: HashMap<String, f64>,
 }
 
 impl<'a> ComputePipelineBuilder<'a> {
@@ -133,6 +175,7 @@ impl<'a> ComputePipelineBuilder<'a> {
       pipeline_layout: None,
       cs_shader: None,
       cs_entry: "cs_main",
+      constants: HashMap::new(),
     }
   }
 
@@ -151,16 +194,213 @@ impl<'a> ComputePipelineBuilder<'a> {
     self
   }
 
+  pub fn constant(mut self, name: &str, value: f64) -> Self {
+    self.constants.insert(name.to_string(), value);
+    self
+  }
+
+  pub fn constants(mut self, constants: &[(&str, f64)]) -> Self {
+    for (name, value) in constants {
+      self.constants.insert(name.to_string(), *value);
+    }
+    self
+  }
+
   pub fn build(&self) -> wgpu::ComputePipeline {
     self.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
       label: Some("[wgsim] compute pipeline"),
       layout: self.pipeline_layout,
 
-      module: &self.cs_shader.unwrap(),
-      entry_point: Some(&self.cs_entry),
+      module: self.cs_shader.unwrap(),
+      entry_point: Some(self.cs_entry),
 
-      compilation_options: wgpu::PipelineCompilationOptions::default(),
+      compilation_options: wgpu::PipelineCompilationOptions {
+        constants: &self.constants,
+        ..Default::default()
+      },
       cache: None,
     })
   }
 }
+
+// f64 はハッシュできないため、ビット列をキーにする
+type VariantKey = Vec<(String, u64)>;
+
+// @id で指定した定数は名前に直し、並べ替えて同じバリアントが同じキーになるようにする
+fn variant_key(
+  constants: &HashMap<String, f64>,
+  ids: &HashMap<String, String>,
+) -> VariantKey {
+  let mut key = constants
+    .iter()
+    .map(|(name, value)| {
+      let name = ids.get(name).unwrap_or(name);
+      (name.clone(), value.to_bits())
+    })
+    .collect::<VariantKey>();
+  key.sort();
+  key
+}
+
+pub struct ComputePipelineVariants<'a> {
+  device: &'a wgpu::Device,
+
+  pipeline_layout: Option<&'a wgpu::PipelineLayout>,
+
+  source: &'a str,
+  cs_entry: &'a str,
+
+  variants: HashMap<VariantKey, ComputeVariant>,
+  // @id の値から override の名前を引く表 (最初の get で作る)
+  override_ids: Option<HashMap<String, String>>,
+}
+
+struct ComputeVariant {
+  pipeline: Arc<wgpu::ComputePipeline>,
+  workgroup_size: [u32; 3],
+}
+
+impl<'a> ComputePipelineVariants<'a> {
+  pub fn new(
+    device: &'a wgpu::Device,
+    source: &'a str,
+    entry: &'a str,
+  ) -> Self {
+    Self {
+      device,
+      pipeline_layout: None,
+      source,
+      cs_entry: entry,
+      variants: HashMap::new(),
+      override_ids: None,
+    }
+  }
+
+  pub fn pipeline_layout(mut self, layout: &'a wgpu::PipelineLayout) -> Self {
+    self.pipeline_layout = Some(layout);
+    self
+  }
+
+  // 定数名がどの override にも一致しない場合や値が型に合わない場合、
+  // エントリポイントが見つからない場合はエラー
+  pub fn get(
+    &mut self,
+    constants: &[(&str, f64)],
+  ) -> Result<&wgpu::ComputePipeline, KernelError> {
+    Ok(&self.variant(constants)?.pipeline)
+  }
+
+  pub fn kernel(
+    &mut self,
+    constants: &[(&str, f64)],
+  ) -> Result<ComputeKernel, KernelError> {
+    let device = self.device;
+    let variant = self.variant(constants)?;
+
+    Ok(ComputeKernel::new(
+      device,
+      variant.pipeline.clone(),
+      variant.workgroup_size,
+    ))
+  }
+
+  fn variant(
+    &mut self,
+    constants: &[(&str, f64)],
+  ) -> Result<&ComputeVariant, KernelError> {
+    let constants = constants
+      .iter()
+      .map(|(name, value)| (name.to_string(), *value))
+      .collect::<HashMap<_, _>>();
+
+    let ids = match &self.override_ids {
+      Some(ids) => ids,
+      None => self.override_ids.insert(shader::override_ids(self.source)?),
+    };
+    let key = variant_key(&constants, ids);
+
+    if !self.variants.contains_key(&key) {
+      let source = shader::specialize(self.source, &constants)?;
+      let workgroup_size =
+        kernel::reflect_workgroup_size(&source, self.cs_entry)?;
+
+      let module =
+        self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+          label: Some("[wgsim] compute shader variant"),
+          source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+      let mut builder = ComputePipelineBuilder::new(self.device)
+        .cs_shader(&module, self.cs_entry);
+      if let Some(layout) = self.pipeline_layout {
+        builder = builder.pipeline_layout(layout);
+      }
+
+      let variant = ComputeVariant {
+        pipeline: Arc::new(builder.build()),
+        workgroup_size,
+      };
+      self.variants.insert(key.clone(), variant);
+    }
+
+    Ok(&self.variants[&key])
+  }
+
+  pub fn len(&self) -> usize {
+    self.variants.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.variants.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.variants.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn constants(values: &[(&str, f64)]) -> HashMap<String, f64> {
+    values.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+  }
+
+  #[test]
+  fn variant_key_resolves_ids_and_sorts() {
+    let ids = shader::override_ids(
+      "@id(0) override width: u32;\n@id(1) override height: u32;\n\
+       override scale: f32;",
+    )
+    .unwrap();
+
+    let key = variant_key(
+      &constants(&[("scale", 0.5), ("1", 4.0), ("width", 8.0)]),
+      &ids,
+    );
+    assert_eq!(
+      key,
+      [
+        ("height".to_string(), 4.0f64.to_bits()),
+        ("scale".to_string(), 0.5f64.to_bits()),
+        ("width".to_string(), 8.0f64.to_bits()),
+      ]
+    );
+    assert_eq!(
+      variant_key(
+        &constants(&[("width", 8.0), ("height", 4.0), ("scale", 0.5)]),
+        &ids
+      ),
+      key
+    );
+    assert_eq!(
+      variant_key(&constants(&[("0", 8.0), ("1", 4.0), ("scale", 0.5)]), &ids),
+      key
+    );
+    assert_ne!(
+      variant_key(&constants(&[("0", 8.0), ("1", 5.0), ("scale", 0.5)]), &ids),
+      key
+    );
+  }
+}
@@ -1,22 +1,1192 @@
-use winit::dpi::PhysicalSize;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
 
-#[derive(Debug, Clone, Copy)]
-pub struct Size {
-  pub width: u32,
-  pub height: u32,
+use winit::dpi::{
+  LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize,
+};
+
+// 画素数は u32、論理座標などは f32 で表す
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Size<T = u32> {
+  pub width: T,
+  pub height: T,
 }
 
-impl Size {
-  pub fn new(width: u32, height: u32) -> Self {
+// 同じ型のフィールドが2つ並ぶだけなのでパディングはない
+unsafe impl<T: bytemuck::Zeroable> bytemuck::Zeroable for Size<T> {}
+unsafe impl<T: bytemuck::Pod> bytemuck::Pod for Size<T> {}
+
+impl<T> Size<T> {
+  pub const fn new(width: T, height: T) -> Self {
     Self { width, height }
   }
 }
 
-impl Into<Size> for PhysicalSize<u32> {
-  fn into(self) -> Size {
-    Size {
-      width: self.width,
-      height: self.height,
+impl<T: Copy + Into<f64>> Size<T> {
+  pub fn aspect_ratio(&self) -> f32 {
+    (self.width.into() / self.height.into()) as f32
+  }
+}
+
+impl Size<u32> {
+  pub fn to_f32(self) -> Size<f32> {
+    Size::new(self.width as f32, self.height as f32)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.width == 0 || self.height == 0
+  }
+}
+
+impl Size<f32> {
+  // 端数は切り上げる
+  pub fn to_u32(self) -> Size<u32> {
+    Size::new(self.width.ceil() as u32, self.height.ceil() as u32)
+  }
+}
+
+impl<T> From<PhysicalSize<T>> for Size<T> {
+  fn from(size: PhysicalSize<T>) -> Self {
+    Size::new(size.width, size.height)
+  }
+}
+
+impl<T> From<Size<T>> for PhysicalSize<T> {
+  fn from(size: Size<T>) -> Self {
+    PhysicalSize::new(size.width, size.height)
+  }
+}
+
+impl<T> From<LogicalSize<T>> for Size<T> {
+  fn from(size: LogicalSize<T>) -> Self {
+    Size::new(size.width, size.height)
+  }
+}
+
+impl<T> From<Size<T>> for LogicalSize<T> {
+  fn from(size: Size<T>) -> Self {
+    LogicalSize::new(size.width, size.height)
+  }
+}
+
+impl From<wgpu::Extent3d> for Size<u32> {
+  fn from(extent: wgpu::Extent3d) -> Self {
+    Size::new(extent.width, extent.height)
+  }
+}
+
+impl From<Size<u32>> for wgpu::Extent3d {
+  fn from(size: Size<u32>) -> Self {
+    wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    }
+  }
+}
+
+impl<T> From<[T; 2]> for Size<T> {
+  fn from([width, height]: [T; 2]) -> Self {
+    Size::new(width, height)
+  }
+}
+
+impl<T> From<Size<T>> for [T; 2] {
+  fn from(size: Size<T>) -> Self {
+    [size.width, size.height]
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Point<T = f32> {
+  pub x: T,
+  pub y: T,
+}
+
+unsafe impl<T: bytemuck::Zeroable> bytemuck::Zeroable for Point<T> {}
+unsafe impl<T: bytemuck::Pod> bytemuck::Pod for Point<T> {}
+
+impl<T> Point<T> {
+  pub const fn new(x: T, y: T) -> Self {
+    Self { x, y }
+  }
+}
+
+impl<T> From<PhysicalPosition<T>> for Point<T> {
+  fn from(position: PhysicalPosition<T>) -> Self {
+    Point::new(position.x, position.y)
+  }
+}
+
+impl<T> From<Point<T>> for PhysicalPosition<T> {
+  fn from(point: Point<T>) -> Self {
+    PhysicalPosition::new(point.x, point.y)
+  }
+}
+
+impl<T> From<LogicalPosition<T>> for Point<T> {
+  fn from(position: LogicalPosition<T>) -> Self {
+    Point::new(position.x, position.y)
+  }
+}
+
+impl<T> From<Point<T>> for LogicalPosition<T> {
+  fn from(point: Point<T>) -> Self {
+    LogicalPosition::new(point.x, point.y)
+  }
+}
+
+impl<T> From<[T; 2]> for Point<T> {
+  fn from([x, y]: [T; 2]) -> Self {
+    Point::new(x, y)
+  }
+}
+
+impl<T> From<Point<T>> for [T; 2] {
+  fn from(point: Point<T>) -> Self {
+    [point.x, point.y]
+  }
+}
+
+impl From<Point<f32>> for Vec2 {
+  fn from(point: Point<f32>) -> Self {
+    Vec2::new(point.x, point.y)
+  }
+}
+
+impl From<Vec2> for Point<f32> {
+  fn from(v: Vec2) -> Self {
+    Point::new(v.x, v.y)
+  }
+}
+
+// origin は左上
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect<T = f32> {
+  pub origin: Point<T>,
+  pub size: Size<T>,
+}
+
+unsafe impl<T: bytemuck::Zeroable> bytemuck::Zeroable for Rect<T> {}
+unsafe impl<T: bytemuck::Pod> bytemuck::Pod for Rect<T> {}
+
+impl<T> Rect<T> {
+  pub const fn new(x: T, y: T, width: T, height: T) -> Self {
+    Self {
+      origin: Point::new(x, y),
+      size: Size::new(width, height),
+    }
+  }
+}
+
+impl<T> Rect<T>
+where
+  T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+  pub fn from_min_max(min: Point<T>, max: Point<T>) -> Self {
+    Self::new(min.x, min.y, max.x - min.x, max.y - min.y)
+  }
+
+  pub fn min(&self) -> Point<T> {
+    self.origin
+  }
+
+  pub fn max(&self) -> Point<T> {
+    Point::new(
+      self.origin.x + self.size.width,
+      self.origin.y + self.size.height,
+    )
+  }
+
+  // 右端と下端は含まない
+  pub fn contains(&self, point: Point<T>) -> bool {
+    let max = self.max();
+    point.x >= self.origin.x
+      && point.y >= self.origin.y
+      && point.x < max.x
+      && point.y < max.y
+  }
+
+  pub fn intersects(&self, other: &Rect<T>) -> bool {
+    let (a, b) = (self.max(), other.max());
+    self.origin.x < b.x
+      && other.origin.x < a.x
+      && self.origin.y < b.y
+      && other.origin.y < a.y
+  }
+}
+
+impl Rect<f32> {
+  pub fn center(&self) -> Point<f32> {
+    Point::new(
+      self.origin.x + self.size.width * 0.5,
+      self.origin.y + self.size.height * 0.5,
+    )
+  }
+}
+
+macro_rules! impl_vector {
+  ($name:ident { $($field:ident),+ }, $n:literal) => {
+    impl $name {
+      pub const ZERO: Self = Self { $($field: 0.0),+ };
+      pub const ONE: Self = Self { $($field: 1.0),+ };
+
+      pub const fn new($($field: f32),+) -> Self {
+        Self { $($field),+ }
+      }
+
+      pub const fn splat(v: f32) -> Self {
+        Self { $($field: v),+ }
+      }
+
+      pub fn dot(self, other: Self) -> f32 {
+        0.0 $(+ self.$field * other.$field)+
+      }
+
+      pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+      }
+
+      pub fn length_squared(self) -> f32 {
+        self.dot(self)
+      }
+
+      // 長さが 0 の場合は 0 を返す
+      pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length > 0.0 {
+          self / length
+        } else {
+          Self::ZERO
+        }
+      }
+
+      pub fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+      }
+
+      pub fn min(self, other: Self) -> Self {
+        Self { $($field: self.$field.min(other.$field)),+ }
+      }
+
+      pub fn max(self, other: Self) -> Self {
+        Self { $($field: self.$field.max(other.$field)),+ }
+      }
+
+      pub fn to_array(self) -> [f32; $n] {
+        [$(self.$field),+]
+      }
+    }
+
+    impl Add for $name {
+      type Output = Self;
+      fn add(self, rhs: Self) -> Self {
+        Self { $($field: self.$field + rhs.$field),+ }
+      }
+    }
+
+    impl Sub for $name {
+      type Output = Self;
+      fn sub(self, rhs: Self) -> Self {
+        Self { $($field: self.$field - rhs.$field),+ }
+      }
+    }
+
+    // 要素ごとの積
+    impl Mul for $name {
+      type Output = Self;
+      fn mul(self, rhs: Self) -> Self {
+        Self { $($field: self.$field * rhs.$field),+ }
+      }
+    }
+
+    impl Mul<f32> for $name {
+      type Output = Self;
+      fn mul(self, rhs: f32) -> Self {
+        Self { $($field: self.$field * rhs),+ }
+      }
+    }
+
+    impl Mul<$name> for f32 {
+      type Output = $name;
+      fn mul(self, rhs: $name) -> $name {
+        rhs * self
+      }
+    }
+
+    impl Div<f32> for $name {
+      type Output = Self;
+      fn div(self, rhs: f32) -> Self {
+        Self { $($field: self.$field / rhs),+ }
+      }
+    }
+
+    impl Neg for $name {
+      type Output = Self;
+      fn neg(self) -> Self {
+        Self { $($field: -self.$field),+ }
+      }
+    }
+
+    impl AddAssign for $name {
+      fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+      }
+    }
+
+    impl SubAssign for $name {
+      fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+      }
+    }
+
+    impl MulAssign<f32> for $name {
+      fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+      }
+    }
+
+    impl From<[f32; $n]> for $name {
+      fn from([$($field),+]: [f32; $n]) -> Self {
+        Self { $($field),+ }
+      }
+    }
+
+    impl From<$name> for [f32; $n] {
+      fn from(v: $name) -> Self {
+        v.to_array()
+      }
+    }
+  };
+}
+
+#[repr(C)]
+#[derive(
+  Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable,
+)]
+pub struct Vec2 {
+  pub x: f32,
+  pub y: f32,
+}
+
+// WGSL の vec3f は 16 バイト境界に揃えられるため、uniform に置く場合は後ろに f32 を1つ詰めるか Vec4 を使う
+#[repr(C)]
+#[derive(
+  Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable,
+)]
+pub struct Vec3 {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+}
+
+#[repr(C)]
+#[derive(
+  Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable,
+)]
+pub struct Vec4 {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+  pub w: f32,
+}
+
+impl_vector!(Vec2 { x, y }, 2);
+impl_vector!(Vec3 { x, y, z }, 3);
+impl_vector!(Vec4 { x, y, z, w }, 4);
+
+impl Vec2 {
+  pub fn extend(self, z: f32) -> Vec3 {
+    Vec3::new(self.x, self.y, z)
+  }
+}
+
+impl Vec3 {
+  pub const X: Self = Self::new(1.0, 0.0, 0.0);
+  pub const Y: Self = Self::new(0.0, 1.0, 0.0);
+  pub const Z: Self = Self::new(0.0, 0.0, 1.0);
+
+  pub fn cross(self, other: Self) -> Self {
+    Self::new(
+      self.y * other.z - self.z * other.y,
+      self.z * other.x - self.x * other.z,
+      self.x * other.y - self.y * other.x,
+    )
+  }
+
+  pub fn extend(self, w: f32) -> Vec4 {
+    Vec4::new(self.x, self.y, self.z, w)
+  }
+
+  pub fn truncate(self) -> Vec2 {
+    Vec2::new(self.x, self.y)
+  }
+}
+
+impl Vec4 {
+  pub fn truncate(self) -> Vec3 {
+    Vec3::new(self.x, self.y, self.z)
+  }
+}
+
+// WGSL の mat3x3f と同じく、各列を vec4 の大きさに揃えて持つ (列優先)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat3 {
+  pub cols: [[f32; 4]; 3],
+}
+
+impl Default for Mat3 {
+  fn default() -> Self {
+    Self::IDENTITY
+  }
+}
+
+impl Mat3 {
+  pub const IDENTITY: Self = Self::from_cols(Vec3::X, Vec3::Y, Vec3::Z);
+
+  pub const fn from_cols(x: Vec3, y: Vec3, z: Vec3) -> Self {
+    Self {
+      cols: [
+        [x.x, x.y, x.z, 0.0],
+        [y.x, y.y, y.z, 0.0],
+        [z.x, z.y, z.z, 0.0],
+      ],
+    }
+  }
+
+  pub fn col(&self, i: usize) -> Vec3 {
+    let c = self.cols[i];
+    Vec3::new(c[0], c[1], c[2])
+  }
+
+  pub fn transpose(&self) -> Self {
+    let [x, y, z] = [0, 1, 2].map(|i| self.col(i));
+    Self::from_cols(
+      Vec3::new(x.x, y.x, z.x),
+      Vec3::new(x.y, y.y, z.y),
+      Vec3::new(x.z, y.z, z.z),
+    )
+  }
+
+  pub fn determinant(&self) -> f32 {
+    let [x, y, z] = [0, 1, 2].map(|i| self.col(i));
+    x.dot(y.cross(z))
+  }
+
+  // 特異行列 (det が 0) の場合は None
+  pub fn inverse(&self) -> Option<Self> {
+    let [x, y, z] = [0, 1, 2].map(|i| self.col(i));
+    let det = x.dot(y.cross(z));
+    let inv = 1.0 / det;
+    // 縮尺の小さい行列も逆にできるよう、det が 0 か 1 / det が溢れる場合だけ弾く
+    if det == 0.0 || !inv.is_finite() {
+      return None;
+    }
+    Some(
+      Self::from_cols(y.cross(z) * inv, z.cross(x) * inv, x.cross(y) * inv)
+        .transpose(),
+    )
+  }
+
+  // 法線の変換に使う (逆行列の転置)
+  pub fn normal_matrix(model: &Mat4) -> Self {
+    Self::from(*model).inverse().unwrap_or(Self::IDENTITY).transpose()
+  }
+}
+
+impl From<Mat4> for Mat3 {
+  fn from(m: Mat4) -> Self {
+    Self::from_cols(
+      m.col(0).truncate(),
+      m.col(1).truncate(),
+      m.col(2).truncate(),
+    )
+  }
+}
+
+impl Mul for Mat3 {
+  type Output = Self;
+  fn mul(self, rhs: Self) -> Self {
+    let [x, y, z] = [0, 1, 2].map(|i| self * rhs.col(i));
+    Self::from_cols(x, y, z)
+  }
+}
+
+impl Mul<Vec3> for Mat3 {
+  type Output = Vec3;
+  fn mul(self, v: Vec3) -> Vec3 {
+    self.col(0) * v.x + self.col(1) * v.y + self.col(2) * v.z
+  }
+}
+
+// 列優先 (WGSL の mat4x4f と同じ並び)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat4 {
+  pub cols: [[f32; 4]; 4],
+}
+
+impl Default for Mat4 {
+  fn default() -> Self {
+    Self::IDENTITY
+  }
+}
+
+impl Mat4 {
+  pub const IDENTITY: Self = Self {
+    cols: [
+      [1.0, 0.0, 0.0, 0.0],
+      [0.0, 1.0, 0.0, 0.0],
+      [0.0, 0.0, 1.0, 0.0],
+      [0.0, 0.0, 0.0, 1.0],
+    ],
+  };
+
+  pub const fn from_cols(x: Vec4, y: Vec4, z: Vec4, w: Vec4) -> Self {
+    Self {
+      cols: [
+        [x.x, x.y, x.z, x.w],
+        [y.x, y.y, y.z, y.w],
+        [z.x, z.y, z.z, z.w],
+        [w.x, w.y, w.z, w.w],
+      ],
+    }
+  }
+
+  pub fn col(&self, i: usize) -> Vec4 {
+    Vec4::from(self.cols[i])
+  }
+
+  pub fn translation(t: Vec3) -> Self {
+    let mut m = Self::IDENTITY;
+    m.cols[3] = [t.x, t.y, t.z, 1.0];
+    m
+  }
+
+  pub fn scale(s: Vec3) -> Self {
+    let mut m = Self::IDENTITY;
+    m.cols[0][0] = s.x;
+    m.cols[1][1] = s.y;
+    m.cols[2][2] = s.z;
+    m
+  }
+
+  pub fn rotation_x(angle: f32) -> Self {
+    let (s, c) = angle.sin_cos();
+    Self::from_cols(
+      Vec4::new(1.0, 0.0, 0.0, 0.0),
+      Vec4::new(0.0, c, s, 0.0),
+      Vec4::new(0.0, -s, c, 0.0),
+      Vec4::new(0.0, 0.0, 0.0, 1.0),
+    )
+  }
+
+  pub fn rotation_y(angle: f32) -> Self {
+    let (s, c) = angle.sin_cos();
+    Self::from_cols(
+      Vec4::new(c, 0.0, -s, 0.0),
+      Vec4::new(0.0, 1.0, 0.0, 0.0),
+      Vec4::new(s, 0.0, c, 0.0),
+      Vec4::new(0.0, 0.0, 0.0, 1.0),
+    )
+  }
+
+  pub fn rotation_z(angle: f32) -> Self {
+    let (s, c) = angle.sin_cos();
+    Self::from_cols(
+      Vec4::new(c, s, 0.0, 0.0),
+      Vec4::new(-s, c, 0.0, 0.0),
+      Vec4::new(0.0, 0.0, 1.0, 0.0),
+      Vec4::new(0.0, 0.0, 0.0, 1.0),
+    )
+  }
+
+  // 右手系、eye から target を見る
+  pub fn look_at_rh(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+    let f = (target - eye).normalize();
+    let s = f.cross(up).normalize();
+    let u = s.cross(f);
+    Self::from_cols(
+      Vec4::new(s.x, u.x, -f.x, 0.0),
+      Vec4::new(s.y, u.y, -f.y, 0.0),
+      Vec4::new(s.z, u.z, -f.z, 0.0),
+      Vec4::new(-s.dot(eye), -u.dot(eye), f.dot(eye), 1.0),
+    )
+  }
+
+  // 右手系、深度は wgpu と同じ 0..1
+  pub fn perspective_rh(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+    let h = 1.0 / (fov_y * 0.5).tan();
+    let r = far / (near - far);
+    Self::from_cols(
+      Vec4::new(h / aspect, 0.0, 0.0, 0.0),
+      Vec4::new(0.0, h, 0.0, 0.0),
+      Vec4::new(0.0, 0.0, r, -1.0),
+      Vec4::new(0.0, 0.0, r * near, 0.0),
+    )
+  }
+
+  // 右手系、深度は wgpu と同じ 0..1
+  pub fn orthographic_rh(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+  ) -> Self {
+    let rw = 1.0 / (right - left);
+    let rh = 1.0 / (top - bottom);
+    let rd = 1.0 / (near - far);
+    Self::from_cols(
+      Vec4::new(2.0 * rw, 0.0, 0.0, 0.0),
+      Vec4::new(0.0, 2.0 * rh, 0.0, 0.0),
+      Vec4::new(0.0, 0.0, rd, 0.0),
+      Vec4::new(-(left + right) * rw, -(top + bottom) * rh, rd * near, 1.0),
+    )
+  }
+
+  pub fn transpose(&self) -> Self {
+    let mut m = Self::IDENTITY;
+    for (c, col) in m.cols.iter_mut().enumerate() {
+      for (r, v) in col.iter_mut().enumerate() {
+        *v = self.cols[r][c];
+      }
     }
+    m
+  }
+
+  // 特異行列 (det が 0) の場合は None
+  pub fn inverse(&self) -> Option<Self> {
+    let m = |c: usize, r: usize| self.cols[c][r];
+
+    let s0 = m(0, 0) * m(1, 1) - m(1, 0) * m(0, 1);
+    let s1 = m(0, 0) * m(1, 2) - m(1, 0) * m(0, 2);
+    let s2 = m(0, 0) * m(1, 3) - m(1, 0) * m(0, 3);
+    let s3 = m(0, 1) * m(1, 2) - m(1, 1) * m(0, 2);
+    let s4 = m(0, 1) * m(1, 3) - m(1, 1) * m(0, 3);
+    let s5 = m(0, 2) * m(1, 3) - m(1, 2) * m(0, 3);
+
+    let c5 = m(2, 2) * m(3, 3) - m(3, 2) * m(2, 3);
+    let c4 = m(2, 1) * m(3, 3) - m(3, 1) * m(2, 3);
+    let c3 = m(2, 1) * m(3, 2) - m(3, 1) * m(2, 2);
+    let c2 = m(2, 0) * m(3, 3) - m(3, 0) * m(2, 3);
+    let c1 = m(2, 0) * m(3, 2) - m(3, 0) * m(2, 2);
+    let c0 = m(2, 0) * m(3, 1) - m(3, 0) * m(2, 1);
+
+    let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+    let inv = 1.0 / det;
+    // Mat3::inverse と同じく、小さな det (縮小したカメラなど) は受け付ける
+    if det == 0.0 || !inv.is_finite() {
+      return None;
+    }
+
+    Some(Self {
+      cols: [
+        [
+          (m(1, 1) * c5 - m(1, 2) * c4 + m(1, 3) * c3) * inv,
+          (-m(0, 1) * c5 + m(0, 2) * c4 - m(0, 3) * c3) * inv,
+          (m(3, 1) * s5 - m(3, 2) * s4 + m(3, 3) * s3) * inv,
+          (-m(2, 1) * s5 + m(2, 2) * s4 - m(2, 3) * s3) * inv,
+        ],
+        [
+          (-m(1, 0) * c5 + m(1, 2) * c2 - m(1, 3) * c1) * inv,
+          (m(0, 0) * c5 - m(0, 2) * c2 + m(0, 3) * c1) * inv,
+          (-m(3, 0) * s5 + m(3, 2) * s2 - m(3, 3) * s1) * inv,
+          (m(2, 0) * s5 - m(2, 2) * s2 + m(2, 3) * s1) * inv,
+        ],
+        [
+          (m(1, 0) * c4 - m(1, 1) * c2 + m(1, 3) * c0) * inv,
+          (-m(0, 0) * c4 + m(0, 1) * c2 - m(0, 3) * c0) * inv,
+          (m(3, 0) * s4 - m(3, 1) * s2 + m(3, 3) * s0) * inv,
+          (-m(2, 0) * s4 + m(2, 1) * s2 - m(2, 3) * s0) * inv,
+        ],
+        [
+          (-m(1, 0) * c3 + m(1, 1) * c1 - m(1, 2) * c0) * inv,
+          (m(0, 0) * c3 - m(0, 1) * c1 + m(0, 2) * c0) * inv,
+          (-m(3, 0) * s3 + m(3, 1) * s1 - m(3, 2) * s0) * inv,
+          (m(2, 0) * s3 - m(2, 1) * s1 + m(2, 2) * s0) * inv,
+        ],
+      ],
+    })
+  }
+
+  pub fn transform_point(&self, p: Vec3) -> Vec3 {
+    let v = *self * p.extend(1.0);
+    v.truncate() / v.w
+  }
+
+  pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+    (*self * v.extend(0.0)).truncate()
+  }
+}
+
+impl Mul for Mat4 {
+  type Output = Self;
+  fn mul(self, rhs: Self) -> Self {
+    let [x, y, z, w] = [0, 1, 2, 3].map(|i| self * rhs.col(i));
+    Self::from_cols(x, y, z, w)
+  }
+}
+
+impl Mul<Vec4> for Mat4 {
+  type Output = Vec4;
+  fn mul(self, v: Vec4) -> Vec4 {
+    self.col(0) * v.x
+      + self.col(1) * v.y
+      + self.col(2) * v.z
+      + self.col(3) * v.w
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl std::fmt::Display for ParseColorError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "'{}' is not a hex color (#rgb, #rgba, #rrggbb or #rrggbbaa)",
+      self.0
+    )
+  }
+}
+
+impl std::error::Error for ParseColorError {}
+
+// 線形の RGBA (シェーダーや wgpu::Color にはこのまま渡す)
+#[repr(C)]
+#[derive(
+  Debug, Clone, Copy, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable,
+)]
+pub struct Color {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+  pub a: f32,
+}
+
+impl Color {
+  pub const TRANSPARENT: Self = Self::linear(0.0, 0.0, 0.0, 0.0);
+  pub const BLACK: Self = Self::linear(0.0, 0.0, 0.0, 1.0);
+  pub const WHITE: Self = Self::linear(1.0, 1.0, 1.0, 1.0);
+
+  pub const fn linear(r: f32, g: f32, b: f32, a: f32) -> Self {
+    Self { r, g, b, a }
+  }
+
+  // CSS などの sRGB の値から作る (アルファはそのまま)
+  pub fn srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+    Self::linear(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a)
+  }
+
+  pub fn from_srgb8(r: u8, g: u8, b: u8, a: u8) -> Self {
+    let unorm = |v: u8| v as f32 / 255.0;
+    Self::srgb(unorm(r), unorm(g), unorm(b), unorm(a))
+  }
+
+  // "#ff8000" のような sRGB の16進数表記 (# は省略できる)
+  pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+    let error = || ParseColorError(hex.to_string());
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    // from_str_radix は先頭の + を受け付けるので、先に16進数の文字だけか確かめる
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+      return Err(error());
+    }
+
+    let parse = |s: &str| u8::from_str_radix(s, 16).map_err(|_| error());
+    let short = |i: usize| parse(&digits[i..i + 1]).map(|v| v * 17);
+    let long = |i: usize| parse(&digits[i * 2..i * 2 + 2]);
+
+    let [r, g, b, a] = match digits.len() {
+      3 => [short(0)?, short(1)?, short(2)?, 255],
+      4 => [short(0)?, short(1)?, short(2)?, short(3)?],
+      6 => [long(0)?, long(1)?, long(2)?, 255],
+      8 => [long(0)?, long(1)?, long(2)?, long(3)?],
+      _ => return Err(error()),
+    };
+    Ok(Self::from_srgb8(r, g, b, a))
+  }
+
+  pub fn with_alpha(mut self, a: f32) -> Self {
+    self.a = a;
+    self
+  }
+
+  pub fn to_srgb(self) -> [f32; 4] {
+    [
+      linear_to_srgb(self.r),
+      linear_to_srgb(self.g),
+      linear_to_srgb(self.b),
+      self.a,
+    ]
+  }
+
+  pub fn to_srgb8(self) -> [u8; 4] {
+    self.to_srgb().map(|v| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8)
+  }
+
+  pub fn to_hex(self) -> String {
+    let [r, g, b, a] = self.to_srgb8();
+    if a == 255 {
+      format!("#{:02x}{:02x}{:02x}", r, g, b)
+    } else {
+      format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+  }
+
+  pub fn lerp(self, other: Self, t: f32) -> Self {
+    Self::from(Vec4::from(self).lerp(Vec4::from(other), t))
+  }
+}
+
+impl std::str::FromStr for Color {
+  type Err = ParseColorError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Self::from_hex(s)
+  }
+}
+
+impl From<Color> for wgpu::Color {
+  fn from(c: Color) -> Self {
+    wgpu::Color {
+      r: c.r as f64,
+      g: c.g as f64,
+      b: c.b as f64,
+      a: c.a as f64,
+    }
+  }
+}
+
+impl From<wgpu::Color> for Color {
+  fn from(c: wgpu::Color) -> Self {
+    Color::linear(c.r as f32, c.g as f32, c.b as f32, c.a as f32)
+  }
+}
+
+impl From<Color> for [f32; 4] {
+  fn from(c: Color) -> Self {
+    [c.r, c.g, c.b, c.a]
+  }
+}
+
+impl From<[f32; 4]> for Color {
+  fn from([r, g, b, a]: [f32; 4]) -> Self {
+    Color::linear(r, g, b, a)
+  }
+}
+
+impl From<Color> for Vec4 {
+  fn from(c: Color) -> Self {
+    Vec4::new(c.r, c.g, c.b, c.a)
+  }
+}
+
+impl From<Vec4> for Color {
+  fn from(v: Vec4) -> Self {
+    Color::linear(v.x, v.y, v.z, v.w)
+  }
+}
+
+pub(crate) fn srgb_to_linear(v: f32) -> f32 {
+  if v <= 0.040_45 {
+    v / 12.92
+  } else {
+    ((v + 0.055) / 1.055).powf(2.4)
+  }
+}
+
+// 0..1 の外の値もそのまま変換する (8 ビットなどにする側で切り詰める)
+pub(crate) fn linear_to_srgb(v: f32) -> f32 {
+  if v <= 0.003_130_8 {
+    v * 12.92
+  } else {
+    1.055 * v.max(0.0).powf(1.0 / 2.4) - 0.055
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hex_colors() {
+    let orange = Color::from_srgb8(0xff, 0x80, 0x00, 0xff);
+    assert_eq!(Color::from_hex("#ff8000"), Ok(orange));
+    assert_eq!(Color::from_hex("FF8000"), Ok(orange));
+    assert_eq!(
+      Color::from_hex("#ff800080"),
+      Ok(Color::from_srgb8(0xff, 0x80, 0x00, 0x80))
+    );
+    // 短い表記は各桁を2回繰り返す
+    assert_eq!(
+      Color::from_hex("#f80"),
+      Ok(Color::from_srgb8(0xff, 0x88, 0x00, 0xff))
+    );
+    assert_eq!(
+      Color::from_hex("#f808"),
+      Ok(Color::from_srgb8(0xff, 0x88, 0x00, 0x88))
+    );
+    assert_eq!("#000".parse(), Ok(Color::BLACK));
+    assert_eq!("#ffffff".parse(), Ok(Color::WHITE));
+    assert_eq!("#00000000".parse(), Ok(Color::TRANSPARENT));
+  }
+
+  #[test]
+  fn invalid_hex_colors() {
+    for hex in [
+      "",
+      "#",
+      "#ff",
+      "#fffff",
+      "#fffffff",
+      "#fffffffff",
+      "#ggg",
+      "#12345z",
+      "##fff",
+      "#+1+2+3",
+      "#-1-2-3",
+      " #fff",
+      "#fff ",
+      "#ｆｆｆ",
+      "#ééé",
+    ] {
+      assert_eq!(
+        Color::from_hex(hex),
+        Err(ParseColorError(hex.to_string())),
+        "{:?}",
+        hex
+      );
+    }
+    assert_eq!(
+      ParseColorError("#ggg".to_string()).to_string(),
+      "'#ggg' is not a hex color (#rgb, #rgba, #rrggbb or #rrggbbaa)"
+    );
+  }
+
+  #[test]
+  fn hex_round_trip() {
+    for hex in ["#000000", "#ffffff", "#ff8000", "#123456", "#0a0b0c80"] {
+      assert_eq!(Color::from_hex(hex).unwrap().to_hex(), hex);
+    }
+    assert_eq!(Color::from_hex("#abc").unwrap().to_hex(), "#aabbcc");
+  }
+
+  #[test]
+  fn srgb_linear_round_trip() {
+    // 8 ビットの値はすべて元に戻る
+    for v in 0..=255u8 {
+      let color = Color::from_srgb8(v, v, v, v);
+      assert_eq!(color.to_srgb8(), [v; 4]);
+    }
+
+    for i in 0..=100 {
+      let v = i as f32 / 100.0;
+      let back = linear_to_srgb(srgb_to_linear(v));
+      assert!((back - v).abs() < 1e-5, "{} -> {}", v, back);
+    }
+
+    // 0.5 (sRGB) は線形で約 0.214
+    let gray = Color::srgb(0.5, 0.5, 0.5, 0.5);
+    assert!((gray.r - 0.214_041).abs() < 1e-5, "{}", gray.r);
+    assert_eq!(gray.a, 0.5);
+    assert_eq!(srgb_to_linear(0.0), 0.0);
+    assert_eq!(srgb_to_linear(1.0), 1.0);
+    assert!(Color::WHITE.to_srgb().iter().all(|v| (v - 1.0).abs() < 1e-6));
+    // 範囲外の値は 8 ビットにするときに収める
+    assert_eq!(
+      Color::linear(2.0, -1.0, 0.0, 1.0).to_srgb8(),
+      [255, 0, 0, 255]
+    );
+  }
+
+  #[test]
+  fn color_conversions() {
+    let color = Color::linear(0.1, 0.2, 0.3, 0.4);
+    assert_eq!(<[f32; 4]>::from(color), [0.1, 0.2, 0.3, 0.4]);
+    assert_eq!(Color::from([0.1, 0.2, 0.3, 0.4]), color);
+    assert_eq!(Color::from(Vec4::from(color)), color);
+
+    let wgpu_color = wgpu::Color::from(color);
+    assert_eq!(wgpu_color.r, 0.1f32 as f64);
+    assert_eq!(wgpu_color.a, 0.4f32 as f64);
+    assert_eq!(Color::from(wgpu_color), color);
+  }
+
+  #[test]
+  fn aspect_ratio() {
+    assert_eq!(Size::new(1920u32, 1080).aspect_ratio(), 16.0 / 9.0);
+    assert_eq!(Size::new(3.0f32, 4.0).aspect_ratio(), 0.75);
+    assert_eq!(Size::new(1.5f32, 0.5).aspect_ratio(), 3.0);
+    assert_eq!(Size::new(2.0f32, 0.0).aspect_ratio(), f32::INFINITY);
+    assert!(Size::new(0.0f32, 0.0).aspect_ratio().is_nan());
+  }
+
+  #[test]
+  fn size_conversions() {
+    let size = Size::new(640u32, 480);
+    assert_eq!(Size::from(PhysicalSize::new(640u32, 480)), size);
+    assert_eq!(PhysicalSize::from(size), PhysicalSize::new(640, 480));
+    assert_eq!(
+      Size::from(LogicalSize::new(320.5f32, 240.0)),
+      Size::new(320.5, 240.0)
+    );
+    assert_eq!(
+      LogicalSize::from(Size::new(320.5f32, 240.0)),
+      LogicalSize::new(320.5, 240.0)
+    );
+    assert_eq!(Size::from([640, 480]), size);
+    assert_eq!(<[u32; 2]>::from(size), [640, 480]);
+
+    let extent = wgpu::Extent3d::from(size);
+    assert_eq!(
+      extent,
+      wgpu::Extent3d {
+        width: 640,
+        height: 480,
+        depth_or_array_layers: 1,
+      }
+    );
+    let layered = wgpu::Extent3d {
+      depth_or_array_layers: 6,
+      ..extent
+    };
+    assert_eq!(Size::from(layered), size);
+
+    assert_eq!(size.to_f32(), Size::new(640.0, 480.0));
+    assert_eq!(Size::new(0.2f32, 479.01).to_u32(), Size::new(1, 480));
+    assert!(Size::new(0u32, 480).is_empty());
+    assert!(!size.is_empty());
+  }
+
+  fn assert_mat4_near(a: Mat4, b: Mat4, eps: f32) {
+    for c in 0..4 {
+      for r in 0..4 {
+        let (x, y) = (a.cols[c][r], b.cols[c][r]);
+        assert!((x - y).abs() <= eps, "[{}][{}]: {:?} != {:?}", c, r, a, b);
+      }
+    }
+  }
+
+  fn assert_vec3_near(a: Vec3, b: Vec3, eps: f32) {
+    assert!((a - b).length() <= eps, "{:?} != {:?}", a, b);
+  }
+
+  #[test]
+  fn mat4_inverse_round_trip() {
+    let m = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+      * Mat4::rotation_y(0.7)
+      * Mat4::rotation_x(-0.3)
+      * Mat4::scale(Vec3::new(2.0, 0.5, 3.0));
+    let inv = m.inverse().unwrap();
+    assert_mat4_near(m * inv, Mat4::IDENTITY, 1e-5);
+    assert_mat4_near(inv * m, Mat4::IDENTITY, 1e-5);
+
+    let p = Mat4::perspective_rh(1.0, 1.5, 0.1, 100.0);
+    assert_mat4_near(p * p.inverse().unwrap(), Mat4::IDENTITY, 1e-5);
+
+    assert_eq!(Mat4::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+    assert_eq!(
+      Mat4::from_cols(Vec4::ZERO, Vec4::ZERO, Vec4::ZERO, Vec4::ZERO).inverse(),
+      None
+    );
+  }
+
+  #[test]
+  fn small_scale_ortho_is_invertible() {
+    // 1920x1080 の画面を縮小して見る Camera2D と同じ大きさの行列
+    // (det は f32::EPSILON よりずっと小さい)
+    let zoom = 0.05;
+    let (w, h) = (1920.0 / zoom, 1080.0 / zoom);
+    let m =
+      Mat4::orthographic_rh(-w / 2.0, w / 2.0, -h / 2.0, h / 2.0, -1.0, 1.0);
+    let inv = m.inverse().expect("small-scale ortho must be invertible");
+    assert_mat4_near(m * inv, Mat4::IDENTITY, 1e-5);
+    assert_vec3_near(
+      inv.transform_point(Vec3::new(1.0, 1.0, 0.0)),
+      // 深度 0 は z = -near = 1
+      Vec3::new(w / 2.0, h / 2.0, 1.0),
+      w * 1e-5,
+    );
+
+    let m3 = Mat3::from(Mat4::scale(Vec3::splat(1e-3)));
+    let inv3 = m3.inverse().unwrap();
+    assert!((inv3.col(0).x - 1e3).abs() < 1e-1);
+  }
+
+  #[test]
+  fn normal_matrix_keeps_rotation_at_small_scale() {
+    // 小さな glTF ノードでも回転が失われない
+    let model = Mat4::rotation_z(std::f32::consts::FRAC_PI_2)
+      * Mat4::scale(Vec3::splat(1e-3));
+    let n = Mat3::normal_matrix(&model) * Vec3::X;
+    assert_vec3_near(n.normalize(), Vec3::Y, 1e-5);
+  }
+
+  #[test]
+  fn perspective_depth_range() {
+    let (near, far) = (0.5, 50.0);
+    let p = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 2.0, near, far);
+    // 右手系なので -Z が前、深度は near で 0、far で 1
+    assert!(p.transform_point(Vec3::new(0.0, 0.0, -near)).z.abs() < 1e-6);
+    assert!(
+      (p.transform_point(Vec3::new(0.0, 0.0, -far)).z - 1.0).abs() < 1e-5
+    );
+    // fov 90 度なので、距離 1 の位置で y = 1 が画面の上端
+    let top = p.transform_point(Vec3::new(0.0, 1.0, -1.0));
+    assert!((top.y - 1.0).abs() < 1e-6);
+    // aspect 2 なので x = 2 が右端
+    let right = p.transform_point(Vec3::new(2.0, 0.0, -1.0));
+    assert!((right.x - 1.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn look_at_moves_eye_to_origin() {
+    let eye = Vec3::new(3.0, 2.0, 5.0);
+    let target = Vec3::new(0.0, 2.0, 0.0);
+    let view = Mat4::look_at_rh(eye, target, Vec3::Y);
+
+    assert_vec3_near(view.transform_point(eye), Vec3::ZERO, 1e-5);
+    // target は -Z 上、距離はそのまま
+    let t = view.transform_point(target);
+    assert_vec3_near(t, Vec3::new(0.0, 0.0, -(target - eye).length()), 1e-5);
+    // 上は上のまま
+    assert_vec3_near(view.transform_vector(Vec3::Y), Vec3::Y, 1e-5);
+  }
+
+  #[test]
+  fn transform_point_divides_by_w() {
+    let m = Mat4::translation(Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(m.transform_point(Vec3::ZERO), Vec3::new(1.0, 2.0, 3.0));
+    // ベクトルは平行移動しない
+    assert_eq!(m.transform_vector(Vec3::X), Vec3::X);
+
+    let mut project = Mat4::IDENTITY;
+    project.cols[3][3] = 2.0;
+    assert_eq!(
+      project.transform_point(Vec3::new(2.0, 4.0, 6.0)),
+      Vec3::new(1.0, 2.0, 3.0)
+    );
+  }
+
+  #[test]
+  fn point_conversions() {
+    let point = Point::new(1.5f32, -2.0);
+    assert_eq!(Point::from(PhysicalPosition::new(1.5f32, -2.0)), point);
+    assert_eq!(
+      PhysicalPosition::from(point),
+      PhysicalPosition::new(1.5, -2.0)
+    );
+    assert_eq!(Point::from(LogicalPosition::new(1.5f32, -2.0)), point);
+    assert_eq!(
+      LogicalPosition::from(point),
+      LogicalPosition::new(1.5, -2.0)
+    );
+    assert_eq!(Point::from(Vec2::from(point)), point);
+    assert_eq!(<[f32; 2]>::from(point), [1.5, -2.0]);
   }
 }
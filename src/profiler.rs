@@ -0,0 +1,513 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use crate::ctx::DrawingContext;
+
+const DEFAULT_MAX_SCOPES: u32 = 32;
+// 解決待ちのフレームをいくつまで持てるか (足りなければそのフレームは計測しない)
+const READBACK_BUFFER_COUNT: usize = 4;
+const HISTORY_LEN: usize = 60;
+const MAX_TRACE_EVENTS: usize = 100_000;
+
+const MAP_PENDING: u8 = 0;
+const MAP_OK: u8 = 1;
+const MAP_FAILED: u8 = 2;
+
+pub trait TimestampWriter {
+  const FEATURE: wgpu::Features;
+
+  fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, index: u32);
+}
+
+impl TimestampWriter for wgpu::CommandEncoder {
+  const FEATURE: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+
+  fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, index: u32) {
+    wgpu::CommandEncoder::write_timestamp(self, query_set, index);
+  }
+}
+
+impl TimestampWriter for wgpu::ComputePass<'_> {
+  const FEATURE: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES;
+
+  fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, index: u32) {
+    wgpu::ComputePass::write_timestamp(self, query_set, index);
+  }
+}
+
+impl TimestampWriter for wgpu::RenderPass<'_> {
+  const FEATURE: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES;
+
+  fn write_timestamp(&mut self, query_set: &wgpu::QuerySet, index: u32) {
+    wgpu::RenderPass::write_timestamp(self, query_set, index);
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Scope {
+  end: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassTiming {
+  pub name: String,
+  pub last_ms: f64,
+  pub average_ms: f64,
+  pub min_ms: f64,
+  pub max_ms: f64,
+}
+
+// 解決できたフレームの GPU 時間
+// frame は計測したフレーム (update を呼んだ回数 - 1) で、解決は 1〜3 フレーム遅れる
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpuFrameTime {
+  pub frame: u64,
+  pub ms: f64,
+}
+
+struct ScopeRecord {
+  name: String,
+  begin: u32,
+  end: u32,
+}
+
+struct ReadbackBuffer {
+  buffer: wgpu::Buffer,
+  state: Arc<AtomicU8>,
+  frame: Option<PendingFrame>,
+}
+
+struct PendingFrame {
+  frame: u64,
+  scopes: Vec<ScopeRecord>,
+  query_count: u32,
+  map_requested: bool,
+}
+
+struct PassHistory {
+  name: String,
+  samples: VecDeque<f64>,
+}
+
+struct TraceEvent {
+  name: String,
+  begin: u64,
+  duration_ns: f64,
+}
+
+struct QueryResources {
+  query_set: wgpu::QuerySet,
+  resolve_buffer: wgpu::Buffer,
+  readback: Vec<ReadbackBuffer>,
+}
+
+pub struct GpuProfiler {
+  queries: Option<QueryResources>,
+  features: wgpu::Features,
+  max_queries: u32,
+  period: f64,
+  current: Option<usize>,
+  scopes: Vec<ScopeRecord>,
+  next_query: u32,
+  histories: Vec<PassHistory>,
+  frame: u64,
+  // 直前の update で解決できたフレーム
+  resolved: Vec<GpuFrameTime>,
+  last_frame: Option<GpuFrameTime>,
+  tracing: bool,
+  trace: Vec<TraceEvent>,
+}
+
+impl GpuProfiler {
+  // App::with_gpu_profiling() はこれらの機能を要求する
+  pub const FEATURES: wgpu::Features = wgpu::Features::TIMESTAMP_QUERY
+    .union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS)
+    .union(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES);
+
+  pub fn new(ctx: &DrawingContext) -> Self {
+    Self::with_max_scopes(ctx, DEFAULT_MAX_SCOPES)
+  }
+
+  // TIMESTAMP_QUERY が使えない場合は何も計測しない
+  pub fn with_max_scopes(ctx: &DrawingContext, max_scopes: u32) -> Self {
+    let device = &ctx.device;
+    let features = device.features() & Self::FEATURES;
+    let max_queries = max_scopes * 2;
+
+    let queries =
+      features.contains(wgpu::Features::TIMESTAMP_QUERY).then(|| {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+          label: Some("[wgsim] profiler query set"),
+          ty: wgpu::QueryType::Timestamp,
+          count: max_queries,
+        });
+
+        let size = max_queries as u64 * wgpu::QUERY_SIZE as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+          label: Some("[wgsim] profiler resolve buffer"),
+          size,
+          usage: wgpu::BufferUsages::QUERY_RESOLVE
+            | wgpu::BufferUsages::COPY_SRC,
+          mapped_at_creation: false,
+        });
+
+        let readback = (0..READBACK_BUFFER_COUNT)
+          .map(|_| ReadbackBuffer {
+            buffer: device.create_buffer(&wgpu::BufferDescriptor {
+              label: Some("[wgsim] profiler readback buffer"),
+              size,
+              usage: wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::MAP_READ,
+              mapped_at_creation: false,
+            }),
+            state: Arc::new(AtomicU8::new(MAP_PENDING)),
+            frame: None,
+          })
+          .collect();
+
+        QueryResources {
+          query_set,
+          resolve_buffer,
+          readback,
+        }
+      });
+
+    Self {
+      queries,
+      features,
+      max_queries,
+      period: ctx.queue.get_timestamp_period() as f64,
+      current: None,
+      scopes: Vec::new(),
+      next_query: 0,
+      histories: Vec::new(),
+      frame: 0,
+      resolved: Vec::new(),
+      last_frame: None,
+      tracing: false,
+      trace: Vec::new(),
+    }
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.queries.is_some()
+  }
+
+  pub fn features(&self) -> wgpu::Features {
+    self.features
+  }
+
+  // 前のフレームまでの結果を待たずに回収し、このフレームの計測を始める
+  // (1フレームに1回、draw の前に呼ぶ想定)
+  pub fn update(&mut self, ctx: &DrawingContext) {
+    self.resolved.clear();
+    let Some(queries) = &mut self.queries else {
+      self.frame += 1;
+      return;
+    };
+
+    for slot in &mut queries.readback {
+      let Some(frame) = &mut slot.frame else {
+        continue;
+      };
+      if frame.map_requested {
+        continue;
+      }
+
+      // 提出済みのコマンドに含まれるため、ここで初めて map できる
+      let state = slot.state.clone();
+      let size = frame.query_count as u64 * wgpu::QUERY_SIZE as u64;
+      slot.buffer.slice(..size).map_async(wgpu::MapMode::Read, move |result| {
+        let value = if result.is_ok() { MAP_OK } else { MAP_FAILED };
+        state.store(value, Ordering::Release);
+      });
+      frame.map_requested = true;
+    }
+
+    ctx.device.poll(wgpu::Maintain::Poll);
+
+    let mut resolved = Vec::new();
+    for slot in &mut queries.readback {
+      let state = slot.state.load(Ordering::Acquire);
+      if state == MAP_PENDING {
+        continue;
+      }
+
+      let frame = slot.frame.take().expect("mapped without a frame");
+      if state == MAP_OK {
+        let size = frame.query_count as u64 * wgpu::QUERY_SIZE as u64;
+        let data = slot.buffer.slice(..size).get_mapped_range();
+        let timestamps: Vec<u64> = bytemuck::cast_slice(&data).to_vec();
+        drop(data);
+        slot.buffer.unmap();
+        resolved.push((frame.frame, frame.scopes, timestamps));
+      }
+      slot.state.store(MAP_PENDING, Ordering::Release);
+    }
+
+    for (frame, scopes, timestamps) in resolved {
+      self.record(frame, &scopes, &timestamps);
+    }
+
+    if self.current.is_none() {
+      let queries = self.queries.as_ref().unwrap();
+      self.current = queries.readback.iter().position(|s| s.frame.is_none());
+    }
+    self.scopes.clear();
+    self.next_query = 0;
+    self.frame += 1;
+  }
+
+  // 計測中のフレームの番号 (最初の update の後が 0)
+  pub fn frame(&self) -> u64 {
+    self.frame.saturating_sub(1)
+  }
+
+  // パスの開始と終了に書き込む (TIMESTAMP_QUERY のみで使える)
+  pub fn compute_pass_timestamps(
+    &mut self,
+    name: &str,
+  ) -> Option<wgpu::ComputePassTimestampWrites<'_>> {
+    let begin = self.reserve(name)?;
+    Some(wgpu::ComputePassTimestampWrites {
+      query_set: &self.queries.as_ref()?.query_set,
+      beginning_of_pass_write_index: Some(begin),
+      end_of_pass_write_index: Some(begin + 1),
+    })
+  }
+
+  pub fn render_pass_timestamps(
+    &mut self,
+    name: &str,
+  ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+    let begin = self.reserve(name)?;
+    Some(wgpu::RenderPassTimestampWrites {
+      query_set: &self.queries.as_ref()?.query_set,
+      beginning_of_pass_write_index: Some(begin),
+      end_of_pass_write_index: Some(begin + 1),
+    })
+  }
+
+  // エンコーダやパスの途中に書き込む (対応する機能がない場合は None)
+  pub fn begin<W: TimestampWriter>(
+    &mut self,
+    writer: &mut W,
+    name: &str,
+  ) -> Option<Scope> {
+    if !self.features.contains(W::FEATURE) {
+      return None;
+    }
+
+    let begin = self.reserve(name)?;
+    writer.write_timestamp(&self.queries.as_ref()?.query_set, begin);
+    Some(Scope { end: begin + 1 })
+  }
+
+  pub fn end<W: TimestampWriter>(
+    &mut self,
+    writer: &mut W,
+    scope: Option<Scope>,
+  ) {
+    if let (Some(scope), Some(queries)) = (scope, &self.queries) {
+      writer.write_timestamp(&queries.query_set, scope.end);
+    }
+  }
+
+  // このフレームのクエリを解決する (draw の最後、submit の前に呼ぶ)
+  pub fn end_frame(&mut self, encoder: &mut wgpu::CommandEncoder) {
+    let (Some(queries), Some(current)) = (&mut self.queries, self.current)
+    else {
+      return;
+    };
+    if self.next_query == 0 {
+      return;
+    }
+
+    let slot = &mut queries.readback[current];
+    encoder.resolve_query_set(
+      &queries.query_set,
+      0..self.next_query,
+      &queries.resolve_buffer,
+      0,
+    );
+    encoder.copy_buffer_to_buffer(
+      &queries.resolve_buffer,
+      0,
+      &slot.buffer,
+      0,
+      self.next_query as u64 * wgpu::QUERY_SIZE as u64,
+    );
+
+    slot.frame = Some(PendingFrame {
+      frame: self.frame.saturating_sub(1),
+      scopes: std::mem::take(&mut self.scopes),
+      query_count: self.next_query,
+      map_requested: false,
+    });
+    self.current = None;
+    self.next_query = 0;
+  }
+
+  // 直近 HISTORY_LEN フレームの集計 (最初に現れた順)
+  pub fn timings(&self) -> Vec<PassTiming> {
+    self.histories.iter().filter_map(timing_of).collect()
+  }
+
+  pub fn timing(&self, name: &str) -> Option<PassTiming> {
+    self.histories.iter().find(|h| h.name == name).and_then(timing_of)
+  }
+
+  // 最後に解決できたフレームの、最初の開始から最後の終了までの時間
+  pub fn gpu_frame_time(&self) -> Option<GpuFrameTime> {
+    self.last_frame
+  }
+
+  // 直前の update で解決できたフレーム (古い順)
+  pub fn resolved_frames(&self) -> &[GpuFrameTime] {
+    &self.resolved
+  }
+
+  pub fn start_trace(&mut self) {
+    self.tracing = true;
+    self.trace.clear();
+  }
+
+  pub fn stop_trace(&mut self) {
+    self.tracing = false;
+  }
+
+  // chrome://tracing や Perfetto で読み込める形式
+  pub fn chrome_trace(&self) -> String {
+    // 解決される順序はフレームの順とは限らないため、最も早い時刻を起点にする
+    let origin = self.trace.iter().map(|e| e.begin).min().unwrap_or(0);
+
+    let mut json = String::from("{\"traceEvents\":[");
+    for (i, event) in self.trace.iter().enumerate() {
+      if i > 0 {
+        json.push(',');
+      }
+      let _ = write!(
+        json,
+        "{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":0,\"tid\":0}}",
+        escape_json(&event.name),
+        (event.begin - origin) as f64 * self.period / 1000.0,
+        event.duration_ns / 1000.0,
+      );
+    }
+    json.push_str("],\"displayTimeUnit\":\"ms\"}");
+    json
+  }
+
+  pub fn write_chrome_trace(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> std::io::Result<()> {
+    std::fs::write(path, self.chrome_trace())
+  }
+
+  fn reserve(&mut self, name: &str) -> Option<u32> {
+    self.current?;
+    if self.next_query + 2 > self.max_queries {
+      return None;
+    }
+
+    let begin = self.next_query;
+    self.next_query += 2;
+    self.scopes.push(ScopeRecord {
+      name: name.to_string(),
+      begin,
+      end: begin + 1,
+    });
+    Some(begin)
+  }
+
+  fn record(&mut self, frame: u64, scopes: &[ScopeRecord], timestamps: &[u64]) {
+    let mut frame_range: Option<(u64, u64)> = None;
+    let mut durations: Vec<(&str, f64)> = Vec::new();
+
+    for scope in scopes {
+      let begin = timestamps[scope.begin as usize];
+      let end = timestamps[scope.end as usize];
+      // 書き込まれなかったクエリは 0 になる
+      if begin == 0 || end < begin {
+        continue;
+      }
+
+      let duration_ns = (end - begin) as f64 * self.period;
+      match durations.iter_mut().find(|(name, _)| *name == scope.name) {
+        Some((_, total)) => *total += duration_ns,
+        None => durations.push((&scope.name, duration_ns)),
+      }
+
+      frame_range = Some(match frame_range {
+        Some((first, last)) => (first.min(begin), last.max(end)),
+        None => (begin, end),
+      });
+
+      if self.tracing && self.trace.len() < MAX_TRACE_EVENTS {
+        self.trace.push(TraceEvent {
+          name: scope.name.clone(),
+          begin,
+          duration_ns,
+        });
+      }
+    }
+
+    for (name, duration_ns) in durations {
+      let history = match self.histories.iter().position(|h| h.name == name) {
+        Some(index) => &mut self.histories[index],
+        None => {
+          self.histories.push(PassHistory {
+            name: name.to_string(),
+            samples: VecDeque::with_capacity(HISTORY_LEN),
+          });
+          self.histories.last_mut().unwrap()
+        }
+      };
+      if history.samples.len() == HISTORY_LEN {
+        history.samples.pop_front();
+      }
+      history.samples.push_back(duration_ns / 1_000_000.0);
+    }
+
+    if let Some((first, last)) = frame_range {
+      let time = GpuFrameTime {
+        frame,
+        ms: (last - first) as f64 * self.period / 1e6,
+      };
+      self.resolved.push(time);
+      self.last_frame = Some(time);
+    }
+  }
+}
+
+fn timing_of(history: &PassHistory) -> Option<PassTiming> {
+  let last_ms = *history.samples.back()?;
+  let len = history.samples.len() as f64;
+
+  Some(PassTiming {
+    name: history.name.clone(),
+    last_ms,
+    average_ms: history.samples.iter().sum::<f64>() / len,
+    min_ms: history.samples.iter().copied().fold(f64::INFINITY, f64::min),
+    max_ms: history.samples.iter().copied().fold(0.0, f64::max),
+  })
+}
+
+fn escape_json(s: &str) -> String {
+  let mut escaped = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      c if (c as u32) < 0x20 => {
+        let _ = write!(escaped, "\\u{:04x}", c as u32);
+      }
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
@@ -0,0 +1,351 @@
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{Arc, Mutex};
+
+use crate::ctx::DrawingContext;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadbackError {
+  // 読み戻し元に COPY_SRC がない
+  MissingCopySrc,
+  OutOfBounds { end: u64, size: u64 },
+  // 読み戻したバイト数が T の大きさで割り切れない
+  SizeMismatch { len: usize, element_size: usize },
+  UnsupportedFormat(wgpu::TextureFormat),
+  Map(wgpu::BufferAsyncError),
+  // 既に try_take で取り出している
+  AlreadyTaken,
+}
+
+impl std::fmt::Display for ReadbackError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::MissingCopySrc => {
+        write!(f, "the source was not created with COPY_SRC usage")
+      }
+      Self::OutOfBounds { end, size } => {
+        write!(f, "the range ends at {} but the source has {}", end, size)
+      }
+      Self::SizeMismatch { len, element_size } => write!(
+        f,
+        "{} bytes cannot be split into elements of {} bytes",
+        len, element_size
+      ),
+      Self::UnsupportedFormat(format) => {
+        write!(f, "{:?} cannot be copied to a buffer", format)
+      }
+      Self::Map(e) => write!(f, "failed to map the buffer: {}", e),
+      Self::AlreadyTaken => write!(f, "the data has already been taken"),
+    }
+  }
+}
+
+impl std::error::Error for ReadbackError {}
+
+// 読み戻すテクスチャの範囲 (size が None の場合は origin からミップの端まで)
+#[derive(Debug, Clone, Copy)]
+pub struct TextureRegion {
+  pub mip_level: u32,
+  pub origin: wgpu::Origin3d,
+  pub size: Option<wgpu::Extent3d>,
+  pub aspect: wgpu::TextureAspect,
+}
+
+impl Default for TextureRegion {
+  fn default() -> Self {
+    Self {
+      mip_level: 0,
+      origin: wgpu::Origin3d::ZERO,
+      size: None,
+      aspect: wgpu::TextureAspect::All,
+    }
+  }
+}
+
+enum Layout {
+  // ステージングバッファの offset から len バイト
+  Linear {
+    offset: usize,
+    len: usize,
+  },
+  // 行ごとのパディングを取り除く
+  Rows {
+    padded_bytes_per_row: usize,
+    bytes_per_row: usize,
+    rows: usize,
+  },
+}
+
+type MapState = Option<Result<(), wgpu::BufferAsyncError>>;
+
+// コピーを送信済みの読み戻し
+// 止めずに待つ場合は毎フレーム (や async の処理の中で繰り返し) try_take を呼ぶ
+// ネイティブでは device.poll を呼ばないと完了しないため Future にはしていない
+pub struct Readback<T> {
+  buffer: wgpu::Buffer,
+  layout: Layout,
+  submission: wgpu::SubmissionIndex,
+  state: Arc<Mutex<MapState>>,
+  taken: bool,
+  _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> Readback<T> {
+  fn new(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    encoder: wgpu::CommandEncoder,
+    buffer: wgpu::Buffer,
+    layout: Layout,
+  ) -> Self {
+    let submission = queue.submit(std::iter::once(encoder.finish()));
+
+    let state = Arc::new(Mutex::new(None));
+    let callback_state = state.clone();
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+      *callback_state.lock().unwrap() = Some(result);
+    });
+    // map_async の要求を GPU 側に伝える
+    device.poll(wgpu::Maintain::Poll);
+
+    Self {
+      buffer,
+      layout,
+      submission,
+      state,
+      taken: false,
+      _marker: PhantomData,
+    }
+  }
+
+  pub fn is_ready(&self) -> bool {
+    self.state.lock().unwrap().is_some()
+  }
+
+  // 待たずに確認し、まだ終わっていなければ None
+  pub fn try_take(
+    &mut self,
+    device: &wgpu::Device,
+  ) -> Option<Result<Vec<T>, ReadbackError>> {
+    device.poll(wgpu::Maintain::Poll);
+    self.take()
+  }
+
+  // 完了するまで待つ
+  pub fn wait(
+    mut self,
+    device: &wgpu::Device,
+  ) -> Result<Vec<T>, ReadbackError> {
+    device.poll(wgpu::Maintain::wait_for(self.submission.clone()));
+    self.take().unwrap_or(Err(ReadbackError::Map(wgpu::BufferAsyncError)))
+  }
+
+  fn take(&mut self) -> Option<Result<Vec<T>, ReadbackError>> {
+    if self.taken {
+      return Some(Err(ReadbackError::AlreadyTaken));
+    }
+    let result = self.state.lock().unwrap().clone()?;
+    self.taken = true;
+
+    if let Err(e) = result {
+      return Some(Err(ReadbackError::Map(e)));
+    }
+
+    let mapped = self.buffer.slice(..).get_mapped_range();
+    let bytes: Vec<u8> = match self.layout {
+      Layout::Linear { offset, len } => mapped[offset..offset + len].to_vec(),
+      Layout::Rows {
+        padded_bytes_per_row,
+        bytes_per_row,
+        rows,
+      } => mapped
+        .chunks(padded_bytes_per_row)
+        .take(rows)
+        .flat_map(|row| &row[..bytes_per_row])
+        .copied()
+        .collect(),
+    };
+    drop(mapped);
+    self.buffer.unmap();
+
+    Some(cast_bytes(bytes))
+  }
+}
+
+// bytes の先頭は T の境界に揃っているとは限らないため、コピーして並べ直す
+fn cast_bytes<T: bytemuck::Pod>(
+  bytes: Vec<u8>,
+) -> Result<Vec<T>, ReadbackError> {
+  let element_size = std::mem::size_of::<T>();
+  if element_size == 0 || !bytes.len().is_multiple_of(element_size) {
+    return Err(ReadbackError::SizeMismatch {
+      len: bytes.len(),
+      element_size,
+    });
+  }
+  Ok(bytemuck::pod_collect_to_vec(&bytes))
+}
+
+impl DrawingContext<'_> {
+  // range はバイト単位 (4 バイト境界に揃っていなくてもよい)
+  pub fn request_buffer_readback<T: bytemuck::Pod>(
+    &self,
+    buffer: &wgpu::Buffer,
+    range: impl RangeBounds<wgpu::BufferAddress>,
+  ) -> Result<Readback<T>, ReadbackError> {
+    if !buffer.usage().contains(wgpu::BufferUsages::COPY_SRC) {
+      return Err(ReadbackError::MissingCopySrc);
+    }
+
+    let start = match range.start_bound() {
+      Bound::Included(&start) => start,
+      Bound::Excluded(&start) => start + 1,
+      Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+      Bound::Included(&end) => end + 1,
+      Bound::Excluded(&end) => end,
+      Bound::Unbounded => buffer.size(),
+    };
+    if end > buffer.size() || start > end {
+      return Err(ReadbackError::OutOfBounds {
+        end,
+        size: buffer.size(),
+      });
+    }
+
+    // コピーの範囲は COPY_BUFFER_ALIGNMENT に揃える必要がある
+    let align = wgpu::COPY_BUFFER_ALIGNMENT;
+    let aligned_start = start / align * align;
+    let aligned_end = end.div_ceil(align) * align;
+    let copy_size = aligned_end - aligned_start;
+
+    let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("[wgsim] readback buffer"),
+      size: copy_size.max(align),
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    let mut encoder =
+      self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("[wgsim] readback encoder"),
+      });
+    if copy_size > 0 {
+      encoder.copy_buffer_to_buffer(
+        buffer,
+        aligned_start,
+        &staging,
+        0,
+        copy_size,
+      );
+    }
+
+    let layout = Layout::Linear {
+      offset: (start - aligned_start) as usize,
+      len: (end - start) as usize,
+    };
+    Ok(Readback::new(
+      &self.device,
+      &self.queue,
+      encoder,
+      staging,
+      layout,
+    ))
+  }
+
+  pub fn request_texture_readback<T: bytemuck::Pod>(
+    &self,
+    texture: &wgpu::Texture,
+    region: TextureRegion,
+  ) -> Result<Readback<T>, ReadbackError> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+      return Err(ReadbackError::MissingCopySrc);
+    }
+
+    let format = texture.format();
+    let block_size = format
+      .block_copy_size(Some(region.aspect))
+      .ok_or(ReadbackError::UnsupportedFormat(format))?;
+    let (block_width, block_height) = format.block_dimensions();
+
+    let mip_size =
+      texture.size().mip_level_size(region.mip_level, texture.dimension());
+    let size = region.size.unwrap_or(wgpu::Extent3d {
+      width: mip_size.width.saturating_sub(region.origin.x),
+      height: mip_size.height.saturating_sub(region.origin.y),
+      depth_or_array_layers: mip_size
+        .depth_or_array_layers
+        .saturating_sub(region.origin.z),
+    });
+
+    let blocks_per_row = size.width.div_ceil(block_width);
+    let rows_per_image = size.height.div_ceil(block_height);
+    let bytes_per_row = blocks_per_row * block_size;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let padded_bytes_per_row = bytes_per_row.div_ceil(align) * align;
+    let rows = rows_per_image * size.depth_or_array_layers;
+
+    let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+      label: Some("[wgsim] readback buffer"),
+      size: (padded_bytes_per_row as u64 * rows as u64).max(1),
+      usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+      mapped_at_creation: false,
+    });
+
+    let mut encoder =
+      self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("[wgsim] readback encoder"),
+      });
+    encoder.copy_texture_to_buffer(
+      wgpu::ImageCopyTexture {
+        texture,
+        mip_level: region.mip_level,
+        origin: region.origin,
+        aspect: region.aspect,
+      },
+      wgpu::ImageCopyBuffer {
+        buffer: &staging,
+        layout: wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(padded_bytes_per_row),
+          rows_per_image: Some(rows_per_image),
+        },
+      },
+      size,
+    );
+
+    let layout = Layout::Rows {
+      padded_bytes_per_row: padded_bytes_per_row as usize,
+      bytes_per_row: bytes_per_row as usize,
+      rows: rows as usize,
+    };
+    Ok(Readback::new(
+      &self.device,
+      &self.queue,
+      encoder,
+      staging,
+      layout,
+    ))
+  }
+
+  // 送信したコピーが終わるまで呼び出したスレッドを止める
+  // async の処理や update から読む場合は request_buffer_readback と
+  // try_take を使う
+  pub fn read_buffer<T: bytemuck::Pod>(
+    &self,
+    buffer: &wgpu::Buffer,
+    range: impl RangeBounds<wgpu::BufferAddress>,
+  ) -> Result<Vec<T>, ReadbackError> {
+    self.request_buffer_readback(buffer, range)?.wait(&self.device)
+  }
+
+  // read_buffer と同じく完了までスレッドを止める
+  pub fn read_texture<T: bytemuck::Pod>(
+    &self,
+    texture: &wgpu::Texture,
+    region: TextureRegion,
+  ) -> Result<Vec<T>, ReadbackError> {
+    self.request_texture_readback(texture, region)?.wait(&self.device)
+  }
+}
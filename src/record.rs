@@ -0,0 +1,401 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+use crate::capture::CapturedFrame;
+
+// エンコードが追いつかない場合に溜めておく量の既定値 (超えた分は捨てる)
+// 1080p の RGBA で 60 フレーム程度
+pub const DEFAULT_QUEUE_BYTES: usize = 512 * 1024 * 1024;
+const GIF_SPEED: i32 = 10;
+
+type EncodeResult = Result<Encoded, Box<dyn Error + Send + Sync>>;
+pub type RecordResult = Result<RecordSummary, Box<dyn Error + Send + Sync>>;
+
+struct Encoded {
+  written: usize,
+  resized: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RecordSummary {
+  pub written: usize,
+  // エンコードが追いつかず、キューに入らなかったフレーム
+  pub skipped: usize,
+  // 録画中にウィンドウの大きさが変わり、GIF に書けなかったフレーム
+  pub resized: usize,
+}
+
+impl RecordSummary {
+  pub fn dropped(&self) -> usize {
+    self.skipped + self.resized
+  }
+}
+
+impl std::fmt::Display for RecordSummary {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} frames were written", self.written)?;
+    if self.skipped > 0 {
+      write!(
+        f,
+        ", {} frames were dropped because the encoder fell behind",
+        self.skipped
+      )?;
+    }
+    if self.resized > 0 {
+      write!(
+        f,
+        ", {} frames were dropped because the window was resized",
+        self.resized
+      )?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordFormat {
+  #[default]
+  Gif,
+  // 連番の PNG と、表示時間を記した ffmpeg の concat 用ファイル (frames.txt)
+  PngSequence,
+}
+
+// 読み戻したフレームを別スレッドで書き出す
+pub struct Recorder {
+  path: PathBuf,
+  sender: Option<mpsc::Sender<CapturedFrame>>,
+  handle: Option<JoinHandle<EncodeResult>>,
+  // キューに入っていて、まだエンコードしていないフレームのバイト数
+  queued_bytes: Arc<AtomicUsize>,
+  max_queued_bytes: usize,
+  skipped: usize,
+}
+
+impl Recorder {
+  pub fn start(path: impl Into<PathBuf>, format: RecordFormat) -> Self {
+    Self::with_queue_bytes(path, format, DEFAULT_QUEUE_BYTES)
+  }
+
+  // max_queued_bytes を超えて溜まる場合はフレームを捨てる
+  pub fn with_queue_bytes(
+    path: impl Into<PathBuf>,
+    format: RecordFormat,
+    max_queued_bytes: usize,
+  ) -> Self {
+    let path = path.into();
+    let (sender, receiver) = mpsc::channel::<CapturedFrame>();
+    let queued_bytes = Arc::new(AtomicUsize::new(0));
+
+    let output = path.clone();
+    let queued = queued_bytes.clone();
+    let handle = std::thread::spawn(move || {
+      let frames = receiver.into_iter().inspect(|frame| {
+        queued.fetch_sub(frame.data.len(), Ordering::AcqRel);
+      });
+      match format {
+        RecordFormat::Gif => encode_gif(output, frames),
+        RecordFormat::PngSequence => encode_png_sequence(output, frames),
+      }
+    });
+
+    Self {
+      path,
+      sender: Some(sender),
+      handle: Some(handle),
+      queued_bytes,
+      max_queued_bytes,
+      skipped: 0,
+    }
+  }
+
+  pub fn path(&self) -> &std::path::Path {
+    &self.path
+  }
+
+  // 待たずに渡す (キューが一杯なら捨てる)
+  pub fn push(&mut self, frame: CapturedFrame) {
+    let Some(sender) = &self.sender else {
+      return;
+    };
+
+    let bytes = frame.data.len();
+    let queued = self.queued_bytes.fetch_add(bytes, Ordering::AcqRel);
+    if queued + bytes > self.max_queued_bytes {
+      self.queued_bytes.fetch_sub(bytes, Ordering::AcqRel);
+      self.skipped += 1;
+      return;
+    }
+    if sender.send(frame).is_err() {
+      // エンコードのスレッドがエラーで終わっている
+      self.queued_bytes.fetch_sub(bytes, Ordering::AcqRel);
+      self.skipped += 1;
+    }
+  }
+
+  pub fn queued_bytes(&self) -> usize {
+    self.queued_bytes.load(Ordering::Acquire)
+  }
+
+  pub fn skipped_frames(&self) -> usize {
+    self.skipped
+  }
+
+  // 残りのフレームを書き出し終えるまで待つ
+  pub fn finish(mut self) -> RecordResult {
+    self.sender.take();
+    let handle = self.handle.take().unwrap();
+    let encoded =
+      handle.join().map_err(|_| "the encoder thread panicked")??;
+
+    Ok(RecordSummary {
+      written: encoded.written,
+      skipped: self.skipped,
+      resized: encoded.resized,
+    })
+  }
+
+  // 書き出しが終わるまで待ち、結果を表示する
+  pub fn finish_and_report(self) {
+    let path = self.path.clone();
+    match self.finish() {
+      Ok(summary) => {
+        println!("Recording has been saved to {}", path.display());
+        if summary.dropped() > 0 {
+          println!("{}", summary);
+        }
+      }
+      Err(e) => eprintln!("{:?}", e),
+    }
+  }
+
+  // 書き出しの完了を待たずに止める (結果は別スレッドで表示する)
+  pub fn finish_in_background(self) {
+    std::thread::spawn(move || self.finish_and_report());
+  }
+}
+
+fn encode_gif(
+  path: PathBuf,
+  mut frames: impl Iterator<Item = CapturedFrame>,
+) -> EncodeResult {
+  use gif::{Encoder, Frame, Repeat};
+
+  let Some(first) = frames.next() else {
+    return Ok(Encoded {
+      written: 0,
+      resized: 0,
+    });
+  };
+  let (width, height) = (first.size.width as u16, first.size.height as u16);
+
+  let file = std::fs::File::create(&path)?;
+  let mut encoder = Encoder::new(file, width, height, &[])?;
+  encoder.set_repeat(Repeat::Infinite)?;
+
+  // 遅延は次のフレームが届いてから決まるため、1フレーム遅れて書き出す
+  let mut delays = FrameDelays::new(first.presented_at);
+  let mut previous = first;
+  let mut count = 0;
+  let mut resized = 0;
+
+  for frame in frames {
+    if frame.size.width != previous.size.width
+      || frame.size.height != previous.size.height
+    {
+      // GIF の大きさは途中で変えられない
+      resized += 1;
+      continue;
+    }
+
+    // 短すぎるフレームは捨て、その時間を次のフレームに回す
+    if let Some(delay) = delays.next(frame.presented_at) {
+      write_gif_frame(&mut encoder, &previous, delay)?;
+      count += 1;
+    }
+    previous = frame;
+  }
+
+  let delay = delays.last();
+  write_gif_frame(&mut encoder, &previous, delay)?;
+  count += 1;
+
+  fn write_gif_frame(
+    encoder: &mut Encoder<std::fs::File>,
+    frame: &CapturedFrame,
+    delay: u16,
+  ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut rgba = frame
+      .to_rgba8()
+      .ok_or_else(|| format!("{:?} cannot be recorded", frame.format))?
+      .into_raw();
+    let mut gif_frame = Frame::from_rgba_speed(
+      frame.size.width as u16,
+      frame.size.height as u16,
+      &mut rgba,
+      GIF_SPEED,
+    );
+    gif_frame.delay = delay;
+    encoder.write_frame(&gif_frame)?;
+    Ok(())
+  }
+
+  Ok(Encoded {
+    written: count,
+    resized,
+  })
+}
+
+fn encode_png_sequence(
+  dir: PathBuf,
+  frames: impl Iterator<Item = CapturedFrame>,
+) -> EncodeResult {
+  std::fs::create_dir_all(&dir)?;
+
+  let mut concat = String::from("ffconcat version 1.0\n");
+  let mut previous: Option<(String, Instant)> = None;
+  let mut count = 0;
+
+  for frame in frames {
+    let name = format!("frame_{:05}.png", count);
+    frame.save_png(dir.join(&name))?;
+
+    if let Some((name, presented_at)) = previous.take() {
+      let duration = frame.presented_at - presented_at;
+      let _ = writeln!(concat, "file '{}'", name);
+      let _ = writeln!(concat, "duration {:.6}", duration.as_secs_f64());
+    }
+    previous = Some((name, frame.presented_at));
+    count += 1;
+  }
+
+  // concat では最後のフレームを繰り返して書く必要がある
+  if let Some((name, _)) = previous {
+    let _ = writeln!(concat, "file '{}'", name);
+    let _ = writeln!(concat, "file '{}'", name);
+  }
+  std::fs::write(dir.join("frames.txt"), concat)?;
+
+  Ok(Encoded {
+    written: count,
+    resized: 0,
+  })
+}
+
+// GIF の遅延は 1/100 秒単位なので、丸めの誤差を次のフレームに持ち越す
+struct FrameDelays {
+  last_presented_at: Instant,
+  last_delay: u16,
+  carry: f64,
+}
+
+impl FrameDelays {
+  fn new(presented_at: Instant) -> Self {
+    Self {
+      last_presented_at: presented_at,
+      last_delay: 2,
+      carry: 0.0,
+    }
+  }
+
+  fn next(&mut self, presented_at: Instant) -> Option<u16> {
+    let elapsed = presented_at - self.last_presented_at;
+    self.last_presented_at = presented_at;
+
+    let exact = elapsed.as_secs_f64() * 100.0 + self.carry;
+    // 多くのビューアは 2 未満の遅延を正しく扱えない
+    if exact < 2.0 {
+      self.carry = exact;
+      return None;
+    }
+
+    let delay = exact.round();
+    self.carry = exact - delay;
+    self.last_delay = delay.min(u16::MAX as f64) as u16;
+    Some(self.last_delay)
+  }
+
+  fn last(&self) -> u16 {
+    self.last_delay
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::*;
+  use crate::primitive::Size;
+
+  fn frame(size: u32, presented_at: Instant) -> CapturedFrame {
+    CapturedFrame {
+      size: Size::new(size, size),
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      data: vec![128; (size * size * 4) as usize],
+      presented_at,
+    }
+  }
+
+  fn output(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+      "wgsim-record-{}-{}",
+      std::process::id(),
+      name
+    ))
+  }
+
+  #[test]
+  fn drops_frames_over_the_byte_limit() {
+    let path = output("limit.gif");
+    // 4x4 の RGBA は 64 バイトなので 1 フレームも入らない
+    let mut recorder = Recorder::with_queue_bytes(&path, RecordFormat::Gif, 63);
+    let start = Instant::now();
+    for _ in 0..3 {
+      recorder.push(frame(4, start));
+    }
+    assert_eq!(recorder.skipped_frames(), 3);
+    assert_eq!(recorder.queued_bytes(), 0);
+
+    let summary = recorder.finish().unwrap();
+    assert_eq!(
+      summary,
+      RecordSummary {
+        written: 0,
+        skipped: 3,
+        resized: 0,
+      }
+    );
+  }
+
+  #[test]
+  fn reports_frames_dropped_on_resize() {
+    let path = output("resize.gif");
+    let mut recorder = Recorder::start(&path, RecordFormat::Gif);
+    let start = Instant::now();
+    for i in 0..3 {
+      recorder.push(frame(4, start + Duration::from_millis(50 * i)));
+    }
+    recorder.push(frame(8, start + Duration::from_millis(150)));
+
+    let summary = recorder.finish().unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(
+      summary,
+      RecordSummary {
+        written: 3,
+        skipped: 0,
+        resized: 1,
+      }
+    );
+    assert_eq!(summary.dropped(), 1);
+    assert_eq!(
+      summary.to_string(),
+      "3 frames were written, \
+       1 frames were dropped because the window was resized"
+    );
+  }
+}
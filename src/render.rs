@@ -21,6 +21,11 @@ pub trait Render<'a> {
   fn process_event(&mut self, event: &WindowEvent) -> bool {
     false
   }
+  // App::with_egui で有効になり、毎フレーム update の前に呼ばれる
+  // スライダーの値は UniformBuffer::update の中で書き換えると、
+  // 変更があった場合だけ update での flush で書き込まれる
+  #[cfg(feature = "egui")]
+  fn ui(&mut self, ctx: &egui::Context) {}
   fn update(&mut self, ctx: &DrawingContext, dt: std::time::Duration) {}
   fn draw(
     &mut self,
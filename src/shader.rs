@@ -0,0 +1,388 @@
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderError {
+  // @id(...) の括弧が閉じていない、または中身が整数でない
+  InvalidId(String),
+  // override 宣言が ; で終わっていない
+  UnterminatedOverride(String),
+  // どの override の名前にも @id にも一致しない定数
+  UnknownConstant(String),
+  // override の型で表せない値 (u32 に負の値など)
+  InvalidValue {
+    name: String,
+    ty: String,
+    value: f64,
+  },
+}
+
+impl std::fmt::Display for ShaderError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::InvalidId(id) => write!(f, "invalid @id attribute: '{}'", id),
+      Self::UnterminatedOverride(name) => {
+        write!(f, "override '{}' is not terminated by ';'", name)
+      }
+      Self::UnknownConstant(name) => {
+        write!(f, "no override named '{}' or with @id({})", name, name)
+      }
+      Self::InvalidValue { name, ty, value } => {
+        write!(f, "{} is not a valid {} for override '{}'", value, ty, name)
+      }
+    }
+  }
+}
+
+impl std::error::Error for ShaderError {}
+
+// override 宣言を const 宣言に置き換える
+// wgpu 23 では @workgroup_size に override を使えないため、
+// バリアントごとに値を埋め込んだソースを生成する
+// 値の変換は wgpu (naga) のパイプライン定数と同じく、整数型は小数部を切り捨てる
+pub fn specialize(
+  source: &str,
+  constants: &HashMap<String, f64>,
+) -> Result<String, ShaderError> {
+  let mut output = String::with_capacity(source.len());
+  let mut rest = source;
+  let mut used = HashSet::new();
+
+  while let Some(decl) = find_override(rest)? {
+    let name = &rest[decl.name.clone()];
+    let id = decl.id.as_ref().map(|id| &rest[id.clone()]);
+    let key = id
+      .filter(|id| constants.contains_key(*id))
+      .or(Some(name).filter(|name| constants.contains_key(*name)));
+
+    match key {
+      Some(key) => {
+        used.insert(key);
+        let ty = decl.ty.as_ref().map(|ty| rest[ty.clone()].trim());
+        let literal = format_literal(constants[key], ty).ok_or_else(|| {
+          ShaderError::InvalidValue {
+            name: name.to_string(),
+            ty: ty.unwrap_or("abstract").to_string(),
+            value: constants[key],
+          }
+        })?;
+
+        output.push_str(&rest[..decl.start]);
+        output.push_str(&format!(
+          "const {}{} = {};",
+          name,
+          ty.map(|ty| format!(": {}", ty)).unwrap_or_default(),
+          literal
+        ));
+      }
+      None => output.push_str(&rest[..decl.end]),
+    }
+
+    rest = &rest[decl.end..];
+  }
+
+  if let Some(unknown) =
+    constants.keys().find(|name| !used.contains(name.as_str()))
+  {
+    return Err(ShaderError::UnknownConstant(unknown.clone()));
+  }
+
+  output.push_str(rest);
+  Ok(output)
+}
+
+// @id の値から override の名前を引く表
+pub fn override_ids(
+  source: &str,
+) -> Result<HashMap<String, String>, ShaderError> {
+  let mut ids = HashMap::new();
+  let mut rest = source;
+  while let Some(decl) = find_override(rest)? {
+    if let Some(id) = &decl.id {
+      ids.insert(rest[id.clone()].to_string(), rest[decl.name].to_string());
+    }
+    rest = &rest[decl.end..];
+  }
+  Ok(ids)
+}
+
+// 型で表せない値の場合は None
+fn format_literal(value: f64, ty: Option<&str>) -> Option<String> {
+  let integer = |min: f64, max: f64| {
+    let value = value.trunc();
+    (value.is_finite() && (min..=max).contains(&value)).then_some(value)
+  };
+
+  Some(match ty {
+    Some("bool") => (value != 0.0 && !value.is_nan()).to_string(),
+    Some("u32") => format!("{}u", integer(0.0, u32::MAX as f64)? as u32),
+    Some("i32") => {
+      format!("{}i", integer(i32::MIN as f64, i32::MAX as f64)? as i32)
+    }
+    Some("f32") if value.is_finite() && value.abs() <= f32::MAX as f64 => {
+      format!("{:?}f", value as f32)
+    }
+    Some("f16") if value.is_finite() && value.abs() <= 65504.0 => {
+      format!("{:?}h", value as f32)
+    }
+    Some(_) => return None,
+    None if !value.is_finite() => return None,
+    None if value.fract() == 0.0 => format!("{}", value as i64),
+    None => format!("{:?}", value),
+  })
+}
+
+struct OverrideDecl {
+  start: usize,
+  end: usize,
+  id: Option<std::ops::Range<usize>>,
+  name: std::ops::Range<usize>,
+  ty: Option<std::ops::Range<usize>>,
+}
+
+fn find_override(source: &str) -> Result<Option<OverrideDecl>, ShaderError> {
+  let bytes = source.as_bytes();
+  let mut i = 0;
+  // 直前に現れた @id(n) 属性
+  let mut id: Option<(usize, std::ops::Range<usize>)> = None;
+
+  while i < bytes.len() {
+    if source[i..].starts_with("//") {
+      i += source[i..].find('\n').unwrap_or(source.len() - i);
+      continue;
+    }
+    if source[i..].starts_with("/*") {
+      i += source[i..].find("*/").map(|n| n + 2).unwrap_or(source.len() - i);
+      continue;
+    }
+
+    if source[i..].starts_with("@id")
+      && !bytes.get(i + 3).is_some_and(|&b| is_ident_char(b))
+    {
+      let invalid = || {
+        let end = source[i..].find('\n').map_or(source.len(), |n| i + n);
+        ShaderError::InvalidId(source[i..end].trim().to_string())
+      };
+
+      let open = skip_whitespace(bytes, i + 3);
+      if bytes.get(open) != Some(&b'(') {
+        return Err(invalid());
+      }
+      let close = open + source[open..].find(')').ok_or_else(invalid)?;
+      let range = trim_range(source, open + 1..close);
+      let digits = source[range.clone()].trim_end_matches(['u', 'i']);
+      if digits.parse::<u16>().is_err() {
+        return Err(invalid());
+      }
+
+      id = Some((i, range.start..range.start + digits.len()));
+      i = close + 1;
+      continue;
+    }
+
+    if is_ident_start(bytes[i]) {
+      let word_end = ident_end(bytes, i);
+      let at_boundary = i == 0 || !is_ident_char(bytes[i - 1]);
+
+      if at_boundary && &source[i..word_end] == "override" {
+        let name_start = skip_whitespace(bytes, word_end);
+        let name_end = ident_end(bytes, name_start);
+        let semicolon = match source[name_end..].find(';') {
+          Some(n) => name_end + n,
+          None => {
+            return Err(ShaderError::UnterminatedOverride(
+              source[name_start..name_end].to_string(),
+            ))
+          }
+        };
+
+        let decl = &source[name_end..semicolon];
+        let ty = decl.trim_start().strip_prefix(':').map(|after_colon| {
+          let ty_start = semicolon - after_colon.len();
+          ty_start
+            ..ty_start + after_colon.find('=').unwrap_or(after_colon.len())
+        });
+
+        let (start, id) = match id.take() {
+          Some((start, id)) => (start, Some(id)),
+          None => (i, None),
+        };
+
+        return Ok(Some(OverrideDecl {
+          start,
+          end: semicolon + 1,
+          id,
+          name: name_start..name_end,
+          ty,
+        }));
+      }
+
+      // @id の後に別の属性や宣言が来た場合は破棄する
+      id = None;
+      i = word_end;
+      continue;
+    }
+
+    i += source[i..].chars().next().map_or(1, |c| c.len_utf8());
+  }
+
+  Ok(None)
+}
+
+fn trim_range(
+  source: &str,
+  range: std::ops::Range<usize>,
+) -> std::ops::Range<usize> {
+  let s = &source[range.clone()];
+  let start = range.start + (s.len() - s.trim_start().len());
+  let end = range.end - (s.len() - s.trim_end().len());
+  start..end
+}
+
+fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+  while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+    i += 1;
+  }
+  i
+}
+
+fn ident_end(bytes: &[u8], mut i: usize) -> usize {
+  while i < bytes.len() && is_ident_char(bytes[i]) {
+    i += 1;
+  }
+  i
+}
+
+fn is_ident_start(b: u8) -> bool {
+  b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_ident_char(b: u8) -> bool {
+  b.is_ascii_alphanumeric() || b == b'_'
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn constants(values: &[(&str, f64)]) -> HashMap<String, f64> {
+    values.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+  }
+
+  #[test]
+  fn replaces_typed_override_by_name() {
+    let source = "override radius: u32 = 4u;\nfn f() {}";
+    let output = specialize(source, &constants(&[("radius", 8.0)])).unwrap();
+    assert_eq!(output, "const radius: u32 = 8u;\nfn f() {}");
+  }
+
+  #[test]
+  fn replaces_untyped_override() {
+    let source = "override scale = 1.0;\noverride count = 2;";
+    let output =
+      specialize(source, &constants(&[("scale", 0.5), ("count", 3.0)]))
+        .unwrap();
+    assert_eq!(output, "const scale = 0.5;\nconst count = 3;");
+  }
+
+  #[test]
+  fn replaces_override_by_id() {
+    let source = "@id(7) override gain: f32 = 1.0;";
+    let output = specialize(source, &constants(&[("7", 2.5)])).unwrap();
+    assert_eq!(output, "const gain: f32 = 2.5f;");
+
+    let output = specialize(source, &constants(&[("gain", 2.5)])).unwrap();
+    assert_eq!(output, "const gain: f32 = 2.5f;");
+  }
+
+  #[test]
+  fn maps_ids_to_override_names() {
+    let source = "@id(0) override a: u32 = 1u;\noverride b: bool = true;\n\
+                  // @id(1) override c: f32;\n@id(2) override d = 1.0;";
+    let ids = override_ids(source).unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(ids["0"], "a");
+    assert_eq!(ids["2"], "d");
+  }
+
+  #[test]
+  fn keeps_overrides_without_value() {
+    let source = "@id(0) override a: u32 = 1u;\noverride b: bool = true;";
+    let output = specialize(source, &constants(&[("b", 0.0)])).unwrap();
+    assert_eq!(
+      output,
+      "@id(0) override a: u32 = 1u;\nconst b: bool = false;"
+    );
+  }
+
+  #[test]
+  fn ignores_overrides_in_comments() {
+    let source = "// override a: u32 = 1u;\n/* override a: u32; */\n\
+                  override a: u32 = 2u;";
+    let output = specialize(source, &constants(&[("a", 3.0)])).unwrap();
+    assert_eq!(
+      output,
+      "// override a: u32 = 1u;\n/* override a: u32; */\nconst a: u32 = 3u;"
+    );
+  }
+
+  #[test]
+  fn ignores_identifiers_containing_override() {
+    let source = "let my_override = 1;\noverride a: i32;";
+    let output = specialize(source, &constants(&[("a", -3.9)])).unwrap();
+    assert_eq!(output, "let my_override = 1;\nconst a: i32 = -3i;");
+  }
+
+  #[test]
+  fn specialized_workgroup_size_can_be_reflected() {
+    let source = "
+      override WG_X: u32 = 8u;
+      override WG_Y: u32 = 8u;
+      @compute @workgroup_size(WG_X, WG_Y)
+      fn cs_main() {}
+    ";
+    let output =
+      specialize(source, &constants(&[("WG_X", 32.0), ("WG_Y", 1.0)])).unwrap();
+    let size = crate::kernel::reflect_workgroup_size(&output, "cs_main");
+    assert_eq!(size.unwrap(), [32, 1, 1]);
+  }
+
+  #[test]
+  fn rejects_unknown_constant() {
+    let source = "override a: u32 = 1u;";
+    let error = specialize(source, &constants(&[("b", 1.0)])).unwrap_err();
+    assert_eq!(error, ShaderError::UnknownConstant("b".to_string()));
+  }
+
+  #[test]
+  fn rejects_values_outside_type() {
+    let source = "override a: u32 = 1u;";
+    let error = specialize(source, &constants(&[("a", -1.0)])).unwrap_err();
+    assert!(matches!(error, ShaderError::InvalidValue { .. }));
+
+    let source = "override a: i32 = 1i;";
+    let error = specialize(source, &constants(&[("a", 3e9)])).unwrap_err();
+    assert!(matches!(error, ShaderError::InvalidValue { .. }));
+
+    let source = "override a: f32 = 1.0;";
+    let error = specialize(source, &constants(&[("a", f64::NAN)])).unwrap_err();
+    assert!(matches!(error, ShaderError::InvalidValue { .. }));
+  }
+
+  #[test]
+  fn rejects_malformed_id() {
+    let source = "@id(x) override a: u32 = 1u;\noverride b: u32 = 1u;";
+    let error = specialize(source, &constants(&[("b", 2.0)])).unwrap_err();
+    assert!(matches!(error, ShaderError::InvalidId(_)));
+
+    let source = "@id(0 override a: u32 = 1u;";
+    let error = specialize(source, &HashMap::new()).unwrap_err();
+    assert!(matches!(error, ShaderError::InvalidId(_)));
+  }
+
+  #[test]
+  fn rejects_unterminated_override() {
+    let source = "override a: u32 = 1u";
+    let error = specialize(source, &HashMap::new()).unwrap_err();
+    assert_eq!(error, ShaderError::UnterminatedOverride("a".to_string()));
+  }
+}
@@ -0,0 +1,23 @@
+@group(0) @binding(0) var src_texture: texture_2d<f32>;
+@group(0) @binding(1) var src_sampler: sampler;
+
+struct VertexOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+// 画面全体を覆う1枚の三角形
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> VertexOutput {
+  let uv = vec2f(f32((i << 1u) & 2u), f32(i & 2u));
+
+  var output: VertexOutput;
+  output.position = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+  output.uv = uv;
+  return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+  return textureSample(src_texture, src_sampler, in.uv);
+}
@@ -0,0 +1,10 @@
+// CameraUniform と同じ並び
+// 使う側で @group(N) @binding(0) var<uniform> camera: Camera; を宣言する
+struct Camera {
+  view_proj: mat4x4f,
+  view: mat4x4f,
+  proj: mat4x4f,
+  inv_view_proj: mat4x4f,
+  position: vec4f,
+  viewport: vec4f,
+}
@@ -0,0 +1,59 @@
+struct FullscreenUniforms {
+  resolution: vec2f,
+  content_size: vec2f,
+  fit: u32,
+}
+
+@group(0) @binding(0) var<uniform> fullscreen: FullscreenUniforms;
+
+struct FullscreenOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+}
+
+const FIT_FILL: u32 = 0u;
+const FIT_CONTAIN: u32 = 1u;
+const FIT_COVER: u32 = 2u;
+
+fn fullscreen_scale() -> vec2f {
+  if (fullscreen.fit == FIT_FILL) {
+    return vec2f(1.0, 1.0);
+  }
+
+  let content_aspect = fullscreen.content_size.x / fullscreen.content_size.y;
+  let screen_aspect = fullscreen.resolution.x / fullscreen.resolution.y;
+  let ratio = content_aspect / screen_aspect;
+
+  // ratio < 1.0 のとき、コンテンツはスクリーンに比べて縦長
+  let taller = ratio < 1.0;
+  if (taller == (fullscreen.fit == FIT_CONTAIN)) {
+    return vec2f(ratio, 1.0);
+  }
+  return vec2f(1.0, 1.0 / ratio);
+}
+
+@vertex
+fn fullscreen_vs(@builtin(vertex_index) i: u32) -> FullscreenOutput {
+  var pos = array<vec2f, 6>(
+    vec2f( 1.0,  1.0),
+    vec2f( 1.0, -1.0),
+    vec2f(-1.0, -1.0),
+    vec2f( 1.0,  1.0),
+    vec2f(-1.0, -1.0),
+    vec2f(-1.0,  1.0),
+  );
+
+  var uv = array<vec2f, 6>(
+    vec2f(1.0, 0.0),
+    vec2f(1.0, 1.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(0.0, 0.0),
+  );
+
+  var output: FullscreenOutput;
+  output.position = vec4f(pos[i] * fullscreen_scale(), 0.0, 1.0);
+  output.uv = uv[i];
+  return output;
+}
@@ -0,0 +1,103 @@
+// 0: Quad, 1: PointSprite, 2: Arrow (InstanceShape と揃える)
+override SHAPE: u32;
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+
+// instance_attributes がインスタンスのデータから作る
+struct InstanceAttributes {
+  position: vec3f,
+  // ワールド座標での幅 (Arrow は長さ)
+  size: f32,
+  // Quad は XY 平面での回転、Arrow は向き (長さは使わない)
+  direction: vec3f,
+  color: vec4f,
+}
+
+struct InstanceOutput {
+  @builtin(position) position: vec4f,
+  @location(0) color: vec4f,
+  // 形の中での位置 (-1..1)
+  @location(1) local: vec2f,
+}
+
+fn shape_corner(v: u32) -> vec2f {
+  var quad = array<vec2f, 6>(
+    vec2f(-1.0, -1.0),
+    vec2f( 1.0, -1.0),
+    vec2f(-1.0,  1.0),
+    vec2f(-1.0,  1.0),
+    vec2f( 1.0, -1.0),
+    vec2f( 1.0,  1.0),
+  );
+  // +X を向いた軸と三角形の頭
+  var arrow = array<vec2f, 9>(
+    vec2f(-1.0, -0.12),
+    vec2f( 0.2, -0.12),
+    vec2f(-1.0,  0.12),
+    vec2f(-1.0,  0.12),
+    vec2f( 0.2, -0.12),
+    vec2f( 0.2,  0.12),
+    vec2f( 0.2, -0.45),
+    vec2f( 1.0,  0.0),
+    vec2f( 0.2,  0.45),
+  );
+
+  if (SHAPE == 2u) {
+    return arrow[v];
+  }
+  return quad[v];
+}
+
+fn instance_vertex(v: u32, a: InstanceAttributes) -> InstanceOutput {
+  let corner = shape_corner(v);
+
+  // ビュー行列の各行がカメラの右・上・後ろ (Camera2D は拡大率を含むので正規化する)
+  let view = camera.view;
+  let right = normalize(vec3f(view[0][0], view[1][0], view[2][0]));
+  let up = normalize(vec3f(view[0][1], view[1][1], view[2][1]));
+  let back = normalize(vec3f(view[0][2], view[1][2], view[2][2]));
+
+  var axis_x = right;
+  var axis_y = up;
+  if (SHAPE == 0u) {
+    var d = vec2f(1.0, 0.0);
+    if (dot(a.direction.xy, a.direction.xy) > 0.0) {
+      d = normalize(a.direction.xy);
+    }
+    axis_x = vec3f(d, 0.0);
+    axis_y = vec3f(-d.y, d.x, 0.0);
+  } else if (SHAPE == 2u) {
+    if (dot(a.direction, a.direction) > 0.0) {
+      axis_x = normalize(a.direction);
+    }
+    // 矢印の面がなるべくカメラを向くようにする
+    let side = cross(back, axis_x);
+    if (dot(side, side) > 1e-8) {
+      axis_y = normalize(side);
+    } else {
+      axis_y = normalize(cross(axis_x, right));
+    }
+  }
+
+  let world = a.position + (axis_x * corner.x + axis_y * corner.y) * a.size * 0.5;
+
+  var output: InstanceOutput;
+  output.position = camera.view_proj * vec4f(world, 1.0);
+  output.color = a.color;
+  output.local = corner;
+  return output;
+}
+
+@fragment
+fn fs_main(in: InstanceOutput) -> @location(0) vec4f {
+  if (SHAPE == 1u) {
+    // 縁を 1 ピクセル程度ぼかした円
+    let d = length(in.local);
+    let alpha = 1.0 - smoothstep(1.0 - fwidth(d), 1.0, d);
+    if (alpha <= 0.0) {
+      discard;
+    }
+    return vec4f(in.color.rgb, in.color.a * alpha);
+  }
+  return in.color;
+}
@@ -0,0 +1,79 @@
+const VALUE_COUNT: u32 = 120u;
+
+struct StatsUniforms {
+  resolution: vec2f,
+  // 左上からのオフセットと大きさ (ピクセル)
+  origin: vec2f,
+  panel_size: vec2f,
+  scale_ms: f32,
+  target_ms: f32,
+  count: u32,
+  head: u32,
+  values: array<vec4f, 30>,
+}
+
+@group(0) @binding(0) var<uniform> stats: StatsUniforms;
+
+struct StatsOutput {
+  @builtin(position) position: vec4f,
+  @location(0) local: vec2f,
+}
+
+fn value_at(i: u32) -> f32 {
+  return stats.values[i / 4u][i % 4u];
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) i: u32) -> StatsOutput {
+  var corners = array<vec2f, 6>(
+    vec2f(0.0, 0.0),
+    vec2f(1.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 0.0),
+    vec2f(1.0, 1.0),
+  );
+
+  let pixel = stats.origin + corners[i] * stats.panel_size;
+  let ndc = pixel / stats.resolution * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0);
+
+  var output: StatsOutput;
+  output.position = vec4f(ndc, 0.0, 1.0);
+  output.local = corners[i];
+  return output;
+}
+
+@fragment
+fn fs_main(in: StatsOutput) -> @location(0) vec4f {
+  let background = vec4f(0.0, 0.0, 0.0, 0.6);
+  let height = 1.0 - in.local.y;
+
+  // 目標のフレーム時間に線を引く
+  let target_height = stats.target_ms / stats.scale_ms;
+  let line_width = 1.0 / stats.panel_size.y;
+  if (abs(height - target_height) < line_width) {
+    return vec4f(1.0, 1.0, 1.0, 0.5);
+  }
+
+  // 古いものから順に左から並べる
+  let slot = u32(in.local.x * f32(VALUE_COUNT));
+  if (slot + stats.count < VALUE_COUNT) {
+    return background;
+  }
+  let age = VALUE_COUNT - 1u - slot;
+  let index = (stats.head + VALUE_COUNT - 1u - age) % VALUE_COUNT;
+  let ms = value_at(index);
+
+  if (height > ms / stats.scale_ms) {
+    return background;
+  }
+
+  if (ms > stats.target_ms * 2.0) {
+    return vec4f(0.9, 0.2, 0.2, 0.9);
+  }
+  // FrameStats がフレームを落としたとみなす間隔
+  if (ms > stats.target_ms * 1.5) {
+    return vec4f(0.9, 0.8, 0.2, 0.9);
+  }
+  return vec4f(0.3, 0.9, 0.4, 0.9);
+}
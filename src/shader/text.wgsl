@@ -0,0 +1,74 @@
+// 物理ピクセルでのターゲットの大きさ
+@group(0) @binding(0) var<uniform> viewport: vec2f;
+@group(0) @binding(1) var atlas: texture_2d<f32>;
+@group(0) @binding(2) var atlas_sampler: sampler;
+
+// ワールド座標に描くときだけ使う
+@group(1) @binding(0) var<uniform> camera: Camera;
+
+struct GlyphInstance {
+  // 文字列の基準点 (画面ではピクセル、ワールドではワールド座標)
+  @location(0) anchor: vec3f,
+  // 基準点からのグリフの左上と大きさ (y は下向き)
+  @location(1) offset: vec2f,
+  @location(2) extent: vec2f,
+  @location(3) uv_min: vec2f,
+  @location(4) uv_max: vec2f,
+  @location(5) color: vec4f,
+}
+
+struct GlyphOutput {
+  @builtin(position) position: vec4f,
+  @location(0) uv: vec2f,
+  @location(1) color: vec4f,
+}
+
+fn glyph_corner(v: u32) -> vec2f {
+  var corners = array<vec2f, 6>(
+    vec2f(0.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 0.0),
+    vec2f(1.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 1.0),
+  );
+  return corners[v];
+}
+
+fn glyph_output(position: vec4f, corner: vec2f, g: GlyphInstance) -> GlyphOutput {
+  var output: GlyphOutput;
+  output.position = position;
+  output.uv = mix(g.uv_min, g.uv_max, corner);
+  output.color = g.color;
+  return output;
+}
+
+@vertex
+fn vs_screen(@builtin(vertex_index) v: u32, g: GlyphInstance) -> GlyphOutput {
+  let corner = glyph_corner(v);
+  let pixel = g.anchor.xy + g.offset + corner * g.extent;
+  let ndc = vec2f(pixel.x / viewport.x * 2.0 - 1.0, 1.0 - pixel.y / viewport.y * 2.0);
+  return glyph_output(vec4f(ndc, 0.0, 1.0), corner, g);
+}
+
+@vertex
+fn vs_world(@builtin(vertex_index) v: u32, g: GlyphInstance) -> GlyphOutput {
+  let corner = glyph_corner(v);
+
+  // カメラの右と上に沿って並べる (Camera2D では XY 平面になる)
+  let view = camera.view;
+  let right = normalize(vec3f(view[0][0], view[1][0], view[2][0]));
+  let up = normalize(vec3f(view[0][1], view[1][1], view[2][1]));
+  let local = g.offset + corner * g.extent;
+  let world = g.anchor + right * local.x - up * local.y;
+  return glyph_output(camera.view_proj * vec4f(world, 1.0), corner, g);
+}
+
+@fragment
+fn fs_main(in: GlyphOutput) -> @location(0) vec4f {
+  let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+  if (coverage <= 0.0) {
+    discard;
+  }
+  return vec4f(in.color.rgb, in.color.a * coverage);
+}
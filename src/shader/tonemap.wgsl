@@ -0,0 +1,55 @@
+struct TonemapUniforms {
+  exposure: f32,
+  operator_id: u32,
+  encode_srgb: u32,
+  hdr_output: u32,
+}
+
+@group(2) @binding(0) var<uniform> tonemap: TonemapUniforms;
+
+fn tonemap_reinhard(c: vec3f) -> vec3f {
+  return c / (1.0 + c);
+}
+
+// Narkowicz による ACES filmic の近似
+fn tonemap_aces(c: vec3f) -> vec3f {
+  let a = 2.51;
+  let b = 0.03;
+  let d = 2.43;
+  let e = 0.59;
+  let f = 0.14;
+  return saturate((c * (a * c + b)) / (c * (d * c + e) + f));
+}
+
+fn linear_to_srgb(c: vec3f) -> vec3f {
+  let low = c * 12.92;
+  let high = 1.055 * pow(c, vec3f(1.0 / 2.4)) - 0.055;
+  return select(high, low, c <= vec3f(0.0031308));
+}
+
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let src = textureSample(effect_input, effect_sampler, in.uv);
+  var color = max(src.rgb * tonemap.exposure, vec3f(0.0));
+
+  // HDR で出力できる場合は圧縮しない
+  if tonemap.hdr_output == 0u {
+    switch tonemap.operator_id {
+      case 1u: {
+        color = tonemap_reinhard(color);
+      }
+      case 2u: {
+        color = tonemap_aces(color);
+      }
+      default: {
+        color = saturate(color);
+      }
+    }
+  }
+
+  if tonemap.encode_srgb == 1u {
+    color = linear_to_srgb(color);
+  }
+
+  return vec4f(color, src.a);
+}
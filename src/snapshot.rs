@@ -0,0 +1,572 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::capture::CapturedFrame;
+use crate::ctx::{ContextOptions, DrawingContext};
+use crate::primitive::{srgb_to_linear, Size};
+use crate::readback::{ReadbackError, TextureRegion};
+use crate::render::Render;
+
+// 1 にすると参照画像を今回の結果で置き換える
+pub const UPDATE_ENV: &str = "WGSIM_UPDATE_SNAPSHOTS";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapshotReport {
+  pub pixel_count: usize,
+  // tolerance を超えたチャンネルを含むピクセルの数
+  pub mismatched_pixels: usize,
+  pub max_channel_diff: u8,
+  // CIE76 の色差
+  pub mean_delta_e: f64,
+  pub max_delta_e: f64,
+  // 一致している場合は無限大
+  pub psnr: f64,
+}
+
+impl SnapshotReport {
+  pub fn mismatched_ratio(&self) -> f64 {
+    self.mismatched_pixels as f64 / self.pixel_count.max(1) as f64
+  }
+}
+
+impl std::fmt::Display for SnapshotReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{} / {} pixels differ (max channel diff {}), mean ΔE {:.3}, max ΔE {:.3}, PSNR {:.2} dB",
+      self.mismatched_pixels,
+      self.pixel_count,
+      self.max_channel_diff,
+      self.mean_delta_e,
+      self.max_delta_e,
+      self.psnr
+    )
+  }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  MissingReference {
+    reference: PathBuf,
+    actual: PathBuf,
+  },
+  SizeMismatch {
+    expected: Size,
+    actual: Size,
+    actual_path: PathBuf,
+  },
+  Mismatch {
+    report: SnapshotReport,
+    actual: PathBuf,
+    diff: PathBuf,
+  },
+  UnsupportedFormat(wgpu::TextureFormat),
+  Render(wgpu::SurfaceError),
+  Readback(ReadbackError),
+  Image(image::ImageError),
+}
+
+impl std::fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::MissingReference { reference, actual } => write!(
+        f,
+        "{} does not exist (the result was saved to {}, set {}=1 to accept it)",
+        reference.display(),
+        actual.display(),
+        UPDATE_ENV
+      ),
+      Self::SizeMismatch {
+        expected,
+        actual,
+        actual_path,
+      } => write!(
+        f,
+        "expected {}x{} but rendered {}x{} (saved to {})",
+        expected.width,
+        expected.height,
+        actual.width,
+        actual.height,
+        actual_path.display()
+      ),
+      Self::Mismatch {
+        report,
+        actual,
+        diff,
+      } => write!(
+        f,
+        "{} (actual: {}, diff: {})",
+        report,
+        actual.display(),
+        diff.display()
+      ),
+      Self::UnsupportedFormat(format) => {
+        write!(f, "{:?} cannot be compared", format)
+      }
+      Self::Render(e) => write!(f, "failed to render: {}", e),
+      Self::Readback(e) => write!(f, "failed to read back the frame: {}", e),
+      Self::Image(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<image::ImageError> for SnapshotError {
+  fn from(e: image::ImageError) -> Self {
+    Self::Image(e)
+  }
+}
+
+// Render の描画結果を参照画像 ({dir}/{name}.png) と比べる
+// 既定ではソフトウェアのアダプタを使うため、GPU のない CI でも実行できる
+pub struct Snapshot<'a> {
+  name: &'a str,
+  dir: PathBuf,
+  size: Size,
+  format: wgpu::TextureFormat,
+  frames: usize,
+  timestep: Duration,
+  msaa: bool,
+  options: ContextOptions,
+  tolerance: u8,
+  max_mismatched_ratio: f64,
+  max_mean_delta_e: Option<f64>,
+  min_psnr: Option<f64>,
+  update: bool,
+}
+
+impl<'a> Snapshot<'a> {
+  pub fn new(name: &'a str) -> Self {
+    // cargo test はクレートのディレクトリを CARGO_MANIFEST_DIR に入れて実行する
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+      .map(PathBuf::from)
+      .unwrap_or_default();
+
+    Self {
+      name,
+      dir: root.join("tests").join("snapshots"),
+      size: Size::new(256, 256),
+      format: wgpu::TextureFormat::Rgba8UnormSrgb,
+      frames: 1,
+      timestep: Duration::from_secs_f64(1.0 / 60.0),
+      msaa: false,
+      options: ContextOptions::new().force_fallback_adapter(true),
+      tolerance: 2,
+      max_mismatched_ratio: 0.0,
+      max_mean_delta_e: None,
+      min_psnr: None,
+      update: update_requested(),
+    }
+  }
+
+  pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+    self.dir = dir.into();
+    self
+  }
+
+  pub fn size(mut self, width: u32, height: u32) -> Self {
+    self.size = Size::new(width, height);
+    self
+  }
+
+  pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+    self.format = format;
+    self
+  }
+
+  // update には i * timestep が渡される (最後のフレームを比べる)
+  pub fn frames(mut self, frames: usize) -> Self {
+    self.frames = frames.max(1);
+    self
+  }
+
+  pub fn timestep(mut self, timestep: Duration) -> Self {
+    self.timestep = timestep;
+    self
+  }
+
+  pub fn msaa(mut self) -> Self {
+    self.msaa = true;
+    self
+  }
+
+  // 既定ではソフトウェアのアダプタを使う
+  // force_fallback_adapter(false) を渡すと既定のアダプタ (GPU) を使う
+  pub fn context_options(mut self, options: ContextOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  // チャンネルごとの差がこれ以下のピクセルは一致とみなす
+  pub fn tolerance(mut self, tolerance: u8) -> Self {
+    self.tolerance = tolerance;
+    self
+  }
+
+  // 0.0 から 1.0 (一致しないピクセルの割合)
+  pub fn max_mismatched_ratio(mut self, ratio: f64) -> Self {
+    self.max_mismatched_ratio = ratio;
+    self
+  }
+
+  pub fn max_mean_delta_e(mut self, delta_e: f64) -> Self {
+    self.max_mean_delta_e = Some(delta_e);
+    self
+  }
+
+  pub fn min_psnr(mut self, psnr: f64) -> Self {
+    self.min_psnr = Some(psnr);
+    self
+  }
+
+  // true にすると参照画像を今回の結果で置き換える
+  // 既定は WGSIM_UPDATE_SNAPSHOTS=1 のときだけ true
+  pub fn update_reference(mut self, update: bool) -> Self {
+    self.update = update;
+    self
+  }
+
+  pub fn reference_path(&self) -> PathBuf {
+    self.dir.join(format!("{}.png", self.name))
+  }
+
+  fn output_path(&self, suffix: &str) -> PathBuf {
+    self.dir.join(format!("{}.{}.png", self.name, suffix))
+  }
+
+  // 一致しなければ理由を表示して panic する (テストから呼ぶ)
+  pub fn assert<'r, R>(&self, initial: R::Initial) -> SnapshotReport
+  where
+    R: Render<'r>,
+  {
+    match pollster::block_on(self.check::<R>(initial)) {
+      Ok(report) => report,
+      Err(e) => panic!("[wgsim] snapshot '{}': {}", self.name, e),
+    }
+  }
+
+  pub async fn check<'r, R>(
+    &self,
+    initial: R::Initial,
+  ) -> Result<SnapshotReport, SnapshotError>
+  where
+    R: Render<'r>,
+  {
+    let frame = self.render::<R>(initial).await?;
+    let actual =
+      frame.to_rgba8().ok_or(SnapshotError::UnsupportedFormat(frame.format))?;
+
+    let reference_path = self.reference_path();
+    let actual_path = self.output_path("actual");
+    let diff_path = self.output_path("diff");
+
+    if self.update {
+      save(&actual, &reference_path)?;
+      remove_outputs(&[&actual_path, &diff_path]);
+      return Ok(compare(&actual, &actual, self.tolerance).0);
+    }
+
+    if !reference_path.exists() {
+      save(&actual, &actual_path)?;
+      return Err(SnapshotError::MissingReference {
+        reference: reference_path,
+        actual: actual_path,
+      });
+    }
+
+    let expected = image::open(&reference_path)?.to_rgba8();
+    if expected.dimensions() != actual.dimensions() {
+      save(&actual, &actual_path)?;
+      return Err(SnapshotError::SizeMismatch {
+        expected: Size::new(expected.width(), expected.height()),
+        actual: Size::new(actual.width(), actual.height()),
+        actual_path,
+      });
+    }
+
+    let (report, diff) = compare(&expected, &actual, self.tolerance);
+
+    let failed = report.mismatched_ratio() > self.max_mismatched_ratio
+      || self.max_mean_delta_e.is_some_and(|max| report.mean_delta_e > max)
+      || self.min_psnr.is_some_and(|min| report.psnr < min);
+    if failed {
+      save(&actual, &actual_path)?;
+      save(&diff, &diff_path)?;
+      return Err(SnapshotError::Mismatch {
+        report,
+        actual: actual_path,
+        diff: diff_path,
+      });
+    }
+
+    remove_outputs(&[&actual_path, &diff_path]);
+    Ok(report)
+  }
+
+  async fn render<'r, R>(
+    &self,
+    initial: R::Initial,
+  ) -> Result<CapturedFrame, SnapshotError>
+  where
+    R: Render<'r>,
+  {
+    let sample_count = if self.msaa { 4 } else { 1 };
+    let ctx = DrawingContext::new_for_texture_with_options(
+      self.size,
+      self.format,
+      &self.options,
+    )
+    .await
+    .with_sample_count(sample_count);
+
+    let mut renderer = R::new(&ctx, &initial).await;
+
+    let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("[wgsim] snapshot target"),
+      size: wgpu::Extent3d {
+        width: self.size.width,
+        height: self.size.height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: self.format,
+      usage: wgpu::TextureUsages::COPY_SRC
+        | wgpu::TextureUsages::RENDER_ATTACHMENT,
+      view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    for i in 0..self.frames {
+      let mut encoder =
+        ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+          label: Some("[wgsim] snapshot encoder"),
+        });
+
+      renderer.update(&ctx, self.timestep * i as u32);
+      renderer
+        .draw(&mut encoder, &view, sample_count)
+        .map_err(SnapshotError::Render)?;
+      renderer.submit(&ctx.queue, encoder, None);
+    }
+
+    let data = ctx
+      .read_texture::<u8>(&texture, TextureRegion::default())
+      .map_err(SnapshotError::Readback)?;
+
+    Ok(CapturedFrame {
+      size: self.size,
+      format: self.format,
+      data,
+      presented_at: std::time::Instant::now(),
+    })
+  }
+}
+
+fn update_requested() -> bool {
+  update_requested_from(std::env::var(UPDATE_ENV).ok().as_deref())
+}
+
+// UPDATE_ENV の値 (設定されていなければ None)
+fn update_requested_from(value: Option<&str>) -> bool {
+  value == Some("1")
+}
+
+fn save(image: &image::RgbaImage, path: &Path) -> Result<(), SnapshotError> {
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir).map_err(image::ImageError::IoError)?;
+  }
+  image.save_with_format(path, image::ImageFormat::Png)?;
+  Ok(())
+}
+
+// 前回の失敗で残ったファイルを消す
+fn remove_outputs(paths: &[&Path]) {
+  for path in paths {
+    let _ = std::fs::remove_file(path);
+  }
+}
+
+// 差分画像は一致しないピクセルを赤、それ以外を差の大きさに応じた灰色で表す
+fn compare(
+  expected: &image::RgbaImage,
+  actual: &image::RgbaImage,
+  tolerance: u8,
+) -> (SnapshotReport, image::RgbaImage) {
+  let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+  let mut mismatched_pixels = 0;
+  let mut max_channel_diff = 0;
+  let mut sum_delta_e = 0.0;
+  let mut max_delta_e: f64 = 0.0;
+  let mut sum_squared_error = 0.0;
+
+  for ((e, a), d) in
+    expected.pixels().zip(actual.pixels()).zip(diff.pixels_mut())
+  {
+    let channel_diff = (0..4).map(|i| e[i].abs_diff(a[i])).max().unwrap();
+    max_channel_diff = max_channel_diff.max(channel_diff);
+
+    for i in 0..3 {
+      sum_squared_error += (e[i] as f64 - a[i] as f64).powi(2);
+    }
+
+    let delta_e = delta_e76(e.0, a.0);
+    sum_delta_e += delta_e;
+    max_delta_e = max_delta_e.max(delta_e);
+
+    *d = if channel_diff > tolerance {
+      mismatched_pixels += 1;
+      image::Rgba([255, 0, 0, 255])
+    } else {
+      let gray =
+        32 + (channel_diff as u32 * 223 / tolerance.max(1) as u32) as u8;
+      image::Rgba([gray, gray, gray, 255])
+    };
+  }
+
+  let pixel_count = (actual.width() * actual.height()) as usize;
+  let mse = sum_squared_error / (pixel_count.max(1) * 3) as f64;
+  let psnr = if mse == 0.0 {
+    f64::INFINITY
+  } else {
+    10.0 * (255.0 * 255.0 / mse).log10()
+  };
+
+  let report = SnapshotReport {
+    pixel_count,
+    mismatched_pixels,
+    max_channel_diff,
+    mean_delta_e: sum_delta_e / pixel_count.max(1) as f64,
+    max_delta_e,
+    psnr,
+  };
+  (report, diff)
+}
+
+fn delta_e76(a: [u8; 4], b: [u8; 4]) -> f64 {
+  let (a, b) = (srgb8_to_lab(a), srgb8_to_lab(b));
+  ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+// D65 白色点の CIELAB
+fn srgb8_to_lab(rgba: [u8; 4]) -> [f64; 3] {
+  let linear = |v: u8| srgb_to_linear(v as f32 / 255.0) as f64;
+  let (r, g, b) = (linear(rgba[0]), linear(rgba[1]), linear(rgba[2]));
+
+  let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+  let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+  let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / 1.088_83;
+
+  let f = |t: f64| {
+    if t > 216.0 / 24389.0 {
+      t.cbrt()
+    } else {
+      (24389.0 / 27.0 * t + 16.0) / 116.0
+    }
+  };
+  let (fx, fy, fz) = (f(x), f(y), f(z));
+
+  [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn filled(width: u32, height: u32, rgba: [u8; 4]) -> image::RgbaImage {
+    image::RgbaImage::from_pixel(width, height, image::Rgba(rgba))
+  }
+
+  #[test]
+  fn identical_images_match() {
+    let image = filled(4, 4, [10, 200, 30, 255]);
+    let (report, diff) = compare(&image, &image, 0);
+
+    assert_eq!(report.pixel_count, 16);
+    assert_eq!(report.mismatched_pixels, 0);
+    assert_eq!(report.max_channel_diff, 0);
+    assert_eq!(report.mean_delta_e, 0.0);
+    assert_eq!(report.max_delta_e, 0.0);
+    assert_eq!(report.psnr, f64::INFINITY);
+    assert!(diff.pixels().all(|p| p.0 == [32, 32, 32, 255]));
+  }
+
+  #[test]
+  fn mismatched_pixels_are_red_in_the_diff() {
+    let expected = filled(2, 2, [100, 100, 100, 255]);
+    let mut actual = expected.clone();
+    actual.put_pixel(1, 0, image::Rgba([100, 110, 100, 255]));
+    actual.put_pixel(0, 1, image::Rgba([101, 100, 100, 255]));
+
+    let (report, diff) = compare(&expected, &actual, 2);
+    assert_eq!(report.mismatched_pixels, 1);
+    assert_eq!(report.max_channel_diff, 10);
+    assert_eq!(report.mismatched_ratio(), 0.25);
+    assert_eq!(diff.get_pixel(1, 0).0, [255, 0, 0, 255]);
+    assert_eq!(diff.get_pixel(0, 0).0, [32, 32, 32, 255]);
+    // tolerance 以下の差は灰色の明るさで表す
+    assert_eq!(diff.get_pixel(0, 1).0, [143, 143, 143, 255]);
+  }
+
+  #[test]
+  fn alpha_counts_as_a_channel_diff() {
+    let expected = filled(1, 1, [0, 0, 0, 255]);
+    let actual = filled(1, 1, [0, 0, 0, 0]);
+    let (report, _) = compare(&expected, &actual, 2);
+    assert_eq!(report.mismatched_pixels, 1);
+    assert_eq!(report.max_channel_diff, 255);
+    // PSNR と ΔE は色だけで計算する
+    assert_eq!(report.psnr, f64::INFINITY);
+    assert_eq!(report.max_delta_e, 0.0);
+  }
+
+  #[test]
+  fn psnr_of_uniform_error() {
+    // すべてのチャンネルが 1 ずれると MSE は 1
+    let expected = filled(8, 8, [50, 60, 70, 255]);
+    let actual = filled(8, 8, [51, 61, 71, 255]);
+    let (report, _) = compare(&expected, &actual, 2);
+    let psnr = 20.0 * 255f64.log10();
+    assert!((report.psnr - psnr).abs() < 1e-9, "{}", report.psnr);
+    assert_eq!(report.mismatched_pixels, 0);
+  }
+
+  #[test]
+  fn lab_of_reference_colors() {
+    let lab = srgb8_to_lab([255, 255, 255, 255]);
+    assert!((lab[0] - 100.0).abs() < 1e-3, "{:?}", lab);
+    assert!(lab[1].abs() < 1e-3 && lab[2].abs() < 1e-3, "{:?}", lab);
+    assert_eq!(srgb8_to_lab([0, 0, 0, 255]), [0.0, 0.0, 0.0]);
+
+    // sRGB の赤は L* 53.24, a* 80.09, b* 67.20
+    let red = srgb8_to_lab([255, 0, 0, 255]);
+    for (v, expected) in red.iter().zip([53.24, 80.09, 67.20]) {
+      assert!((v - expected).abs() < 0.01, "{:?}", red);
+    }
+  }
+
+  #[test]
+  fn delta_e76_is_the_lab_distance() {
+    let black = [0, 0, 0, 255];
+    let white = [255, 255, 255, 255];
+    assert!((delta_e76(black, white) - 100.0).abs() < 1e-3);
+    assert_eq!(delta_e76(white, white), 0.0);
+    assert_eq!(
+      delta_e76([255, 0, 0, 255], white),
+      delta_e76(white, [255, 0, 0, 255])
+    );
+  }
+
+  #[test]
+  fn update_env_enables_update_reference() {
+    assert!(update_requested_from(Some("1")));
+    assert!(!update_requested_from(Some("0")));
+    assert!(!update_requested_from(Some("")));
+    assert!(!update_requested_from(None));
+
+    assert!(Snapshot::new("env").update_reference(true).update);
+    assert!(!Snapshot::new("env").update_reference(false).update);
+  }
+}
@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::ctx::DrawingContext;
+use crate::layout::ShaderType;
+use crate::ppl::RenderPipelineBuilder;
+use crate::util::{BindGroupBuilder, BindGroupLayoutBuilder, UniformBuffer};
+
+const HISTORY_LEN: usize = 120;
+// 想定した間隔のこの倍率を超えたらフレームを落としたとみなす (stats.wgsl と揃える)
+const DROPPED_THRESHOLD: f64 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameSample {
+  pub frame: u64,
+  // App の開始からの経過時間
+  pub time: Duration,
+  // 前のフレームの submit からの間隔 (画面に表示された時刻ではない)
+  pub submit_interval_ms: Option<f64>,
+  pub update_ms: f64,
+  pub draw_ms: f64,
+  // update から submit までの CPU 時間
+  pub cpu_ms: f64,
+  // このフレームの GPU 時間 (timestamp query が使える場合のみ)
+  // 数フレーム後に解決されてから record_gpu で埋まる
+  pub gpu_ms: Option<f64>,
+  pub dropped: bool,
+}
+
+pub struct FrameStats {
+  history: VecDeque<FrameSample>,
+  // CSV に書き出すため、有効な場合はすべてのフレームを残す
+  log: Option<Vec<FrameSample>>,
+  expected_interval: Option<Duration>,
+  frame: u64,
+  dropped_frames: u64,
+  last_submit: Option<Instant>,
+}
+
+impl FrameStats {
+  pub fn new(expected_interval: Option<Duration>) -> Self {
+    Self {
+      history: VecDeque::with_capacity(HISTORY_LEN),
+      log: None,
+      expected_interval,
+      frame: 0,
+      dropped_frames: 0,
+      last_submit: None,
+    }
+  }
+
+  pub fn with_log(mut self) -> Self {
+    self.log = Some(Vec::new());
+    self
+  }
+
+  pub fn set_expected_interval(&mut self, interval: Option<Duration>) {
+    self.expected_interval = interval;
+  }
+
+  // 描画できなかった場合は submitted に None を渡す
+  // 返り値はこのフレームの番号 (record_gpu に渡す)
+  pub fn record(
+    &mut self,
+    time: Duration,
+    update: Duration,
+    draw: Duration,
+    cpu: Duration,
+    submitted: Option<Instant>,
+  ) -> u64 {
+    let submit_interval_ms = match (self.last_submit, submitted) {
+      (Some(last), Some(now)) => Some(ms(now - last)),
+      _ => None,
+    };
+    if submitted.is_some() {
+      self.last_submit = submitted;
+    }
+
+    let expected_ms = self
+      .expected_interval
+      .map(ms)
+      .or_else(|| self.average(|s| s.submit_interval_ms));
+    let late = match (submit_interval_ms, expected_ms) {
+      (Some(interval), Some(expected)) => {
+        interval > expected * DROPPED_THRESHOLD
+      }
+      _ => false,
+    };
+    let dropped = submitted.is_none() || late;
+    if dropped {
+      self.dropped_frames += 1;
+    }
+
+    let sample = FrameSample {
+      frame: self.frame,
+      time,
+      submit_interval_ms,
+      update_ms: ms(update),
+      draw_ms: ms(draw),
+      cpu_ms: ms(cpu),
+      gpu_ms: None,
+      dropped,
+    };
+    self.frame += 1;
+
+    if self.history.len() == HISTORY_LEN {
+      self.history.pop_front();
+    }
+    self.history.push_back(sample);
+    if let Some(log) = &mut self.log {
+      log.push(sample);
+    }
+    sample.frame
+  }
+
+  // 後から解決された GPU 時間を、計測したフレームのサンプルに書き込む
+  // 履歴から外れたフレームは無視する
+  pub fn record_gpu(&mut self, frame: u64, gpu_ms: f64) {
+    let find = |sample: &&mut FrameSample| sample.frame == frame;
+    if let Some(sample) = self.history.iter_mut().rev().find(find) {
+      sample.gpu_ms = Some(gpu_ms);
+    }
+    if let Some(sample) =
+      self.log.iter_mut().flatten().rev().find(|s| s.frame == frame)
+    {
+      sample.gpu_ms = Some(gpu_ms);
+    }
+  }
+
+  pub fn frame_count(&self) -> u64 {
+    self.frame
+  }
+
+  pub fn dropped_frames(&self) -> u64 {
+    self.dropped_frames
+  }
+
+  pub fn latest(&self) -> Option<&FrameSample> {
+    self.history.back()
+  }
+
+  // 直近 HISTORY_LEN フレーム (古い順)
+  pub fn samples(&self) -> impl Iterator<Item = &FrameSample> {
+    self.history.iter()
+  }
+
+  pub fn fps(&self) -> Option<f64> {
+    self.average(|s| s.submit_interval_ms).map(|ms| 1000.0 / ms)
+  }
+
+  pub fn average_cpu_ms(&self) -> Option<f64> {
+    self.average(|s| Some(s.cpu_ms))
+  }
+
+  pub fn average_update_ms(&self) -> Option<f64> {
+    self.average(|s| Some(s.update_ms))
+  }
+
+  pub fn average_draw_ms(&self) -> Option<f64> {
+    self.average(|s| Some(s.draw_ms))
+  }
+
+  pub fn average_gpu_ms(&self) -> Option<f64> {
+    self.average(|s| s.gpu_ms)
+  }
+
+  // ウィンドウタイトルなどに表示するための1行の要約
+  pub fn summary(&self) -> String {
+    let mut text = String::new();
+    if let Some(fps) = self.fps() {
+      let _ = write!(text, "{:.1} fps | ", fps);
+    }
+    if let (Some(cpu), Some(update), Some(draw)) = (
+      self.average_cpu_ms(),
+      self.average_update_ms(),
+      self.average_draw_ms(),
+    ) {
+      let _ = write!(
+        text,
+        "cpu {:.2} ms (update {:.2} / draw {:.2}) | ",
+        cpu, update, draw
+      );
+    }
+    if let Some(gpu) = self.average_gpu_ms() {
+      let _ = write!(text, "gpu {:.2} ms | ", gpu);
+    }
+    let _ = write!(text, "dropped {}", self.dropped_frames);
+    text
+  }
+
+  // with_log() を呼んでいない場合は直近 HISTORY_LEN フレームだけを書き出す
+  pub fn write_csv(
+    &self,
+    path: impl AsRef<std::path::Path>,
+  ) -> std::io::Result<()> {
+    let mut csv = String::from(
+      "frame,time_s,submit_interval_ms,update_ms,draw_ms,cpu_ms,gpu_ms,dropped\n",
+    );
+
+    let samples: Box<dyn Iterator<Item = &FrameSample>> = match &self.log {
+      Some(log) => Box::new(log.iter()),
+      None => Box::new(self.history.iter()),
+    };
+    for s in samples {
+      let optional = |v: Option<f64>| v.map(|v| format!("{:.4}", v));
+      let _ = writeln!(
+        csv,
+        "{},{:.6},{},{:.4},{:.4},{:.4},{},{}",
+        s.frame,
+        s.time.as_secs_f64(),
+        optional(s.submit_interval_ms).unwrap_or_default(),
+        s.update_ms,
+        s.draw_ms,
+        s.cpu_ms,
+        optional(s.gpu_ms).unwrap_or_default(),
+        u8::from(s.dropped),
+      );
+    }
+
+    std::fs::write(path, csv)
+  }
+
+  fn average(&self, f: impl Fn(&FrameSample) -> Option<f64>) -> Option<f64> {
+    let values: Vec<f64> = self.history.iter().filter_map(f).collect();
+    if values.is_empty() {
+      return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+  }
+}
+
+fn ms(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct StatsUniforms {
+  resolution: [f32; 2],
+  origin: [f32; 2],
+  panel_size: [f32; 2],
+  scale_ms: f32,
+  target_ms: f32,
+  count: u32,
+  head: u32,
+  _padding: [u32; 2],
+  values: [[f32; 4]; HISTORY_LEN / 4],
+}
+
+// 配列のメンバーは #[derive(ShaderType)] で扱えないので手で書く
+impl ShaderType for StatsUniforms {
+  const ALIGN: usize = 16;
+  const SIZE: usize = std::mem::size_of::<Self>();
+  const WGSL_TYPE: &'static str = "StatsUniforms";
+}
+
+// 直近のフレーム時間を棒グラフで左上に重ねて描く
+pub struct StatsOverlay {
+  pipeline: wgpu::RenderPipeline,
+  uniforms: UniformBuffer<StatsUniforms>,
+  bind_group: wgpu::BindGroup,
+}
+
+impl StatsOverlay {
+  pub fn new(ctx: &DrawingContext) -> Self {
+    let device = &ctx.device;
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("[wgsim] stats overlay shader"),
+      source: wgpu::ShaderSource::Wgsl(
+        include_str!("./shader/stats.wgsl").into(),
+      ),
+    });
+
+    let layout = BindGroupLayoutBuilder::new(device)
+      .label("[wgsim] stats overlay bind group layout")
+      .entry(
+        0,
+        wgpu::ShaderStages::VERTEX_FRAGMENT,
+        UniformBuffer::<StatsUniforms>::binding_type(),
+      )
+      .build();
+
+    let uniforms = UniformBuffer::new(device, bytemuck::Zeroable::zeroed());
+
+    let bind_group = BindGroupBuilder::new(device, &layout)
+      .label("[wgsim] stats overlay bind group")
+      .entry(0, uniforms.as_binding_resource())
+      .build();
+
+    let pipeline_layout =
+      device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("[wgsim] stats overlay pipeline layout"),
+        bind_group_layouts: &[&layout],
+        push_constant_ranges: &[],
+      });
+
+    let pipeline = RenderPipelineBuilder::new(ctx)
+      .vs_shader(&shader, "vs_main")
+      .fs_shader(&shader, "fs_main")
+      .pipeline_layout(&pipeline_layout)
+      .targets(vec![Some(wgpu::ColorTargetState {
+        format: ctx.format(),
+        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+        write_mask: wgpu::ColorWrites::ALL,
+      })])
+      .sample_count(1)
+      .build();
+
+    Self {
+      pipeline,
+      uniforms,
+      bind_group,
+    }
+  }
+
+  pub fn update(&mut self, ctx: &DrawingContext, stats: &FrameStats) {
+    let size = ctx.size();
+    let scale = (size.width as f32 / ctx.resolution().width as f32).max(1.0);
+
+    let target_ms =
+      stats.expected_interval.map(|d| ms(d) as f32).unwrap_or(1000.0 / 60.0);
+
+    let mut values = [[0.0; 4]; HISTORY_LEN / 4];
+    for (i, sample) in stats.history.iter().enumerate() {
+      values[i / 4][i % 4] = sample.submit_interval_ms.unwrap_or(0.0) as f32;
+    }
+
+    self.uniforms.set(StatsUniforms {
+      resolution: [size.width as f32, size.height as f32],
+      origin: [8.0 * scale, 8.0 * scale],
+      panel_size: [240.0 * scale, 64.0 * scale],
+      // 目標の3倍までを表示する
+      scale_ms: target_ms * 3.0,
+      target_ms,
+      count: stats.history.len() as u32,
+      head: (stats.history.len() % HISTORY_LEN) as u32,
+      _padding: [0; 2],
+      values,
+    });
+    self.uniforms.flush(&ctx.queue);
+  }
+
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+  ) {
+    let mut render_pass =
+      encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("[wgsim] stats overlay pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: target,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Load,
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+    render_pass.set_pipeline(&self.pipeline);
+    render_pass.set_bind_group(0, &self.bind_group, &[]);
+    render_pass.draw(0..6, 0..1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn record(stats: &mut FrameStats, submitted: Option<Instant>) -> u64 {
+    let ms = Duration::from_millis(1);
+    stats.record(Duration::ZERO, ms, ms, ms * 2, submitted)
+  }
+
+  #[test]
+  fn gpu_times_are_written_to_their_frame() {
+    let mut stats = FrameStats::new(None).with_log();
+    let start = Instant::now();
+    for i in 0..3 {
+      record(&mut stats, Some(start + Duration::from_millis(16 * i)));
+    }
+
+    // フレーム 0 の結果が 2 フレーム後に解決された場合
+    stats.record_gpu(0, 4.0);
+    let gpu: Vec<_> = stats.samples().map(|s| s.gpu_ms).collect();
+    assert_eq!(gpu, [Some(4.0), None, None]);
+    assert_eq!(stats.log.as_ref().unwrap()[0].gpu_ms, Some(4.0));
+    assert_eq!(stats.average_gpu_ms(), Some(4.0));
+
+    // 履歴にないフレームは無視する
+    stats.record_gpu(10, 1.0);
+    assert_eq!(stats.average_gpu_ms(), Some(4.0));
+  }
+
+  #[test]
+  fn submit_intervals_and_dropped_frames() {
+    let mut stats = FrameStats::new(Some(Duration::from_millis(16)));
+    let start = Instant::now();
+
+    assert_eq!(record(&mut stats, Some(start)), 0);
+    record(&mut stats, Some(start + Duration::from_millis(16)));
+    // 想定の 1.5 倍を超えた間隔と、submit できなかったフレーム
+    record(&mut stats, Some(start + Duration::from_millis(56)));
+    assert_eq!(record(&mut stats, None), 3);
+
+    let intervals: Vec<_> =
+      stats.samples().map(|s| s.submit_interval_ms).collect();
+    assert_eq!(intervals, [None, Some(16.0), Some(40.0), None]);
+    let dropped: Vec<_> = stats.samples().map(|s| s.dropped).collect();
+    assert_eq!(dropped, [false, false, true, true]);
+    assert_eq!(stats.dropped_frames(), 2);
+  }
+}
@@ -1,52 +1,589 @@
+// 垂直同期を切る場合に試す順 (どちらもなければエラー)
+const NO_VSYNC_PRESENT_MODES: &[wgpu::PresentMode] =
+  &[wgpu::PresentMode::Immediate, wgpu::PresentMode::Mailbox];
+// OutputColorSpace::ExtendedLinearSrgb になるサーフェスのフォーマット
+// (Rgb10a2Unorm は 0..1 に制限されるので SDR として扱う)
+const HDR_FORMATS: &[wgpu::TextureFormat] = &[wgpu::TextureFormat::Rgba16Float];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatPreference {
+  // 対応していなければ最初に見つかったフォーマットを使う
+  #[default]
+  Srgb,
+  // Rgba16Float (なければ Srgb と同じ)
+  Hdr,
+  // サーフェスが最初に返したフォーマット
+  Any,
+}
+
+// 書き込んだ値が出力でどう解釈されるか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputColorSpace {
+  // 線形の値を書き込めばフォーマットが sRGB に変換する (0..1 に制限される)
+  Srgb,
+  // シェーダーで sRGB に変換してから書き込む (0..1 に制限される)
+  SrgbUnencoded,
+  // 線形の値をそのまま書き込み、1.0 を超える値も切り捨てずに渡す
+  // wgpu 23 ではスワップチェーンの色空間を選べないため、1.0 を超える値が
+  // 明るく表示されるかはプラットフォーム次第 (macOS の extended sRGB など)
+  ExtendedLinearSrgb,
+}
+
+impl OutputColorSpace {
+  pub fn from_format(format: wgpu::TextureFormat) -> Self {
+    use wgpu::TextureFormat::*;
+
+    match format {
+      Rgba16Float | Rgba32Float | Rg11b10Ufloat => Self::ExtendedLinearSrgb,
+      format if format.is_srgb() => Self::Srgb,
+      _ => Self::SrgbUnencoded,
+    }
+  }
+
+  pub fn is_hdr(&self) -> bool {
+    matches!(self, Self::ExtendedLinearSrgb)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SurfaceConfigError {
+  // サーフェスがアダプタに対応していない
+  Incompatible,
+  UnsupportedFormat {
+    requested: Vec<wgpu::TextureFormat>,
+    supported: Vec<wgpu::TextureFormat>,
+  },
+  UnsupportedPresentMode {
+    requested: Vec<wgpu::PresentMode>,
+    supported: Vec<wgpu::PresentMode>,
+  },
+  UnsupportedAlphaMode {
+    requested: wgpu::CompositeAlphaMode,
+    supported: Vec<wgpu::CompositeAlphaMode>,
+  },
+  UnsupportedUsage {
+    requested: wgpu::TextureUsages,
+    supported: wgpu::TextureUsages,
+  },
+  IncompatibleViewFormat {
+    format: wgpu::TextureFormat,
+    view_format: wgpu::TextureFormat,
+  },
+}
+
+impl std::fmt::Display for SurfaceConfigError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Incompatible => {
+        write!(f, "the surface is not supported by the adapter")
+      }
+      Self::UnsupportedFormat {
+        requested,
+        supported,
+      } => write!(
+        f,
+        "none of the formats {:?} is supported (supported: {:?})",
+        requested, supported
+      ),
+      Self::UnsupportedPresentMode {
+        requested,
+        supported,
+      } => write!(
+        f,
+        "none of the present modes {:?} is supported (supported: {:?})",
+        requested, supported
+      ),
+      Self::UnsupportedAlphaMode {
+        requested,
+        supported,
+      } => write!(
+        f,
+        "alpha mode {:?} is not supported (supported: {:?})",
+        requested, supported
+      ),
+      Self::UnsupportedUsage {
+        requested,
+        supported,
+      } => write!(
+        f,
+        "usage {:?} is not supported (supported: {:?})",
+        requested, supported
+      ),
+      Self::IncompatibleViewFormat {
+        format,
+        view_format,
+      } => write!(
+        f,
+        "{:?} cannot be used as a view format of {:?}",
+        view_format, format
+      ),
+    }
+  }
+}
+
+impl std::error::Error for SurfaceConfigError {}
+
 pub struct SurfaceConfigBuilder<'a> {
   usage: wgpu::TextureUsages,
-  format: Option<wgpu::TextureFormat>,
-  present_mode: wgpu::PresentMode,
+  optional_usage: wgpu::TextureUsages,
+  // 空なら format_preference で選ぶ
+  formats: Vec<wgpu::TextureFormat>,
+  format_preference: FormatPreference,
+  // 先頭から順に試す
+  present_modes: Vec<wgpu::PresentMode>,
   alpha_mode: Option<wgpu::CompositeAlphaMode>,
   view_formats: &'a [wgpu::TextureFormat],
   desired_maximum_frame_latency: u32,
 }
 
+impl Default for SurfaceConfigBuilder<'_> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl<'a> SurfaceConfigBuilder<'a> {
   pub fn new() -> Self {
     Self {
       usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-      format: None,
-      present_mode: wgpu::PresentMode::Fifo,
+      // フレームのキャプチャに使う
+      optional_usage: wgpu::TextureUsages::COPY_SRC,
+      formats: Vec::new(),
+      format_preference: FormatPreference::Srgb,
+      present_modes: vec![wgpu::PresentMode::Fifo],
       alpha_mode: None,
       view_formats: &[],
       desired_maximum_frame_latency: 2,
     }
   }
 
-  pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
-    self.format = Some(format);
+  // 対応していなければ build がエラーを返す
+  pub fn format(self, format: wgpu::TextureFormat) -> Self {
+    self.formats(&[format])
+  }
+
+  // 先頭から順に試し、どれにも対応していなければエラー
+  pub fn formats(mut self, formats: &[wgpu::TextureFormat]) -> Self {
+    self.formats = formats.to_vec();
+    self
+  }
+
+  // format を指定しなかった場合の選び方
+  pub fn format_preference(mut self, preference: FormatPreference) -> Self {
+    self.format_preference = preference;
+    self
+  }
+
+  pub fn prefer_srgb(self) -> Self {
+    self.format_preference(FormatPreference::Srgb)
+  }
+
+  pub fn prefer_hdr(self) -> Self {
+    self.format_preference(FormatPreference::Hdr)
+  }
+
+  // 対応していない usage が含まれていれば build がエラーを返す
+  pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+    self.usage = usage;
+    self
+  }
+
+  // サーフェスが対応している場合だけ usage に加える
+  pub fn optional_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+    self.optional_usage = usage;
+    self
+  }
+
+  pub fn present_mode(self, mode: wgpu::PresentMode) -> Self {
+    self.present_modes(&[mode])
+  }
+
+  // 先頭から順に試し、どれにも対応していなければエラー
+  pub fn present_modes(mut self, modes: &[wgpu::PresentMode]) -> Self {
+    self.present_modes = modes.to_vec();
     self
   }
 
+  // false の場合は Immediate、なければ Mailbox
+  pub fn vsync(self, vsync: bool) -> Self {
+    self.present_modes(vsync_present_modes(vsync))
+  }
+
+  // 対応していなければ build がエラーを返す
   pub fn alpha_mode(mut self, mode: wgpu::CompositeAlphaMode) -> Self {
     self.alpha_mode = Some(mode);
     self
   }
 
+  // sRGB かどうかだけが異なるフォーマットを指定できる
+  pub fn view_formats(mut self, formats: &'a [wgpu::TextureFormat]) -> Self {
+    self.view_formats = formats;
+    self
+  }
+
+  pub fn desired_maximum_frame_latency(mut self, latency: u32) -> Self {
+    self.desired_maximum_frame_latency = latency;
+    self
+  }
+
   pub fn build(
     &self,
     adapter: &'a wgpu::Adapter,
     surface: &wgpu::Surface,
     width: u32,
     height: u32,
-  ) -> wgpu::SurfaceConfiguration {
-    let surface_caps = surface.get_capabilities(&adapter);
+  ) -> Result<wgpu::SurfaceConfiguration, SurfaceConfigError> {
+    self.resolve(&surface.get_capabilities(adapter), width, height)
+  }
 
-    wgpu::SurfaceConfiguration {
-      usage: self.usage,
-      format: self.format.unwrap_or(surface_caps.formats[0]),
+  // サーフェスの対応状況 caps に合わせて設定を決める
+  fn resolve(
+    &self,
+    caps: &wgpu::SurfaceCapabilities,
+    width: u32,
+    height: u32,
+  ) -> Result<wgpu::SurfaceConfiguration, SurfaceConfigError> {
+    if caps.formats.is_empty() {
+      return Err(SurfaceConfigError::Incompatible);
+    }
+
+    if !caps.usages.contains(self.usage) {
+      return Err(SurfaceConfigError::UnsupportedUsage {
+        requested: self.usage,
+        supported: caps.usages,
+      });
+    }
+
+    let format = self.resolve_format(caps)?;
+
+    if let Some(&view_format) = self
+      .view_formats
+      .iter()
+      .find(|f| f.remove_srgb_suffix() != format.remove_srgb_suffix())
+    {
+      return Err(SurfaceConfigError::IncompatibleViewFormat {
+        format,
+        view_format,
+      });
+    }
+
+    let alpha_mode = match self.alpha_mode {
+      Some(mode) if caps.alpha_modes.contains(&mode) => mode,
+      Some(mode) => {
+        return Err(SurfaceConfigError::UnsupportedAlphaMode {
+          requested: mode,
+          supported: caps.alpha_modes.clone(),
+        })
+      }
+      None => caps.alpha_modes[0],
+    };
+
+    Ok(wgpu::SurfaceConfiguration {
+      usage: self.usage | (self.optional_usage & caps.usages),
+      format,
       width,
       height,
-      present_mode: self.present_mode,
-      alpha_mode: self.alpha_mode.unwrap_or(surface_caps.alpha_modes[0]),
+      present_mode: resolve_present_mode(caps, &self.present_modes)?,
+      alpha_mode,
       view_formats: self.view_formats.to_vec(),
       desired_maximum_frame_latency: self.desired_maximum_frame_latency,
+    })
+  }
+
+  fn resolve_format(
+    &self,
+    caps: &wgpu::SurfaceCapabilities,
+  ) -> Result<wgpu::TextureFormat, SurfaceConfigError> {
+    if !self.formats.is_empty() {
+      return self
+        .formats
+        .iter()
+        .find(|f| caps.formats.contains(f))
+        .copied()
+        .ok_or_else(|| SurfaceConfigError::UnsupportedFormat {
+          requested: self.formats.clone(),
+          supported: caps.formats.clone(),
+        });
     }
+
+    let hdr = || HDR_FORMATS.iter().find(|f| caps.formats.contains(f)).copied();
+    let srgb = || caps.formats.iter().find(|f| f.is_srgb()).copied();
+
+    let format = match self.format_preference {
+      FormatPreference::Srgb => srgb(),
+      FormatPreference::Hdr => hdr().or_else(srgb),
+      FormatPreference::Any => None,
+    };
+    Ok(format.unwrap_or(caps.formats[0]))
+  }
+}
+
+pub(crate) fn vsync_present_modes(vsync: bool) -> &'static [wgpu::PresentMode] {
+  if vsync {
+    &[wgpu::PresentMode::Fifo]
+  } else {
+    NO_VSYNC_PRESENT_MODES
+  }
+}
+
+pub(crate) fn resolve_present_mode(
+  caps: &wgpu::SurfaceCapabilities,
+  modes: &[wgpu::PresentMode],
+) -> Result<wgpu::PresentMode, SurfaceConfigError> {
+  modes
+    .iter()
+    .find(|mode| caps.present_modes.contains(mode))
+    .copied()
+    .ok_or_else(|| SurfaceConfigError::UnsupportedPresentMode {
+      requested: modes.to_vec(),
+      supported: caps.present_modes.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn hdr_formats_are_classified_as_hdr() {
+    for &format in HDR_FORMATS {
+      assert!(
+        OutputColorSpace::from_format(format).is_hdr(),
+        "{:?}",
+        format
+      );
+    }
+    assert_eq!(
+      OutputColorSpace::from_format(wgpu::TextureFormat::Rgb10a2Unorm),
+      OutputColorSpace::SrgbUnencoded
+    );
+    assert_eq!(
+      OutputColorSpace::from_format(wgpu::TextureFormat::Bgra8UnormSrgb),
+      OutputColorSpace::Srgb
+    );
+  }
+
+  use wgpu::{CompositeAlphaMode, PresentMode, TextureFormat, TextureUsages};
+
+  // Bgra8Unorm を先に返し、Fifo と Mailbox に対応するサーフェス
+  fn surface_caps() -> wgpu::SurfaceCapabilities {
+    wgpu::SurfaceCapabilities {
+      formats: vec![
+        TextureFormat::Bgra8Unorm,
+        TextureFormat::Rgba16Float,
+        TextureFormat::Bgra8UnormSrgb,
+      ],
+      present_modes: vec![PresentMode::Fifo, PresentMode::Mailbox],
+      alpha_modes: vec![
+        CompositeAlphaMode::Opaque,
+        CompositeAlphaMode::PreMultiplied,
+      ],
+      usages: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_DST,
+    }
+  }
+
+  fn resolve(
+    builder: SurfaceConfigBuilder,
+    caps: &wgpu::SurfaceCapabilities,
+  ) -> Result<wgpu::SurfaceConfiguration, SurfaceConfigError> {
+    builder.resolve(caps, 640, 480)
+  }
+
+  #[test]
+  fn explicit_formats_are_tried_in_order() {
+    let caps = surface_caps();
+    let builder = SurfaceConfigBuilder::new().formats(&[
+      TextureFormat::Rgba8UnormSrgb,
+      TextureFormat::Bgra8UnormSrgb,
+      TextureFormat::Bgra8Unorm,
+    ]);
+    let config = resolve(builder, &caps).unwrap();
+    assert_eq!(config.format, TextureFormat::Bgra8UnormSrgb);
+    assert_eq!((config.width, config.height), (640, 480));
+
+    // 指定した場合は format_preference を使わない
+    let builder = SurfaceConfigBuilder::new()
+      .prefer_hdr()
+      .format(TextureFormat::Rgba8UnormSrgb);
+    assert_eq!(
+      resolve(builder, &caps),
+      Err(SurfaceConfigError::UnsupportedFormat {
+        requested: vec![TextureFormat::Rgba8UnormSrgb],
+        supported: caps.formats.clone(),
+      })
+    );
+  }
+
+  #[test]
+  fn format_preference_falls_back() {
+    let caps = surface_caps();
+    let format = |preference, caps: &wgpu::SurfaceCapabilities| {
+      let builder = SurfaceConfigBuilder::new().format_preference(preference);
+      resolve(builder, caps).unwrap().format
+    };
+    assert_eq!(
+      format(FormatPreference::Srgb, &caps),
+      TextureFormat::Bgra8UnormSrgb
+    );
+    assert_eq!(
+      format(FormatPreference::Hdr, &caps),
+      TextureFormat::Rgba16Float
+    );
+    assert_eq!(
+      format(FormatPreference::Any, &caps),
+      TextureFormat::Bgra8Unorm
+    );
+
+    // HDR がなければ sRGB、sRGB もなければ最初のフォーマット
+    let sdr = wgpu::SurfaceCapabilities {
+      formats: vec![TextureFormat::Rgb10a2Unorm, TextureFormat::Rgba8UnormSrgb],
+      ..surface_caps()
+    };
+    assert_eq!(
+      format(FormatPreference::Hdr, &sdr),
+      TextureFormat::Rgba8UnormSrgb
+    );
+    let linear = wgpu::SurfaceCapabilities {
+      formats: vec![TextureFormat::Rgb10a2Unorm, TextureFormat::Bgra8Unorm],
+      ..surface_caps()
+    };
+    assert_eq!(
+      format(FormatPreference::Srgb, &linear),
+      TextureFormat::Rgb10a2Unorm
+    );
+    assert_eq!(
+      format(FormatPreference::Hdr, &linear),
+      TextureFormat::Rgb10a2Unorm
+    );
+  }
+
+  #[test]
+  fn present_modes_are_tried_in_order() {
+    let caps = surface_caps();
+    let modes = [
+      PresentMode::Immediate,
+      PresentMode::Mailbox,
+      PresentMode::Fifo,
+    ];
+    assert_eq!(
+      resolve_present_mode(&caps, &modes),
+      Ok(PresentMode::Mailbox)
+    );
+    assert_eq!(
+      resolve(SurfaceConfigBuilder::new(), &caps).unwrap().present_mode,
+      PresentMode::Fifo
+    );
+
+    // vsync(false) は Immediate、なければ Mailbox
+    let no_vsync = SurfaceConfigBuilder::new().vsync(false);
+    assert_eq!(
+      resolve(no_vsync, &caps).unwrap().present_mode,
+      PresentMode::Mailbox
+    );
+    let immediate = wgpu::SurfaceCapabilities {
+      present_modes: vec![
+        PresentMode::Fifo,
+        PresentMode::Mailbox,
+        PresentMode::Immediate,
+      ],
+      ..surface_caps()
+    };
+    let no_vsync = SurfaceConfigBuilder::new().vsync(false);
+    assert_eq!(
+      resolve(no_vsync, &immediate).unwrap().present_mode,
+      PresentMode::Immediate
+    );
+
+    let fifo_only = wgpu::SurfaceCapabilities {
+      present_modes: vec![PresentMode::Fifo],
+      ..surface_caps()
+    };
+    let no_vsync = SurfaceConfigBuilder::new().vsync(false);
+    assert_eq!(
+      resolve(no_vsync, &fifo_only),
+      Err(SurfaceConfigError::UnsupportedPresentMode {
+        requested: vec![PresentMode::Immediate, PresentMode::Mailbox],
+        supported: vec![PresentMode::Fifo],
+      })
+    );
+  }
+
+  #[test]
+  fn optional_usage_is_limited_to_supported_usages() {
+    let caps = surface_caps();
+    let builder = SurfaceConfigBuilder::new()
+      .optional_usage(TextureUsages::COPY_SRC | TextureUsages::COPY_DST);
+    assert_eq!(
+      resolve(builder, &caps).unwrap().usage,
+      TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_DST
+    );
+
+    // 既定の COPY_SRC は対応していなければ付かない
+    assert_eq!(
+      resolve(SurfaceConfigBuilder::new(), &caps).unwrap().usage,
+      TextureUsages::RENDER_ATTACHMENT
+    );
+
+    // usage は必須
+    let builder = SurfaceConfigBuilder::new()
+      .usage(TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC);
+    assert_eq!(
+      resolve(builder, &caps),
+      Err(SurfaceConfigError::UnsupportedUsage {
+        requested: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        supported: caps.usages,
+      })
+    );
+  }
+
+  #[test]
+  fn view_formats_and_alpha_modes_are_checked() {
+    let caps = surface_caps();
+    let view_formats = [TextureFormat::Bgra8Unorm];
+    let builder = SurfaceConfigBuilder::new().view_formats(&view_formats);
+    let config = resolve(builder, &caps).unwrap();
+    assert_eq!(config.view_formats, view_formats);
+
+    let view_formats = [TextureFormat::Rgba8Unorm];
+    let builder = SurfaceConfigBuilder::new().view_formats(&view_formats);
+    assert_eq!(
+      resolve(builder, &caps),
+      Err(SurfaceConfigError::IncompatibleViewFormat {
+        format: TextureFormat::Bgra8UnormSrgb,
+        view_format: TextureFormat::Rgba8Unorm,
+      })
+    );
+
+    // 指定しなければ最初のアルファモード
+    assert_eq!(
+      resolve(SurfaceConfigBuilder::new(), &caps).unwrap().alpha_mode,
+      CompositeAlphaMode::Opaque
+    );
+    let builder =
+      SurfaceConfigBuilder::new().alpha_mode(CompositeAlphaMode::PreMultiplied);
+    assert_eq!(
+      resolve(builder, &caps).unwrap().alpha_mode,
+      CompositeAlphaMode::PreMultiplied
+    );
+    let builder = SurfaceConfigBuilder::new()
+      .alpha_mode(CompositeAlphaMode::PostMultiplied);
+    assert_eq!(
+      resolve(builder, &caps),
+      Err(SurfaceConfigError::UnsupportedAlphaMode {
+        requested: CompositeAlphaMode::PostMultiplied,
+        supported: caps.alpha_modes.clone(),
+      })
+    );
+  }
+
+  #[test]
+  fn incompatible_surface_has_no_formats() {
+    assert_eq!(
+      resolve(
+        SurfaceConfigBuilder::new(),
+        &wgpu::SurfaceCapabilities::default()
+      ),
+      Err(SurfaceConfigError::Incompatible)
+    );
   }
 }
@@ -0,0 +1,765 @@
+use std::collections::HashMap;
+
+use ab_glyph::{Font, FontArc, GlyphId, PxScale, ScaleFont};
+
+use crate::camera::{camera_bind_group_layout, CAMERA_WGSL};
+use crate::ctx::DrawingContext;
+use crate::layout::VertexLayout;
+use crate::ppl::RenderPipelineBuilder;
+use crate::primitive::{Color, Point, Rect, Size, Vec2, Vec3};
+use crate::util::{BindGroupBuilder, BindGroupLayoutBuilder, UniformBuffer};
+
+const TEXT_WGSL: &str = include_str!("./shader/text.wgsl");
+
+// 同梱のフォント (DejaVu Sans Mono、ライセンスは src/font/LICENSE)
+pub const DEFAULT_FONT: &[u8] = include_bytes!("./font/DejaVuSansMono.ttf");
+
+const ATLAS_SIZE: u32 = 1024;
+// ワールド座標の文字はこの大きさでラスタライズして拡大縮小する
+const WORLD_RASTER_PX: f32 = 64.0;
+
+#[derive(Debug)]
+pub enum TextError {
+  InvalidFont(ab_glyph::InvalidFont),
+  // 1 回の prepare で使うグリフがアトラスに収まらない
+  AtlasFull,
+}
+
+impl std::fmt::Display for TextError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::InvalidFont(e) => write!(f, "[wgsim] invalid font: {}", e),
+      Self::AtlasFull => write!(
+        f,
+        "[wgsim] glyphs do not fit in the {}x{} text atlas",
+        ATLAS_SIZE, ATLAS_SIZE
+      ),
+    }
+  }
+}
+
+impl std::error::Error for TextError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::InvalidFont(e) => Some(e),
+      Self::AtlasFull => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HorizontalAlign {
+  #[default]
+  Left,
+  Center,
+  Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+  #[default]
+  Top,
+  Middle,
+  // 1 行目のベースラインを基準点に合わせる
+  Baseline,
+  Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextPosition {
+  // 左上を原点とする物理ピクセル (y は下向き)、size もピクセル
+  Screen(Point),
+  // ワールド座標、size もワールドの単位 (カメラの右と上に沿って並ぶ)
+  World(Vec3),
+}
+
+// 1 回分の文字列 (改行で複数行になる)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+  pub content: String,
+  pub position: TextPosition,
+  pub size: f32,
+  pub color: Color,
+  pub align: HorizontalAlign,
+  pub vertical_align: VerticalAlign,
+  // 行の間隔 (フォントの行の高さに対する倍率)
+  pub line_spacing: f32,
+}
+
+impl Text {
+  pub fn new(content: impl Into<String>) -> Self {
+    Self {
+      content: content.into(),
+      position: TextPosition::Screen(Point::new(0.0, 0.0)),
+      size: 16.0,
+      color: Color::WHITE,
+      align: HorizontalAlign::Left,
+      vertical_align: VerticalAlign::Top,
+      line_spacing: 1.0,
+    }
+  }
+
+  // 画面のピクセル座標に置く
+  pub fn at(mut self, x: f32, y: f32) -> Self {
+    self.position = TextPosition::Screen(Point::new(x, y));
+    self
+  }
+
+  // ワールド座標に置く (TextRenderer::draw にカメラを渡す)
+  pub fn at_world(mut self, position: Vec3) -> Self {
+    self.position = TextPosition::World(position);
+    self
+  }
+
+  pub fn size(mut self, size: f32) -> Self {
+    self.size = size;
+    self
+  }
+
+  pub fn color(mut self, color: Color) -> Self {
+    self.color = color;
+    self
+  }
+
+  pub fn align(mut self, align: HorizontalAlign) -> Self {
+    self.align = align;
+    self
+  }
+
+  pub fn vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+    self.vertical_align = vertical_align;
+    self
+  }
+
+  pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+    self.line_spacing = line_spacing;
+    self
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GlyphInstance {
+  anchor: [f32; 3],
+  offset: [f32; 2],
+  extent: [f32; 2],
+  uv_min: [f32; 2],
+  uv_max: [f32; 2],
+  color: [f32; 4],
+}
+
+impl VertexLayout for GlyphInstance {
+  const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+    0 => Float32x3,
+    1 => Float32x2,
+    2 => Float32x2,
+    3 => Float32x2,
+    4 => Float32x2,
+    5 => Float32x4,
+  ];
+  const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Instance;
+}
+
+// ベースライン上のペンの位置 (基準点から、y は下向き)
+struct LayoutGlyph {
+  id: GlyphId,
+  pen: Vec2,
+}
+
+fn layout_text(font: &FontArc, text: &Text) -> (Vec<LayoutGlyph>, Rect) {
+  let scaled = font.as_scaled(PxScale::from(text.size));
+  let ascent = scaled.ascent();
+  let descent = scaled.descent();
+  let line_height = (ascent - descent + scaled.line_gap()) * text.line_spacing;
+
+  let lines: Vec<&str> = text.content.split('\n').collect();
+  let height = ascent - descent + line_height * (lines.len() - 1) as f32;
+  let top = match text.vertical_align {
+    VerticalAlign::Top => 0.0,
+    VerticalAlign::Middle => -height * 0.5,
+    VerticalAlign::Baseline => -ascent,
+    VerticalAlign::Bottom => -height,
+  };
+  let align_offset = |width: f32| match text.align {
+    HorizontalAlign::Left => 0.0,
+    HorizontalAlign::Center => -width * 0.5,
+    HorizontalAlign::Right => -width,
+  };
+
+  let mut glyphs = Vec::new();
+  let mut width: f32 = 0.0;
+  for (i, line) in lines.iter().enumerate() {
+    let mut caret = 0.0;
+    let mut previous = None;
+    let first = glyphs.len();
+    for c in line.chars().filter(|c| !c.is_control()) {
+      let id = font.glyph_id(c);
+      if let Some(previous) = previous {
+        caret += scaled.kern(previous, id);
+      }
+      glyphs.push(LayoutGlyph {
+        id,
+        pen: Vec2::new(caret, 0.0),
+      });
+      caret += scaled.h_advance(id);
+      previous = Some(id);
+    }
+
+    // 行ごとに揃える
+    let x = align_offset(caret);
+    let baseline = top + ascent + line_height * i as f32;
+    for glyph in &mut glyphs[first..] {
+      glyph.pen += Vec2::new(x, baseline);
+    }
+    width = width.max(caret);
+  }
+
+  (glyphs, Rect::new(align_offset(width), top, width, height))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AtlasGlyph {
+  uv_min: [f32; 2],
+  uv_max: [f32; 2],
+  // ペンの位置からのビットマップの左上と大きさ (ラスタライズしたピクセル)
+  offset: Vec2,
+  extent: Vec2,
+}
+
+// R8 のテクスチャに行単位でグリフを詰める
+struct GlyphAtlas {
+  texture: wgpu::Texture,
+  // 輪郭のないグリフ (空白など) は None
+  glyphs: HashMap<(GlyphId, u32), Option<AtlasGlyph>>,
+  cursor: (u32, u32),
+  row_height: u32,
+}
+
+impl GlyphAtlas {
+  fn new(device: &wgpu::Device) -> Self {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+      label: Some("[wgsim] text atlas"),
+      size: wgpu::Extent3d {
+        width: ATLAS_SIZE,
+        height: ATLAS_SIZE,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::R8Unorm,
+      usage: wgpu::TextureUsages::TEXTURE_BINDING
+        | wgpu::TextureUsages::COPY_DST,
+      view_formats: &[],
+    });
+
+    Self {
+      texture,
+      glyphs: HashMap::new(),
+      cursor: (0, 0),
+      row_height: 0,
+    }
+  }
+
+  fn clear(&mut self) {
+    self.glyphs.clear();
+    self.cursor = (0, 0);
+    self.row_height = 0;
+  }
+
+  fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+    if self.cursor.0 + width > ATLAS_SIZE {
+      self.cursor = (0, self.cursor.1 + self.row_height);
+      self.row_height = 0;
+    }
+    if self.cursor.0 + width > ATLAS_SIZE || self.cursor.1 + height > ATLAS_SIZE
+    {
+      return None;
+    }
+
+    let origin = self.cursor;
+    self.cursor.0 += width;
+    self.row_height = self.row_height.max(height);
+    Some(origin)
+  }
+
+  fn get(
+    &mut self,
+    queue: &wgpu::Queue,
+    font: &FontArc,
+    id: GlyphId,
+    raster_px: u32,
+  ) -> Result<Option<AtlasGlyph>, TextError> {
+    if let Some(glyph) = self.glyphs.get(&(id, raster_px)) {
+      return Ok(*glyph);
+    }
+
+    let outlined = font.outline_glyph(id.with_scale(raster_px as f32));
+    let Some(outlined) = outlined else {
+      self.glyphs.insert((id, raster_px), None);
+      return Ok(None);
+    };
+    let bounds = outlined.px_bounds();
+    let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+
+    // 線形補間で隣のグリフがにじまないよう、周りに 1 ピクセルの余白を付ける
+    let (padded_width, padded_height) = (width + 2, height + 2);
+    let (x, y) =
+      self.allocate(padded_width, padded_height).ok_or(TextError::AtlasFull)?;
+
+    let mut pixels = vec![0u8; (padded_width * padded_height) as usize];
+    outlined.draw(|gx, gy, coverage| {
+      if gx < width && gy < height {
+        let i = (gy + 1) * padded_width + gx + 1;
+        pixels[i as usize] = (coverage.clamp(0.0, 1.0) * 255.0).round() as u8;
+      }
+    });
+    queue.write_texture(
+      wgpu::ImageCopyTexture {
+        texture: &self.texture,
+        mip_level: 0,
+        origin: wgpu::Origin3d { x, y, z: 0 },
+        aspect: wgpu::TextureAspect::All,
+      },
+      &pixels,
+      wgpu::ImageDataLayout {
+        offset: 0,
+        bytes_per_row: Some(padded_width),
+        rows_per_image: None,
+      },
+      wgpu::Extent3d {
+        width: padded_width,
+        height: padded_height,
+        depth_or_array_layers: 1,
+      },
+    );
+
+    let atlas_size = ATLAS_SIZE as f32;
+    let glyph = AtlasGlyph {
+      uv_min: [(x + 1) as f32 / atlas_size, (y + 1) as f32 / atlas_size],
+      uv_max: [
+        (x + 1 + width) as f32 / atlas_size,
+        (y + 1 + height) as f32 / atlas_size,
+      ],
+      offset: Vec2::new(bounds.min.x, bounds.min.y),
+      extent: Vec2::new(width as f32, height as f32),
+    };
+    self.glyphs.insert((id, raster_px), Some(glyph));
+    Ok(Some(glyph))
+  }
+}
+
+// 文字列をまとめて描く
+// update で queue → prepare し、draw (または render) で描画する
+pub struct TextRenderer {
+  font: FontArc,
+  atlas: GlyphAtlas,
+  viewport: UniformBuffer<Size<f32>>,
+  bind_group: wgpu::BindGroup,
+  screen_pipeline: wgpu::RenderPipeline,
+  world_pipeline: wgpu::RenderPipeline,
+  texts: Vec<Text>,
+  instance_buffer: wgpu::Buffer,
+  // instance_buffer にはワールド座標の文字、画面の文字の順に並ぶ
+  world_count: u32,
+  screen_count: u32,
+}
+
+impl TextRenderer {
+  // 同梱のフォントを使う
+  pub fn new(ctx: &DrawingContext) -> Self {
+    Self::with_font(ctx, DEFAULT_FONT.to_vec())
+      .expect("[wgsim] the bundled font must be valid")
+  }
+
+  // TTF / OTF のデータから作る
+  pub fn with_font(
+    ctx: &DrawingContext,
+    font_data: Vec<u8>,
+  ) -> Result<Self, TextError> {
+    let font =
+      FontArc::try_from_vec(font_data).map_err(TextError::InvalidFont)?;
+    let device = &ctx.device;
+
+    let atlas = GlyphAtlas::new(device);
+    let atlas_view =
+      atlas.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("[wgsim] text atlas sampler"),
+      mag_filter: wgpu::FilterMode::Linear,
+      min_filter: wgpu::FilterMode::Linear,
+      ..Default::default()
+    });
+    let viewport = UniformBuffer::new(device, ctx.size().to_f32());
+
+    let layout = BindGroupLayoutBuilder::new(device)
+      .label("[wgsim] text bind group layout")
+      .entry(
+        0,
+        wgpu::ShaderStages::VERTEX,
+        UniformBuffer::<Size<f32>>::binding_type(),
+      )
+      .texture(
+        1,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::TextureSampleType::Float { filterable: true },
+        wgpu::TextureViewDimension::D2,
+      )
+      .sampler(
+        2,
+        wgpu::ShaderStages::FRAGMENT,
+        wgpu::SamplerBindingType::Filtering,
+      )
+      .build();
+    let bind_group = BindGroupBuilder::new(device, &layout)
+      .label("[wgsim] text bind group")
+      .entry(0, viewport.as_binding_resource())
+      .texture_view(1, &atlas_view)
+      .sampler(2, &sampler)
+      .build();
+    let camera_layout = camera_bind_group_layout(device);
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+      label: Some("[wgsim] text shader"),
+      source: wgpu::ShaderSource::Wgsl(
+        format!("{}\n{}", CAMERA_WGSL, TEXT_WGSL).into(),
+      ),
+    });
+    let vertex_buffer_layout = [GlyphInstance::layout()];
+    let build_pipeline = |entry: &str, layouts: &[&wgpu::BindGroupLayout]| {
+      let pipeline_layout =
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+          label: Some("[wgsim] text pipeline layout"),
+          bind_group_layouts: layouts,
+          push_constant_ranges: &[],
+        });
+      RenderPipelineBuilder::new(ctx)
+        .vs_shader(&shader, entry)
+        .fs_shader(&shader, "fs_main")
+        .pipeline_layout(&pipeline_layout)
+        .vertex_buffer_layout(&vertex_buffer_layout)
+        .targets(vec![Some(wgpu::ColorTargetState {
+          format: ctx.format(),
+          blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+          write_mask: wgpu::ColorWrites::ALL,
+        })])
+        .build()
+    };
+    let screen_pipeline = build_pipeline("vs_screen", &[&layout.layout]);
+    let world_pipeline =
+      build_pipeline("vs_world", &[&layout.layout, &camera_layout.layout]);
+
+    Ok(Self {
+      font,
+      atlas,
+      viewport,
+      bind_group,
+      screen_pipeline,
+      world_pipeline,
+      texts: Vec::new(),
+      instance_buffer: create_instance_buffer(device, 256),
+      world_count: 0,
+      screen_count: 0,
+    })
+  }
+
+  pub fn font(&self) -> &FontArc {
+    &self.font
+  }
+
+  // 次の prepare で描く文字列を積む
+  pub fn queue(&mut self, text: Text) {
+    self.texts.push(text);
+  }
+
+  // 基準点から見た文字列の範囲 (text.size の単位、y は下向き)
+  pub fn measure(&self, text: &Text) -> Rect {
+    layout_text(&self.font, text).1
+  }
+
+  // 積んだ文字列をレイアウトしてアップロードする (Render::update で呼ぶ)
+  // 描いた文字列は消えるので、毎回 queue し直す
+  pub fn prepare(&mut self, ctx: &DrawingContext) -> Result<(), TextError> {
+    self.viewport.set(ctx.size().to_f32());
+    self.viewport.flush(&ctx.queue);
+
+    let texts = std::mem::take(&mut self.texts);
+    let result = match self.build_instances(&ctx.queue, &texts) {
+      // 古いグリフを捨てて、今回使う分だけで詰め直す
+      Err(TextError::AtlasFull) => {
+        self.atlas.clear();
+        self.build_instances(&ctx.queue, &texts)
+      }
+      result => result,
+    };
+    // 失敗した場合は前回の (アトラスが変わった) 文字列も描かない
+    let (world, screen) = result.inspect_err(|_| {
+      self.world_count = 0;
+      self.screen_count = 0;
+    })?;
+
+    let count = world.len() + screen.len();
+    let capacity = self.instance_buffer.size() as usize
+      / std::mem::size_of::<GlyphInstance>();
+    if count > capacity {
+      self.instance_buffer =
+        create_instance_buffer(&ctx.device, count.next_power_of_two());
+    }
+    if count > 0 {
+      let instances = [world.as_slice(), screen.as_slice()].concat();
+      ctx.queue.write_buffer(
+        &self.instance_buffer,
+        0,
+        bytemuck::cast_slice(&instances),
+      );
+    }
+    self.world_count = world.len() as u32;
+    self.screen_count = screen.len() as u32;
+
+    Ok(())
+  }
+
+  fn build_instances(
+    &mut self,
+    queue: &wgpu::Queue,
+    texts: &[Text],
+  ) -> Result<(Vec<GlyphInstance>, Vec<GlyphInstance>), TextError> {
+    let mut world = Vec::new();
+    let mut screen = Vec::new();
+
+    for text in texts {
+      let (glyphs, _) = layout_text(&self.font, text);
+
+      // 画面ではピクセルに合わせてにじみを抑える
+      let (anchor, raster_px, snap) = match text.position {
+        TextPosition::Screen(point) => (
+          Vec3::new(point.x.round(), point.y.round(), 0.0),
+          text.size.round().max(1.0),
+          true,
+        ),
+        TextPosition::World(position) => (position, WORLD_RASTER_PX, false),
+      };
+      let scale = text.size / raster_px;
+
+      for glyph in glyphs {
+        let atlas_glyph =
+          self.atlas.get(queue, &self.font, glyph.id, raster_px as u32)?;
+        let Some(atlas_glyph) = atlas_glyph else {
+          continue;
+        };
+
+        let pen = if snap {
+          Vec2::new(glyph.pen.x.round(), glyph.pen.y.round())
+        } else {
+          glyph.pen
+        };
+        let instance = GlyphInstance {
+          anchor: anchor.to_array(),
+          offset: (pen + atlas_glyph.offset * scale).to_array(),
+          extent: (atlas_glyph.extent * scale).to_array(),
+          uv_min: atlas_glyph.uv_min,
+          uv_max: atlas_glyph.uv_max,
+          color: text.color.into(),
+        };
+        match text.position {
+          TextPosition::Screen(_) => screen.push(instance),
+          TextPosition::World(_) => world.push(instance),
+        }
+      }
+    }
+
+    Ok((world, screen))
+  }
+
+  // 深度バッファのないレンダーパスに描く
+  // ワールド座標の文字列がある場合は CameraBinding::bind_group を渡す
+  pub fn render(
+    &self,
+    pass: &mut wgpu::RenderPass,
+    camera: Option<&wgpu::BindGroup>,
+  ) {
+    pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+    pass.set_bind_group(0, &self.bind_group, &[]);
+
+    if self.world_count > 0 {
+      let camera = camera
+        .expect("[wgsim] world-space text needs a camera bind group to draw");
+      pass.set_pipeline(&self.world_pipeline);
+      pass.set_bind_group(1, camera, &[]);
+      pass.draw(0..6, 0..self.world_count);
+    }
+    if self.screen_count > 0 {
+      pass.set_pipeline(&self.screen_pipeline);
+      let end = self.world_count + self.screen_count;
+      pass.draw(0..6, self.world_count..end);
+    }
+  }
+
+  // view の内容の上に重ねて描く
+  pub fn draw(
+    &self,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+    camera: Option<&wgpu::BindGroup>,
+  ) {
+    if self.world_count + self.screen_count == 0 {
+      return;
+    }
+
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+      label: Some("[wgsim] text pass"),
+      color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+        view,
+        resolve_target: None,
+        ops: wgpu::Operations {
+          load: wgpu::LoadOp::Load,
+          store: wgpu::StoreOp::Store,
+        },
+      })],
+      ..Default::default()
+    });
+    self.render(&mut pass, camera);
+  }
+}
+
+fn create_instance_buffer(
+  device: &wgpu::Device,
+  capacity: usize,
+) -> wgpu::Buffer {
+  device.create_buffer(&wgpu::BufferDescriptor {
+    label: Some("[wgsim] text instance buffer"),
+    size: (capacity * std::mem::size_of::<GlyphInstance>())
+      as wgpu::BufferAddress,
+    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    mapped_at_creation: false,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Metrics {
+    advance: f32,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+  }
+
+  fn font() -> (FontArc, Metrics) {
+    let font = FontArc::try_from_slice(DEFAULT_FONT).unwrap();
+    let scaled = font.as_scaled(PxScale::from(20.0));
+    let metrics = Metrics {
+      // 等幅なのでどの文字も同じ
+      advance: scaled.h_advance(font.glyph_id('a')),
+      ascent: scaled.ascent(),
+      descent: scaled.descent(),
+      line_gap: scaled.line_gap(),
+    };
+    (font, metrics)
+  }
+
+  fn pens(glyphs: &[LayoutGlyph]) -> Vec<(f32, f32)> {
+    glyphs.iter().map(|g| (g.pen.x, g.pen.y)).collect()
+  }
+
+  #[track_caller]
+  fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+  }
+
+  #[test]
+  fn horizontal_align_offsets_each_line() {
+    let (font, m) = font();
+    let a = m.advance;
+    let text = Text::new("ab\nabcd").size(20.0);
+
+    let cases = [
+      (HorizontalAlign::Left, [0.0, 0.0]),
+      (HorizontalAlign::Center, [-a, -2.0 * a]),
+      (HorizontalAlign::Right, [-2.0 * a, -4.0 * a]),
+    ];
+    for (align, [first, second]) in cases {
+      let (glyphs, rect) = layout_text(&font, &text.clone().align(align));
+      let xs: Vec<f32> = glyphs.iter().map(|g| g.pen.x).collect();
+      let expected = [
+        first,
+        first + a,
+        second,
+        second + a,
+        second + 2.0 * a,
+        second + 3.0 * a,
+      ];
+      for (x, expected) in xs.iter().zip(expected) {
+        assert_near(*x, expected);
+      }
+
+      // 範囲は一番長い行で決まる
+      assert_near(rect.origin.x, second);
+      assert_near(rect.size.width, 4.0 * a);
+    }
+  }
+
+  #[test]
+  fn vertical_align_moves_the_first_baseline() {
+    let (font, m) = font();
+    let height = m.ascent - m.descent;
+    let text = Text::new("a").size(20.0);
+
+    let cases = [
+      (VerticalAlign::Top, 0.0),
+      (VerticalAlign::Middle, -height * 0.5),
+      (VerticalAlign::Baseline, -m.ascent),
+      (VerticalAlign::Bottom, -height),
+    ];
+    for (align, top) in cases {
+      let (glyphs, rect) =
+        layout_text(&font, &text.clone().vertical_align(align));
+      assert_near(glyphs[0].pen.y, top + m.ascent);
+      assert_near(rect.origin.y, top);
+      assert_near(rect.size.height, height);
+    }
+
+    // Baseline では 1 行目のベースラインが基準点に乗る
+    let (glyphs, _) =
+      layout_text(&font, &text.clone().vertical_align(VerticalAlign::Baseline));
+    assert_eq!(pens(&glyphs), [(0.0, 0.0)]);
+  }
+
+  #[test]
+  fn lines_are_spaced_by_the_line_height() {
+    let (font, m) = font();
+    let line_height = (m.ascent - m.descent + m.line_gap) * 1.5;
+    let text = Text::new("a\n\nb").size(20.0).line_spacing(1.5);
+
+    // 空の行も 1 行として数える
+    let (glyphs, rect) = layout_text(&font, &text);
+    assert_eq!(glyphs.len(), 2);
+    assert_near(glyphs[0].pen.y, m.ascent);
+    assert_near(glyphs[1].pen.y, m.ascent + line_height * 2.0);
+    assert_near(rect.size.height, m.ascent - m.descent + line_height * 2.0);
+  }
+
+  #[test]
+  fn measure_covers_all_lines() {
+    let (font, m) = font();
+    let text = Text::new("hello\r\nwgsim!")
+      .size(20.0)
+      .align(HorizontalAlign::Center)
+      .vertical_align(VerticalAlign::Bottom);
+
+    // 制御文字 (\r) は幅を持たない
+    let (glyphs, rect) = layout_text(&font, &text);
+    assert_eq!(glyphs.len(), 11);
+    let line_height = m.ascent - m.descent + m.line_gap;
+    let height = m.ascent - m.descent + line_height;
+    assert_near(rect.origin.x, -3.0 * m.advance);
+    assert_near(rect.origin.y, -height);
+    assert_near(rect.size.width, 6.0 * m.advance);
+    assert_near(rect.size.height, height);
+
+    let empty = layout_text(&font, &Text::new("").size(20.0)).1;
+    assert_eq!(empty.size.width, 0.0);
+    assert_near(empty.size.height, m.ascent - m.descent);
+  }
+}
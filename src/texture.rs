@@ -0,0 +1,644 @@
+use std::path::Path;
+
+use crate::ctx::DrawingContext;
+use crate::primitive::{srgb_to_linear, Size};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+  Srgb,
+  Linear,
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+  Image(image::ImageError),
+  EmptyArray,
+  CubeNotSquare(Size),
+  // 画素データの長さがサイズと形式に合わない
+  DataSize {
+    expected: usize,
+    found: usize,
+  },
+  // 配列やキューブマップの各面のサイズが揃っていない
+  LayerSize {
+    layer: usize,
+    expected: Size,
+    found: Size,
+  },
+  LayerFormat {
+    layer: usize,
+    expected: wgpu::TextureFormat,
+    found: wgpu::TextureFormat,
+  },
+}
+
+impl std::fmt::Display for TextureError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Image(e) => write!(f, "failed to load image: {}", e),
+      Self::EmptyArray => write!(f, "texture array must not be empty"),
+      Self::CubeNotSquare(size) => write!(
+        f,
+        "cube map faces must be square, got {}x{}",
+        size.width, size.height
+      ),
+      Self::DataSize { expected, found } => write!(
+        f,
+        "pixel data has {} bytes but the texture needs {}",
+        found, expected
+      ),
+      Self::LayerSize {
+        layer,
+        expected,
+        found,
+      } => write!(
+        f,
+        "layer {} is {}x{} but layer 0 is {}x{}",
+        layer, found.width, found.height, expected.width, expected.height
+      ),
+      Self::LayerFormat {
+        layer,
+        expected,
+        found,
+      } => write!(
+        f,
+        "layer {} has format {:?} but layer 0 has {:?}",
+        layer, found, expected
+      ),
+    }
+  }
+}
+
+impl std::error::Error for TextureError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      Self::Image(e) => Some(e),
+      _ => None,
+    }
+  }
+}
+
+impl From<image::ImageError> for TextureError {
+  fn from(e: image::ImageError) -> Self {
+    Self::Image(e)
+  }
+}
+
+pub struct Texture {
+  pub texture: wgpu::Texture,
+  pub view: wgpu::TextureView,
+  pub sampler: wgpu::Sampler,
+  pub view_dimension: wgpu::TextureViewDimension,
+}
+
+impl Texture {
+  pub fn size(&self) -> Size {
+    Size::new(self.texture.width(), self.texture.height())
+  }
+
+  pub fn format(&self) -> wgpu::TextureFormat {
+    self.texture.format()
+  }
+
+  pub fn is_filterable(&self) -> bool {
+    !matches!(self.format(), wgpu::TextureFormat::Rgba32Float)
+  }
+
+  pub fn binding_type(&self) -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float {
+        filterable: self.is_filterable(),
+      },
+      view_dimension: self.view_dimension,
+      multisampled: false,
+    }
+  }
+
+  pub fn sampler_binding_type(&self) -> wgpu::BindingType {
+    if self.is_filterable() {
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+    } else {
+      wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)
+    }
+  }
+}
+
+// テクスチャ1枚分の画素データ
+enum Pixels {
+  Rgba8(Vec<u8>),
+  Rgba16Float(Vec<u16>),
+  Rgba32Float(Vec<f32>),
+}
+
+impl Pixels {
+  fn from_image(image: &image::DynamicImage, color_space: ColorSpace) -> Self {
+    use image::DynamicImage::*;
+
+    let to_f16 = |v: f32| half::f16::from_f32(v).to_bits();
+
+    match image {
+      ImageRgb32F(_) | ImageRgba32F(_) => Self::Rgba16Float(
+        image.to_rgba32f().iter().map(|v| to_f16(*v)).collect(),
+      ),
+      // 16 ビットの sRGB 形式はないため、線形に変換して f16 で持つ
+      ImageLuma16(_) | ImageLumaA16(_) | ImageRgb16(_) | ImageRgba16(_) => {
+        let data = image
+          .to_rgba32f()
+          .chunks_exact(4)
+          .flat_map(|pixel| {
+            let color = |v: f32| match color_space {
+              ColorSpace::Srgb => srgb_to_linear(v),
+              ColorSpace::Linear => v,
+            };
+            [color(pixel[0]), color(pixel[1]), color(pixel[2]), pixel[3]]
+          })
+          .map(to_f16)
+          .collect();
+        Self::Rgba16Float(data)
+      }
+      _ => Self::Rgba8(image.to_rgba8().into_raw()),
+    }
+  }
+
+  fn bytes(&self) -> &[u8] {
+    match self {
+      Self::Rgba8(data) => data,
+      Self::Rgba16Float(data) => bytemuck::cast_slice(data),
+      Self::Rgba32Float(data) => bytemuck::cast_slice(data),
+    }
+  }
+
+  fn format(&self, color_space: ColorSpace) -> wgpu::TextureFormat {
+    match (self, color_space) {
+      (Self::Rgba8(_), ColorSpace::Srgb) => wgpu::TextureFormat::Rgba8UnormSrgb,
+      (Self::Rgba8(_), ColorSpace::Linear) => wgpu::TextureFormat::Rgba8Unorm,
+      (Self::Rgba16Float(_), _) => wgpu::TextureFormat::Rgba16Float,
+      (Self::Rgba32Float(_), _) => wgpu::TextureFormat::Rgba32Float,
+    }
+  }
+}
+
+pub struct TextureBuilder<'a> {
+  device: &'a wgpu::Device,
+  queue: &'a wgpu::Queue,
+  label: &'a str,
+  color_space: ColorSpace,
+  mipmaps: bool,
+  filter: wgpu::FilterMode,
+  address_mode: wgpu::AddressMode,
+  usage: wgpu::TextureUsages,
+}
+
+impl<'a> TextureBuilder<'a> {
+  pub fn new(ctx: &'a DrawingContext) -> Self {
+    Self {
+      device: &ctx.device,
+      queue: &ctx.queue,
+      label: "[wgsim] texture",
+      color_space: ColorSpace::Srgb,
+      mipmaps: false,
+      filter: wgpu::FilterMode::Linear,
+      address_mode: wgpu::AddressMode::ClampToEdge,
+      usage: wgpu::TextureUsages::empty(),
+    }
+  }
+
+  pub fn label(mut self, label: &'a str) -> Self {
+    self.label = label;
+    self
+  }
+
+  pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+    self.color_space = color_space;
+    self
+  }
+
+  pub fn mipmaps(mut self, enabled: bool) -> Self {
+    self.mipmaps = enabled;
+    self
+  }
+
+  pub fn filter(mut self, filter: wgpu::FilterMode) -> Self {
+    self.filter = filter;
+    self
+  }
+
+  pub fn address_mode(mut self, address_mode: wgpu::AddressMode) -> Self {
+    self.address_mode = address_mode;
+    self
+  }
+
+  pub fn usage(mut self, usage: wgpu::TextureUsages) -> Self {
+    self.usage = usage;
+    self
+  }
+
+  pub fn from_path(
+    &self,
+    path: impl AsRef<Path>,
+  ) -> Result<Texture, TextureError> {
+    Ok(self.from_image(&image::open(path)?))
+  }
+
+  // HDR / OpenEXR もフォーマットを判別して読み込む
+  pub fn from_bytes(&self, bytes: &[u8]) -> Result<Texture, TextureError> {
+    Ok(self.from_image(&image::load_from_memory(bytes)?))
+  }
+
+  // 16 ビットの画像は Rgba16Float、HDR の画像は Rgba16Float になる
+  pub fn from_image(&self, image: &image::DynamicImage) -> Texture {
+    let size = Size::new(image.width(), image.height());
+    let pixels = Pixels::from_image(image, self.color_space);
+    self.create(size, &[pixels], wgpu::TextureViewDimension::D2)
+  }
+
+  pub fn from_rgba8(
+    &self,
+    size: Size,
+    data: &[u8],
+  ) -> Result<Texture, TextureError> {
+    self.create_single(size, Pixels::Rgba8(data.to_vec()))
+  }
+
+  pub fn from_rgba16f(
+    &self,
+    size: Size,
+    data: &[half::f16],
+  ) -> Result<Texture, TextureError> {
+    let data = data.iter().map(|v| v.to_bits()).collect();
+    self.create_single(size, Pixels::Rgba16Float(data))
+  }
+
+  pub fn from_rgba32f(
+    &self,
+    size: Size,
+    data: &[f32],
+  ) -> Result<Texture, TextureError> {
+    self.create_single(size, Pixels::Rgba32Float(data.to_vec()))
+  }
+
+  fn create_single(
+    &self,
+    size: Size,
+    pixels: Pixels,
+  ) -> Result<Texture, TextureError> {
+    let format = pixels.format(self.color_space);
+    check_data_size(size, format, pixels.bytes().len())?;
+    Ok(self.create(size, &[pixels], wgpu::TextureViewDimension::D2))
+  }
+
+  // 面の順序は +X, -X, +Y, -Y, +Z, -Z
+  pub fn cube_from_images(
+    &self,
+    faces: &[image::DynamicImage; 6],
+  ) -> Result<Texture, TextureError> {
+    let size = Size::new(faces[0].width(), faces[0].height());
+    if size.width != size.height {
+      return Err(TextureError::CubeNotSquare(size));
+    }
+
+    let layers = self.layers(faces)?;
+    Ok(self.create(size, &layers, wgpu::TextureViewDimension::Cube))
+  }
+
+  // 1 枚だけでも texture_2d_array としてバインドできる D2Array になる
+  pub fn array_from_images(
+    &self,
+    images: &[image::DynamicImage],
+  ) -> Result<Texture, TextureError> {
+    if images.is_empty() {
+      return Err(TextureError::EmptyArray);
+    }
+    let size = Size::new(images[0].width(), images[0].height());
+
+    let layers = self.layers(images)?;
+    Ok(self.create(size, &layers, wgpu::TextureViewDimension::D2Array))
+  }
+
+  // 各層のサイズと形式が 0 番目と揃っているか確かめる
+  fn layers(
+    &self,
+    images: &[image::DynamicImage],
+  ) -> Result<Vec<Pixels>, TextureError> {
+    let expected = Size::new(images[0].width(), images[0].height());
+    let layers = images
+      .iter()
+      .map(|image| Pixels::from_image(image, self.color_space))
+      .collect::<Vec<_>>();
+    let format = layers[0].format(self.color_space);
+
+    for (layer, (image, pixels)) in images.iter().zip(&layers).enumerate() {
+      let found = Size::new(image.width(), image.height());
+      if found != expected {
+        return Err(TextureError::LayerSize {
+          layer,
+          expected,
+          found,
+        });
+      }
+      if pixels.format(self.color_space) != format {
+        return Err(TextureError::LayerFormat {
+          layer,
+          expected: format,
+          found: pixels.format(self.color_space),
+        });
+      }
+    }
+
+    Ok(layers)
+  }
+
+  // layers のサイズと形式は呼び出し側で確かめておく
+  fn create(
+    &self,
+    size: Size,
+    layers: &[Pixels],
+    view_dimension: wgpu::TextureViewDimension,
+  ) -> Texture {
+    let format = layers[0].format(self.color_space);
+    let bytes_per_row = format.block_copy_size(None).unwrap() * size.width;
+
+    let layer_count = layers.len() as u32;
+
+    // Rgba32Float はフィルタリングできないため、ミップマップは作らない
+    let mipmaps = self.mipmaps && format != wgpu::TextureFormat::Rgba32Float;
+    let mip_level_count = if mipmaps {
+      size.width.max(size.height).max(1).ilog2() + 1
+    } else {
+      1
+    };
+
+    let mut usage = self.usage
+      | wgpu::TextureUsages::TEXTURE_BINDING
+      | wgpu::TextureUsages::COPY_DST;
+    if mip_level_count > 1 {
+      usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+    }
+
+    let extent = wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: layer_count,
+    };
+
+    let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+      label: Some(self.label),
+      size: extent,
+      mip_level_count,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format,
+      usage,
+      view_formats: &[],
+    });
+
+    for (i, layer) in layers.iter().enumerate() {
+      self.queue.write_texture(
+        wgpu::ImageCopyTexture {
+          texture: &texture,
+          mip_level: 0,
+          origin: wgpu::Origin3d {
+            x: 0,
+            y: 0,
+            z: i as u32,
+          },
+          aspect: wgpu::TextureAspect::All,
+        },
+        layer.bytes(),
+        wgpu::ImageDataLayout {
+          offset: 0,
+          bytes_per_row: Some(bytes_per_row),
+          rows_per_image: Some(size.height),
+        },
+        wgpu::Extent3d {
+          depth_or_array_layers: 1,
+          ..extent
+        },
+      );
+    }
+
+    if mip_level_count > 1 {
+      generate_mipmaps(self.device, self.queue, &texture);
+    }
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+      dimension: Some(view_dimension),
+      ..Default::default()
+    });
+
+    let filter = if format == wgpu::TextureFormat::Rgba32Float {
+      wgpu::FilterMode::Nearest
+    } else {
+      self.filter
+    };
+
+    let sampler = self.device.create_sampler(&wgpu::SamplerDescriptor {
+      label: Some("[wgsim] texture sampler"),
+      address_mode_u: self.address_mode,
+      address_mode_v: self.address_mode,
+      address_mode_w: self.address_mode,
+      mag_filter: filter,
+      min_filter: filter,
+      mipmap_filter: filter,
+      ..Default::default()
+    });
+
+    Texture {
+      texture,
+      view,
+      sampler,
+      view_dimension,
+    }
+  }
+}
+
+fn check_data_size(
+  size: Size,
+  format: wgpu::TextureFormat,
+  len: usize,
+) -> Result<(), TextureError> {
+  let block_size = format.block_copy_size(None).unwrap() as usize;
+  let expected = block_size * size.width as usize * size.height as usize;
+  if len != expected {
+    return Err(TextureError::DataSize {
+      expected,
+      found: len,
+    });
+  }
+  Ok(())
+}
+
+// 1つ上のミップレベルを縮小描画して、全レベルを GPU 上で生成する
+pub fn generate_mipmaps(
+  device: &wgpu::Device,
+  queue: &wgpu::Queue,
+  texture: &wgpu::Texture,
+) {
+  let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: Some("[wgsim] mipmap shader"),
+    source: wgpu::ShaderSource::Wgsl(include_str!("./shader/blit.wgsl").into()),
+  });
+
+  let pipeline =
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+      label: Some("[wgsim] mipmap pipeline"),
+      layout: None,
+      vertex: wgpu::VertexState {
+        module: &shader,
+        entry_point: Some("vs_main"),
+        buffers: &[],
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+      },
+      fragment: Some(wgpu::FragmentState {
+        module: &shader,
+        entry_point: Some("fs_main"),
+        targets: &[Some(texture.format().into())],
+        compilation_options: wgpu::PipelineCompilationOptions::default(),
+      }),
+      primitive: wgpu::PrimitiveState::default(),
+      depth_stencil: None,
+      multisample: wgpu::MultisampleState::default(),
+      multiview: None,
+      cache: None,
+    });
+
+  let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+    label: Some("[wgsim] mipmap sampler"),
+    mag_filter: wgpu::FilterMode::Linear,
+    min_filter: wgpu::FilterMode::Linear,
+    ..Default::default()
+  });
+
+  let bind_group_layout = pipeline.get_bind_group_layout(0);
+
+  let mut encoder =
+    device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+      label: Some("[wgsim] mipmap encoder"),
+    });
+
+  for layer in 0..texture.depth_or_array_layers() {
+    let views = (0..texture.mip_level_count())
+      .map(|mip| {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+          label: Some("[wgsim] mipmap view"),
+          dimension: Some(wgpu::TextureViewDimension::D2),
+          base_mip_level: mip,
+          mip_level_count: Some(1),
+          base_array_layer: layer,
+          array_layer_count: Some(1),
+          ..Default::default()
+        })
+      })
+      .collect::<Vec<_>>();
+
+    for mip in 1..views.len() {
+      let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("[wgsim] mipmap bind group"),
+        layout: &bind_group_layout,
+        entries: &[
+          wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(&views[mip - 1]),
+          },
+          wgpu::BindGroupEntry {
+            binding: 1,
+            resource: wgpu::BindingResource::Sampler(&sampler),
+          },
+        ],
+      });
+
+      let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("[wgsim] mipmap pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view: &views[mip],
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      });
+
+      pass.set_pipeline(&pipeline);
+      pass.set_bind_group(0, &bind_group, &[]);
+      pass.draw(0..3, 0..1);
+    }
+  }
+
+  queue.submit(std::iter::once(encoder.finish()));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sixteen_bit_images_keep_precision() {
+    // 8 ビットでは区別できない 2 つの値
+    let image = image::DynamicImage::ImageRgba16(
+      image::ImageBuffer::from_raw(
+        2,
+        1,
+        vec![30000, 0, 0, 65535, 30100, 0, 0, 65535],
+      )
+      .unwrap(),
+    );
+
+    let pixels = Pixels::from_image(&image, ColorSpace::Linear);
+    assert_eq!(
+      pixels.format(ColorSpace::Linear),
+      wgpu::TextureFormat::Rgba16Float
+    );
+    let Pixels::Rgba16Float(data) = pixels else {
+      unreachable!()
+    };
+    assert_ne!(data[0], data[4]);
+    let red = half::f16::from_bits(data[0]).to_f32();
+    assert!((red - 30000.0 / 65535.0).abs() < 1e-3);
+  }
+
+  #[test]
+  fn sixteen_bit_srgb_images_are_linearized() {
+    let image = image::DynamicImage::ImageRgba16(
+      image::ImageBuffer::from_raw(1, 1, vec![32768, 32768, 32768, 32768])
+        .unwrap(),
+    );
+
+    let Pixels::Rgba16Float(data) =
+      Pixels::from_image(&image, ColorSpace::Srgb)
+    else {
+      unreachable!()
+    };
+    let [r, _, _, a] =
+      [0, 1, 2, 3].map(|i| half::f16::from_bits(data[i]).to_f32());
+    assert!((r - srgb_to_linear(0.5)).abs() < 1e-3);
+    // アルファは変換しない
+    assert!((a - 0.5).abs() < 1e-3);
+  }
+
+  #[test]
+  fn eight_bit_images_use_rgba8() {
+    let image = image::DynamicImage::new_rgb8(1, 1);
+    let pixels = Pixels::from_image(&image, ColorSpace::Srgb);
+    assert_eq!(
+      pixels.format(ColorSpace::Srgb),
+      wgpu::TextureFormat::Rgba8UnormSrgb
+    );
+    assert_eq!(pixels.bytes().len(), 4);
+  }
+
+  #[test]
+  fn data_size_must_match() {
+    let size = Size::new(4, 2);
+    let format = wgpu::TextureFormat::Rgba16Float;
+    assert!(check_data_size(size, format, 64).is_ok());
+    assert!(matches!(
+      check_data_size(size, format, 60),
+      Err(TextureError::DataSize {
+        expected: 64,
+        found: 60
+      })
+    ));
+  }
+}
@@ -0,0 +1,83 @@
+use crate::effect::Effect;
+use crate::layout::ShaderType;
+use crate::surface_cfg::OutputColorSpace;
+use crate::util::UniformBuffer;
+
+const TONEMAP_WGSL: &str = include_str!("./shader/tonemap.wgsl");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TonemapOperator {
+  // 1.0 を超えた値は切り捨てる
+  Clamp,
+  Reinhard,
+  #[default]
+  Aces,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TonemapUniforms {
+  exposure: f32,
+  operator: u32,
+  encode_srgb: u32,
+  hdr_output: u32,
+}
+
+impl ShaderType for TonemapUniforms {
+  const ALIGN: usize = 4;
+  const SIZE: usize = std::mem::size_of::<Self>();
+  const WGSL_TYPE: &'static str = "TonemapUniforms";
+}
+
+// HDR の中間結果を出力先に合わせて変換する
+// 出力が HDR (Rgba16Float など) の場合は露出だけを掛け、
+// SDR (Rgb10a2Unorm を含む) の場合は operator で 0..1 に収める
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tonemap {
+  pub operator: TonemapOperator,
+  pub exposure: f32,
+  pub output: OutputColorSpace,
+}
+
+impl Tonemap {
+  pub fn new(output_format: wgpu::TextureFormat) -> Self {
+    Self {
+      operator: TonemapOperator::default(),
+      exposure: 1.0,
+      output: OutputColorSpace::from_format(output_format),
+    }
+  }
+
+  pub fn operator(mut self, operator: TonemapOperator) -> Self {
+    self.operator = operator;
+    self
+  }
+
+  pub fn exposure(mut self, exposure: f32) -> Self {
+    self.exposure = exposure;
+    self
+  }
+
+  // uniform_buffer() で作ったバッファを渡し、EffectChain の最後に置く
+  // (hdr() と組み合わせる)
+  pub fn effect(uniform: &UniformBuffer<TonemapUniforms>) -> Effect<'_> {
+    Effect::new("tonemap", TONEMAP_WGSL).uniform(uniform)
+  }
+
+  pub fn uniform_buffer(
+    &self,
+    device: &wgpu::Device,
+  ) -> UniformBuffer<TonemapUniforms> {
+    UniformBuffer::new(device, self.uniforms())
+  }
+
+  // 設定を変えた場合は UniformBuffer::set に渡す
+  pub fn uniforms(&self) -> TonemapUniforms {
+    TonemapUniforms {
+      exposure: self.exposure,
+      operator: self.operator as u32,
+      encode_srgb: u32::from(self.output == OutputColorSpace::SrgbUnencoded),
+      hdr_output: u32::from(self.output.is_hdr()),
+    }
+  }
+}
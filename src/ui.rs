@@ -0,0 +1,126 @@
+use winit::event::WindowEvent;
+use winit::window::Window;
+
+use crate::ctx::DrawingContext;
+
+// egui の入力・フレーム・描画をまとめたもの (App::with_egui が使う)
+// 独自のイベントループに組み込む場合は
+// on_window_event → run → draw の順に呼ぶ
+pub struct EguiLayer {
+  context: egui::Context,
+  state: egui_winit::State,
+  renderer: egui_wgpu::Renderer,
+  paint_jobs: Vec<egui::ClippedPrimitive>,
+  textures: egui::TexturesDelta,
+  pixels_per_point: f32,
+}
+
+impl EguiLayer {
+  pub fn new(ctx: &DrawingContext, window: &Window) -> Self {
+    let context = egui::Context::default();
+    let state = egui_winit::State::new(
+      context.clone(),
+      egui::ViewportId::ROOT,
+      window,
+      Some(window.scale_factor() as f32),
+      window.theme(),
+      Some(ctx.device.limits().max_texture_dimension_2d as usize),
+    );
+    // サーフェスに直接描くので MSAA と深度バッファは使わない
+    let renderer =
+      egui_wgpu::Renderer::new(&ctx.device, ctx.format(), None, 1, false);
+
+    Self {
+      context,
+      state,
+      renderer,
+      paint_jobs: Vec::new(),
+      textures: egui::TexturesDelta::default(),
+      pixels_per_point: window.scale_factor() as f32,
+    }
+  }
+
+  pub fn context(&self) -> &egui::Context {
+    &self.context
+  }
+
+  // consumed が true の場合、イベントは egui が使ったので他に渡さない
+  pub fn on_window_event(
+    &mut self,
+    window: &Window,
+    event: &WindowEvent,
+  ) -> egui_winit::EventResponse {
+    self.state.on_window_event(window, event)
+  }
+
+  // UI を組み立ててテッセレートする
+  // すぐに描き直す必要がある (アニメーション中など) 場合は true を返す
+  pub fn run(
+    &mut self,
+    window: &Window,
+    ui: impl FnMut(&egui::Context),
+  ) -> bool {
+    let input = self.state.take_egui_input(window);
+    let output = self.context.run(input, ui);
+    self.state.handle_platform_output(window, output.platform_output);
+
+    self.paint_jobs =
+      self.context.tessellate(output.shapes, output.pixels_per_point);
+    self.textures.append(output.textures_delta);
+    self.pixels_per_point = output.pixels_per_point;
+
+    output
+      .viewport_output
+      .get(&egui::ViewportId::ROOT)
+      .is_some_and(|viewport| viewport.repaint_delay.is_zero())
+  }
+
+  // view の内容の上に UI を重ねて描く
+  pub fn draw(
+    &mut self,
+    ctx: &DrawingContext,
+    encoder: &mut wgpu::CommandEncoder,
+    view: &wgpu::TextureView,
+  ) {
+    let screen = egui_wgpu::ScreenDescriptor {
+      size_in_pixels: [ctx.size().width, ctx.size().height],
+      pixels_per_point: self.pixels_per_point,
+    };
+
+    for (id, delta) in &self.textures.set {
+      self.renderer.update_texture(&ctx.device, &ctx.queue, *id, delta);
+    }
+    // ペイントコールバックを使わないので、通常は空
+    let callback_commands = self.renderer.update_buffers(
+      &ctx.device,
+      &ctx.queue,
+      encoder,
+      &self.paint_jobs,
+      &screen,
+    );
+    if !callback_commands.is_empty() {
+      ctx.queue.submit(callback_commands);
+    }
+
+    let mut pass = encoder
+      .begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("[wgsim] egui pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+          view,
+          resolve_target: None,
+          ops: wgpu::Operations {
+            load: wgpu::LoadOp::Load,
+            store: wgpu::StoreOp::Store,
+          },
+        })],
+        ..Default::default()
+      })
+      .forget_lifetime();
+    self.renderer.render(&mut pass, &self.paint_jobs, &screen);
+    drop(pass);
+
+    for id in std::mem::take(&mut self.textures).free {
+      self.renderer.free_texture(&id);
+    }
+  }
+}
@@ -1,21 +1,24 @@
+use wgpu::util::DeviceExt;
+
+use crate::layout::{is_storage_layout, is_uniform_layout, ShaderType};
+
 pub fn create_bind_group_layout(
   device: &wgpu::Device,
   binding_types: &[wgpu::BindingType],
   shader_stages: &[wgpu::ShaderStages],
 ) -> wgpu::BindGroupLayout {
-  let entries = shader_stages.iter().enumerate().map(|(i, stage)| {
-    wgpu::BindGroupLayoutEntry {
-      binding: i as u32,
-      visibility: *stage,
-      ty: binding_types[i],
-      count: None,
-    }
-  });
+  assert_eq!(
+    binding_types.len(),
+    shader_stages.len(),
+    "[wgsim] binding_types and shader_stages must have the same length"
+  );
 
-  device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-    label: Some("[wgsim] bind group layout"),
-    entries: entries.collect::<Vec<_>>().as_slice(),
-  })
+  let builder = binding_types.iter().zip(shader_stages).enumerate().fold(
+    BindGroupLayoutBuilder::new(device),
+    |builder, (i, (ty, stage))| builder.entry(i as u32, *stage, *ty),
+  );
+
+  builder.build().layout
 }
 
 pub fn create_bind_group(
@@ -31,7 +34,807 @@ pub fn create_bind_group(
 
   device.create_bind_group(&wgpu::BindGroupDescriptor {
     label: Some("[wgsim] bind group"),
-    layout: &layout,
+    layout,
     entries: &entries.collect::<Vec<_>>(),
   })
 }
+
+// バインドグループを作るときに照合できるよう、エントリを保持しておく
+#[derive(Debug)]
+pub struct BindGroupLayout {
+  pub layout: wgpu::BindGroupLayout,
+  pub entries: Vec<wgpu::BindGroupLayoutEntry>,
+}
+
+impl std::ops::Deref for BindGroupLayout {
+  type Target = wgpu::BindGroupLayout;
+
+  fn deref(&self) -> &Self::Target {
+    &self.layout
+  }
+}
+
+impl BindGroupLayout {
+  pub fn entry(&self, binding: u32) -> Option<&wgpu::BindGroupLayoutEntry> {
+    self.entries.iter().find(|entry| entry.binding == binding)
+  }
+}
+
+pub struct BindGroupLayoutBuilder<'a> {
+  device: &'a wgpu::Device,
+  label: &'a str,
+  entries: Vec<wgpu::BindGroupLayoutEntry>,
+}
+
+impl<'a> BindGroupLayoutBuilder<'a> {
+  pub fn new(device: &'a wgpu::Device) -> Self {
+    Self {
+      device,
+      label: "[wgsim] bind group layout",
+      entries: Vec::new(),
+    }
+  }
+
+  pub fn label(mut self, label: &'a str) -> Self {
+    self.label = label;
+    self
+  }
+
+  pub fn entry(
+    mut self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    ty: wgpu::BindingType,
+  ) -> Self {
+    self.entries.push(wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility,
+      ty,
+      count: None,
+    });
+    self
+  }
+
+  pub fn array(
+    mut self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    ty: wgpu::BindingType,
+    count: u32,
+  ) -> Self {
+    self.entries.push(wgpu::BindGroupLayoutEntry {
+      binding,
+      visibility,
+      ty,
+      count: Some(
+        std::num::NonZeroU32::new(count)
+          .expect("[wgsim] binding array count must be non-zero"),
+      ),
+    });
+    self
+  }
+
+  pub fn buffer(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    ty: wgpu::BufferBindingType,
+    has_dynamic_offset: bool,
+    min_binding_size: Option<u64>,
+  ) -> Self {
+    self.entry(
+      binding,
+      visibility,
+      wgpu::BindingType::Buffer {
+        ty,
+        has_dynamic_offset,
+        min_binding_size: min_binding_size.and_then(wgpu::BufferSize::new),
+      },
+    )
+  }
+
+  pub fn uniform(self, binding: u32, visibility: wgpu::ShaderStages) -> Self {
+    self.buffer(
+      binding,
+      visibility,
+      wgpu::BufferBindingType::Uniform,
+      false,
+      None,
+    )
+  }
+
+  pub fn dynamic_uniform(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    element_size: u64,
+  ) -> Self {
+    self.buffer(
+      binding,
+      visibility,
+      wgpu::BufferBindingType::Uniform,
+      true,
+      Some(element_size),
+    )
+  }
+
+  pub fn storage(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    read_only: bool,
+  ) -> Self {
+    self.buffer(
+      binding,
+      visibility,
+      wgpu::BufferBindingType::Storage { read_only },
+      false,
+      None,
+    )
+  }
+
+  pub fn texture(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    sample_type: wgpu::TextureSampleType,
+    view_dimension: wgpu::TextureViewDimension,
+  ) -> Self {
+    self.entry(
+      binding,
+      visibility,
+      wgpu::BindingType::Texture {
+        sample_type,
+        view_dimension,
+        multisampled: false,
+      },
+    )
+  }
+
+  pub fn storage_texture(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    access: wgpu::StorageTextureAccess,
+    format: wgpu::TextureFormat,
+    view_dimension: wgpu::TextureViewDimension,
+  ) -> Self {
+    self.entry(
+      binding,
+      visibility,
+      wgpu::BindingType::StorageTexture {
+        access,
+        format,
+        view_dimension,
+      },
+    )
+  }
+
+  pub fn sampler(
+    self,
+    binding: u32,
+    visibility: wgpu::ShaderStages,
+    ty: wgpu::SamplerBindingType,
+  ) -> Self {
+    self.entry(binding, visibility, wgpu::BindingType::Sampler(ty))
+  }
+
+  pub fn build(&self) -> BindGroupLayout {
+    let mut entries = self.entries.clone();
+    entries.sort_by_key(|entry| entry.binding);
+
+    if let Some(pair) =
+      entries.windows(2).find(|w| w[0].binding == w[1].binding)
+    {
+      panic!("[wgsim] duplicate @binding({}) in layout", pair[0].binding);
+    }
+
+    let layout =
+      self.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some(self.label),
+        entries: &entries,
+      });
+
+    BindGroupLayout { layout, entries }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindGroupError {
+  MissingBinding(u32),
+  UnknownBinding(u32),
+  DuplicateBinding(u32),
+  ResourceMismatch {
+    binding: u32,
+    expected: &'static str,
+    found: &'static str,
+  },
+  ArrayLength {
+    binding: u32,
+    count: u32,
+    len: usize,
+  },
+  BufferTooSmall {
+    binding: u32,
+    min_size: u64,
+    size: u64,
+  },
+}
+
+impl std::fmt::Display for BindGroupError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::MissingBinding(binding) => {
+        write!(
+          f,
+          "@binding({}) is in the layout but has no resource",
+          binding
+        )
+      }
+      Self::UnknownBinding(binding) => {
+        write!(f, "@binding({}) is not in the layout", binding)
+      }
+      Self::DuplicateBinding(binding) => {
+        write!(f, "@binding({}) is bound more than once", binding)
+      }
+      Self::ResourceMismatch {
+        binding,
+        expected,
+        found,
+      } => write!(
+        f,
+        "@binding({}) expects {} but {} was given",
+        binding, expected, found
+      ),
+      Self::ArrayLength {
+        binding,
+        count,
+        len,
+      } => write!(
+        f,
+        "@binding({}) is an array of {} but {} resources were given",
+        binding, count, len
+      ),
+      Self::BufferTooSmall {
+        binding,
+        min_size,
+        size,
+      } => write!(
+        f,
+        "@binding({}) needs at least {} bytes but the buffer binds {}",
+        binding, min_size, size
+      ),
+    }
+  }
+}
+
+impl std::error::Error for BindGroupError {}
+
+pub struct BindGroupBuilder<'a> {
+  device: &'a wgpu::Device,
+  layout: &'a BindGroupLayout,
+  label: &'a str,
+  entries: Vec<wgpu::BindGroupEntry<'a>>,
+}
+
+impl<'a> BindGroupBuilder<'a> {
+  pub fn new(device: &'a wgpu::Device, layout: &'a BindGroupLayout) -> Self {
+    Self {
+      device,
+      layout,
+      label: "[wgsim] bind group",
+      entries: Vec::new(),
+    }
+  }
+
+  pub fn label(mut self, label: &'a str) -> Self {
+    self.label = label;
+    self
+  }
+
+  pub fn entry(
+    mut self,
+    binding: u32,
+    resource: wgpu::BindingResource<'a>,
+  ) -> Self {
+    self.entries.push(wgpu::BindGroupEntry { binding, resource });
+    self
+  }
+
+  pub fn buffer(self, binding: u32, buffer: &'a wgpu::Buffer) -> Self {
+    self.entry(binding, buffer.as_entire_binding())
+  }
+
+  pub fn buffer_range(
+    self,
+    binding: u32,
+    buffer: &'a wgpu::Buffer,
+    offset: wgpu::BufferAddress,
+    size: Option<u64>,
+  ) -> Self {
+    self.entry(
+      binding,
+      wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+        buffer,
+        offset,
+        size: size.and_then(wgpu::BufferSize::new),
+      }),
+    )
+  }
+
+  pub fn texture_view(self, binding: u32, view: &'a wgpu::TextureView) -> Self {
+    self.entry(binding, wgpu::BindingResource::TextureView(view))
+  }
+
+  pub fn texture_views(
+    self,
+    binding: u32,
+    views: &'a [&'a wgpu::TextureView],
+  ) -> Self {
+    self.entry(binding, wgpu::BindingResource::TextureViewArray(views))
+  }
+
+  pub fn sampler(self, binding: u32, sampler: &'a wgpu::Sampler) -> Self {
+    self.entry(binding, wgpu::BindingResource::Sampler(sampler))
+  }
+
+  pub fn samplers(
+    self,
+    binding: u32,
+    samplers: &'a [&'a wgpu::Sampler],
+  ) -> Self {
+    self.entry(binding, wgpu::BindingResource::SamplerArray(samplers))
+  }
+
+  pub fn validate(&self) -> Result<(), BindGroupError> {
+    let mut bindings =
+      self.entries.iter().map(|e| e.binding).collect::<Vec<_>>();
+    bindings.sort();
+    if let Some(pair) = bindings.windows(2).find(|w| w[0] == w[1]) {
+      return Err(BindGroupError::DuplicateBinding(pair[0]));
+    }
+
+    for entry in &self.entries {
+      let layout_entry = self
+        .layout
+        .entry(entry.binding)
+        .ok_or(BindGroupError::UnknownBinding(entry.binding))?;
+      if let Some(resource) = ResourceInfo::of(&entry.resource) {
+        check_resource(layout_entry, &resource)?;
+      }
+    }
+
+    for layout_entry in &self.layout.entries {
+      if !bindings.contains(&layout_entry.binding) {
+        return Err(BindGroupError::MissingBinding(layout_entry.binding));
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn try_build(&self) -> Result<wgpu::BindGroup, BindGroupError> {
+    self.validate()?;
+
+    Ok(self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+      label: Some(self.label),
+      layout: self.layout,
+      entries: &self.entries,
+    }))
+  }
+
+  pub fn build(&self) -> wgpu::BindGroup {
+    match self.try_build() {
+      Ok(bind_group) => bind_group,
+      Err(e) => panic!("[wgsim] bind group does not match its layout: {}", e),
+    }
+  }
+}
+
+// 検証に使うリソースの情報 (デバイスなしで検証できるよう分けている)
+struct ResourceInfo {
+  kind: &'static str,
+  // 配列としてバインドした場合の要素数
+  len: Option<usize>,
+  // バッファの場合、各要素のバインドされる範囲のバイト数
+  buffer_sizes: Vec<u64>,
+}
+
+impl ResourceInfo {
+  fn of(resource: &wgpu::BindingResource) -> Option<Self> {
+    let size = |binding: &wgpu::BufferBinding| match binding.size {
+      Some(size) => size.get(),
+      None => binding.buffer.size().saturating_sub(binding.offset),
+    };
+
+    let (kind, len, buffer_sizes) = match resource {
+      wgpu::BindingResource::Buffer(b) => ("buffer", None, vec![size(b)]),
+      wgpu::BindingResource::BufferArray(a) => {
+        ("buffer", Some(a.len()), a.iter().map(size).collect())
+      }
+      wgpu::BindingResource::Sampler(_) => ("sampler", None, vec![]),
+      wgpu::BindingResource::SamplerArray(a) => {
+        ("sampler", Some(a.len()), vec![])
+      }
+      wgpu::BindingResource::TextureView(_) => ("texture view", None, vec![]),
+      wgpu::BindingResource::TextureViewArray(a) => {
+        ("texture view", Some(a.len()), vec![])
+      }
+      _ => return None,
+    };
+
+    Some(Self {
+      kind,
+      len,
+      buffer_sizes,
+    })
+  }
+}
+
+fn check_resource(
+  layout_entry: &wgpu::BindGroupLayoutEntry,
+  resource: &ResourceInfo,
+) -> Result<(), BindGroupError> {
+  let binding = layout_entry.binding;
+
+  let expected = match layout_entry.ty {
+    wgpu::BindingType::Buffer { .. } => "buffer",
+    wgpu::BindingType::Sampler(_) => "sampler",
+    wgpu::BindingType::Texture { .. }
+    | wgpu::BindingType::StorageTexture { .. } => "texture view",
+    _ => return Ok(()),
+  };
+
+  if expected != resource.kind {
+    return Err(BindGroupError::ResourceMismatch {
+      binding,
+      expected,
+      found: resource.kind,
+    });
+  }
+
+  // PARTIALLY_BOUND_BINDING_ARRAY なしでは、配列の要素数は count と一致する必要がある
+  match (layout_entry.count, resource.len) {
+    (None, None) => {}
+    (Some(count), Some(len)) if len == count.get() as usize => {}
+    (Some(count), len) => {
+      return Err(BindGroupError::ArrayLength {
+        binding,
+        count: count.get(),
+        len: len.unwrap_or(1),
+      })
+    }
+    (None, Some(len)) => {
+      return Err(BindGroupError::ArrayLength {
+        binding,
+        count: 1,
+        len,
+      })
+    }
+  }
+
+  if let wgpu::BindingType::Buffer {
+    min_binding_size: Some(min_size),
+    ..
+  } = layout_entry.ty
+  {
+    if let Some(&size) =
+      resource.buffer_sizes.iter().find(|&&size| size < min_size.get())
+    {
+      return Err(BindGroupError::BufferTooSmall {
+        binding,
+        min_size: min_size.get(),
+        size,
+      });
+    }
+  }
+
+  Ok(())
+}
+
+// T のレイアウトは ShaderType で WGSL と突き合わせる
+// (#[derive(ShaderType)] ならフィールドのオフセットまで検査される)
+pub struct UniformBuffer<T: ShaderType> {
+  buffer: wgpu::Buffer,
+  value: T,
+  dirty: bool,
+}
+
+impl<T: ShaderType> UniformBuffer<T> {
+  const LAYOUT_CHECK: () = assert!(
+    is_uniform_layout::<T>(),
+    "[wgsim] uniform type does not match the WGSL uniform layout"
+  );
+
+  pub fn new(device: &wgpu::Device, value: T) -> Self {
+    let () = Self::LAYOUT_CHECK;
+
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("[wgsim] uniform buffer"),
+      contents: bytemuck::bytes_of(&value),
+      usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    Self {
+      buffer,
+      value,
+      dirty: false,
+    }
+  }
+
+  pub fn binding_type() -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Uniform,
+      has_dynamic_offset: false,
+      min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+    }
+  }
+
+  pub fn get(&self) -> &T {
+    &self.value
+  }
+
+  pub fn set(&mut self, value: T) {
+    if bytemuck::bytes_of(&self.value) != bytemuck::bytes_of(&value) {
+      self.value = value;
+      self.dirty = true;
+    }
+  }
+
+  pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+    let mut value = self.value;
+    f(&mut value);
+    self.set(value);
+  }
+
+  pub fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+
+  // 変更があった場合のみ書き込む (1フレームに1回呼ぶ想定)
+  pub fn flush(&mut self, queue: &wgpu::Queue) -> bool {
+    if !self.dirty {
+      return false;
+    }
+
+    queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.value));
+    self.dirty = false;
+    true
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    &self.buffer
+  }
+
+  pub fn as_binding_resource(&self) -> wgpu::BindingResource<'_> {
+    self.buffer.as_entire_binding()
+  }
+}
+
+// 要素は WGSL の配列と同じ間隔 (SIZE を ALIGN に切り上げた値) で並ぶ必要がある
+pub struct StorageBuffer<T: ShaderType> {
+  buffer: wgpu::Buffer,
+  data: Vec<T>,
+  dirty: bool,
+}
+
+impl<T: ShaderType> StorageBuffer<T> {
+  const LAYOUT_CHECK: () = assert!(
+    is_storage_layout::<T>(),
+    "[wgsim] storage element size differs from its WGSL array stride"
+  );
+
+  pub fn new(
+    device: &wgpu::Device,
+    data: &[T],
+    usage: wgpu::BufferUsages,
+  ) -> Self {
+    let () = Self::LAYOUT_CHECK;
+    assert!(!data.is_empty(), "[wgsim] storage buffer must not be empty");
+
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("[wgsim] storage buffer"),
+      contents: bytemuck::cast_slice(data),
+      usage: usage | wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+    });
+
+    Self {
+      buffer,
+      data: data.to_vec(),
+      dirty: false,
+    }
+  }
+
+  pub fn binding_type(read_only: bool) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Storage { read_only },
+      has_dynamic_offset: false,
+      min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<T>() as u64),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn get(&self) -> &[T] {
+    &self.data
+  }
+
+  // バインドグループを作り直さずに済むよう、長さは変えられない
+  pub fn set(&mut self, data: &[T]) {
+    assert_eq!(
+      data.len(),
+      self.data.len(),
+      "[wgsim] storage buffer length cannot be changed"
+    );
+
+    if bytemuck::cast_slice::<T, u8>(&self.data)
+      != bytemuck::cast_slice::<T, u8>(data)
+    {
+      self.data.copy_from_slice(data);
+      self.dirty = true;
+    }
+  }
+
+  pub fn update(&mut self, f: impl FnOnce(&mut [T])) {
+    f(&mut self.data);
+    self.dirty = true;
+  }
+
+  pub fn is_dirty(&self) -> bool {
+    self.dirty
+  }
+
+  pub fn flush(&mut self, queue: &wgpu::Queue) -> bool {
+    if !self.dirty {
+      return false;
+    }
+
+    queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.data));
+    self.dirty = false;
+    true
+  }
+
+  pub fn buffer(&self) -> &wgpu::Buffer {
+    &self.buffer
+  }
+
+  pub fn as_binding_resource(&self) -> wgpu::BindingResource<'_> {
+    self.buffer.as_entire_binding()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn layout_entry(
+    ty: wgpu::BindingType,
+    count: Option<u32>,
+  ) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+      binding: 3,
+      visibility: wgpu::ShaderStages::COMPUTE,
+      ty,
+      count: count.and_then(std::num::NonZeroU32::new),
+    }
+  }
+
+  fn storage(min_binding_size: Option<u64>) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+      ty: wgpu::BufferBindingType::Storage { read_only: true },
+      has_dynamic_offset: false,
+      min_binding_size: min_binding_size.and_then(wgpu::BufferSize::new),
+    }
+  }
+
+  fn texture() -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+      sample_type: wgpu::TextureSampleType::Float { filterable: true },
+      view_dimension: wgpu::TextureViewDimension::D2,
+      multisampled: false,
+    }
+  }
+
+  fn buffer(size: u64) -> ResourceInfo {
+    ResourceInfo {
+      kind: "buffer",
+      len: None,
+      buffer_sizes: vec![size],
+    }
+  }
+
+  fn views(len: Option<usize>) -> ResourceInfo {
+    ResourceInfo {
+      kind: "texture view",
+      len,
+      buffer_sizes: vec![],
+    }
+  }
+
+  #[test]
+  fn accepts_matching_resource() {
+    assert_eq!(
+      check_resource(&layout_entry(storage(None), None), &buffer(4)),
+      Ok(())
+    );
+    assert_eq!(
+      check_resource(&layout_entry(texture(), None), &views(None)),
+      Ok(())
+    );
+  }
+
+  #[test]
+  fn rejects_wrong_resource_kind() {
+    let error =
+      check_resource(&layout_entry(texture(), None), &buffer(16)).unwrap_err();
+    assert_eq!(
+      error,
+      BindGroupError::ResourceMismatch {
+        binding: 3,
+        expected: "texture view",
+        found: "buffer",
+      }
+    );
+  }
+
+  #[test]
+  fn binding_array_length_must_equal_count() {
+    let entry = layout_entry(texture(), Some(4));
+    assert_eq!(check_resource(&entry, &views(Some(4))), Ok(()));
+
+    for len in [Some(3), Some(5), None] {
+      let error = check_resource(&entry, &views(len)).unwrap_err();
+      assert_eq!(
+        error,
+        BindGroupError::ArrayLength {
+          binding: 3,
+          count: 4,
+          len: len.unwrap_or(1),
+        }
+      );
+    }
+
+    let error = check_resource(&layout_entry(texture(), None), &views(Some(2)))
+      .unwrap_err();
+    assert!(matches!(
+      error,
+      BindGroupError::ArrayLength { count: 1, .. }
+    ));
+  }
+
+  #[test]
+  fn buffer_must_cover_min_binding_size() {
+    let entry = layout_entry(storage(Some(64)), None);
+    assert_eq!(check_resource(&entry, &buffer(64)), Ok(()));
+    assert_eq!(
+      check_resource(&entry, &buffer(48)),
+      Err(BindGroupError::BufferTooSmall {
+        binding: 3,
+        min_size: 64,
+        size: 48,
+      })
+    );
+  }
+
+  #[test]
+  fn checks_buffer_layouts() {
+    use crate::camera::CameraUniform;
+    use crate::primitive::{Mat3, Size};
+
+    assert!(is_uniform_layout::<[f32; 3]>());
+    assert!(is_uniform_layout::<Mat3>());
+    assert!(is_uniform_layout::<Size<f32>>());
+    assert!(is_uniform_layout::<CameraUniform>());
+
+    // array<vec3f> の要素は 16 バイト間隔
+    assert!(!is_storage_layout::<[f32; 3]>());
+    assert!(is_storage_layout::<[f32; 4]>());
+    assert!(is_storage_layout::<f32>());
+  }
+}
@@ -0,0 +1,636 @@
+use wgpu::util::DeviceExt;
+
+use crate::ctx::DrawingContext;
+use crate::readback::ReadbackError;
+
+// どれか1つを満たせば一致とみなす
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tolerance {
+  pub abs: f64,
+  // 期待値と実際の値の大きい方に対する割合
+  pub rel: f64,
+  // 浮動小数点数のみ
+  pub ulps: u64,
+}
+
+impl Tolerance {
+  pub fn exact() -> Self {
+    Self::default()
+  }
+
+  pub fn abs(abs: f64) -> Self {
+    Self::exact().or_abs(abs)
+  }
+
+  pub fn rel(rel: f64) -> Self {
+    Self::exact().or_rel(rel)
+  }
+
+  pub fn ulps(ulps: u64) -> Self {
+    Self::exact().or_ulps(ulps)
+  }
+
+  pub fn or_abs(mut self, abs: f64) -> Self {
+    self.abs = abs;
+    self
+  }
+
+  pub fn or_rel(mut self, rel: f64) -> Self {
+    self.rel = rel;
+    self
+  }
+
+  pub fn or_ulps(mut self, ulps: u64) -> Self {
+    self.ulps = ulps;
+    self
+  }
+}
+
+impl std::fmt::Display for Tolerance {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "abs {:e} / rel {:e} / {} ULP",
+      self.abs, self.rel, self.ulps
+    )
+  }
+}
+
+// GPU の結果と比べられる要素の型
+pub trait Element: bytemuck::Pod + std::fmt::Debug {
+  // 許容範囲内なら None、外れていれば差の説明を返す
+  fn compare(
+    expected: &Self,
+    actual: &Self,
+    tolerance: &Tolerance,
+  ) -> Option<String>;
+}
+
+macro_rules! impl_float_element {
+  ($t:ty, $signed:ty) => {
+    impl Element for $t {
+      fn compare(
+        expected: &Self,
+        actual: &Self,
+        tolerance: &Tolerance,
+      ) -> Option<String> {
+        if expected.to_bits() == actual.to_bits()
+          || (expected.is_nan() && actual.is_nan())
+        {
+          return None;
+        }
+        // NaN と無限大は同じ値としか一致しない
+        // (rel * inf や ULP の差では判定できない)
+        if !expected.is_finite() || !actual.is_finite() {
+          return Some(format!("expected {:?}, got {:?}", expected, actual));
+        }
+
+        let diff = (*expected as f64 - *actual as f64).abs();
+        let scale = (*expected as f64).abs().max((*actual as f64).abs());
+        // 符号付き整数に並べ直すと、隣り合う浮動小数点数の差が 1 になる
+        let ordered = |v: $t| {
+          let bits = v.to_bits() as $signed;
+          if bits < 0 {
+            <$signed>::MIN.wrapping_sub(bits) as i128
+          } else {
+            bits as i128
+          }
+        };
+        let ulps = (ordered(*expected) - ordered(*actual)).unsigned_abs();
+
+        let within = diff <= tolerance.abs
+          || diff <= tolerance.rel * scale
+          || ulps <= tolerance.ulps as u128;
+        (!within).then(|| format!("diff {:e}, {} ULP", diff, ulps))
+      }
+    }
+  };
+}
+
+impl_float_element!(f32, i32);
+impl_float_element!(f64, i64);
+
+macro_rules! impl_int_element {
+  ($($t:ty),*) => {
+    $(
+      impl Element for $t {
+        fn compare(
+          expected: &Self,
+          actual: &Self,
+          tolerance: &Tolerance,
+        ) -> Option<String> {
+          let diff = (*expected as i128 - *actual as i128).unsigned_abs();
+          (diff as f64 > tolerance.abs).then(|| format!("diff {}", diff))
+        }
+      }
+    )*
+  };
+}
+
+impl_int_element!(u8, u16, u32, i32, u64, i64);
+
+// vec4f などは [f32; 4] として比べる
+impl<T: Element, const N: usize> Element for [T; N]
+where
+  [T; N]: bytemuck::Pod,
+{
+  fn compare(
+    expected: &Self,
+    actual: &Self,
+    tolerance: &Tolerance,
+  ) -> Option<String> {
+    expected.iter().zip(actual).enumerate().find_map(|(i, (e, a))| {
+      T::compare(e, a, tolerance).map(|d| format!("[{}] {}", i, d))
+    })
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MismatchRow {
+  pub index: usize,
+  pub expected: String,
+  pub actual: String,
+  // 許容範囲内の行 (前後の文脈) は None
+  pub diff: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifyError {
+  LengthMismatch {
+    label: String,
+    step: Option<usize>,
+    expected: usize,
+    actual: usize,
+  },
+  Mismatch {
+    label: String,
+    step: Option<usize>,
+    tolerance: Tolerance,
+    len: usize,
+    mismatched: usize,
+    first_index: usize,
+    // 最初のいくつかの不一致と、その前後の要素
+    rows: Vec<MismatchRow>,
+  },
+  Readback(ReadbackError),
+}
+
+impl std::fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let at = |label: &str, step: &Option<usize>| match step {
+      Some(step) => format!("'{}' after step {}", label, step),
+      None => format!("'{}'", label),
+    };
+
+    match self {
+      Self::LengthMismatch {
+        label,
+        step,
+        expected,
+        actual,
+      } => write!(
+        f,
+        "{}: expected {} elements but the GPU returned {}",
+        at(label, step),
+        expected,
+        actual
+      ),
+      Self::Mismatch {
+        label,
+        step,
+        tolerance,
+        len,
+        mismatched,
+        first_index,
+        rows,
+      } => {
+        writeln!(
+          f,
+          "{}: {} of {} elements differ, first at [{}] (tolerance: {})",
+          at(label, step),
+          mismatched,
+          len,
+          first_index,
+          tolerance
+        )?;
+        let mut previous = None;
+        for row in rows {
+          if previous.is_some_and(|p: usize| row.index > p + 1) {
+            writeln!(f, "  ...")?;
+          }
+          previous = Some(row.index);
+
+          let marker = if row.diff.is_some() { ">" } else { " " };
+          write!(
+            f,
+            "{} [{:>6}] expected {} actual {}",
+            marker, row.index, row.expected, row.actual
+          )?;
+          match &row.diff {
+            Some(diff) => writeln!(f, " ({})", diff)?,
+            None => writeln!(f)?,
+          }
+        }
+        Ok(())
+      }
+      Self::Readback(e) => write!(f, "{}", e),
+    }
+  }
+}
+
+impl std::error::Error for VerifyError {}
+
+impl From<ReadbackError> for VerifyError {
+  fn from(e: ReadbackError) -> Self {
+    Self::Readback(e)
+  }
+}
+
+// コンピュートシェーダの結果を CPU で計算した値と比べる
+pub struct KernelVerifier<'c> {
+  ctx: &'c DrawingContext<'c>,
+  label: String,
+  tolerance: Tolerance,
+  context: usize,
+  max_reported: usize,
+}
+
+impl<'c> KernelVerifier<'c> {
+  pub fn new(ctx: &'c DrawingContext<'c>, label: &str) -> Self {
+    Self {
+      ctx,
+      label: label.to_string(),
+      tolerance: Tolerance::exact(),
+      context: 2,
+      max_reported: 8,
+    }
+  }
+
+  pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+    self.tolerance = tolerance;
+    self
+  }
+
+  // 不一致の前後に表示する要素の数
+  pub fn context(mut self, context: usize) -> Self {
+    self.context = context;
+    self
+  }
+
+  // 表示する不一致の数
+  pub fn max_reported(mut self, max_reported: usize) -> Self {
+    self.max_reported = max_reported.max(1);
+    self
+  }
+
+  // 入出力に使うストレージバッファ (読み戻せるように COPY_SRC を付ける)
+  pub fn storage_buffer<T: bytemuck::Pod>(&self, data: &[T]) -> wgpu::Buffer {
+    self.ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+      label: Some("[wgsim] verify storage buffer"),
+      contents: bytemuck::cast_slice(data),
+      usage: wgpu::BufferUsages::STORAGE
+        | wgpu::BufferUsages::COPY_SRC
+        | wgpu::BufferUsages::COPY_DST,
+    })
+  }
+
+  pub fn submit(&self, f: impl FnOnce(&mut wgpu::CommandEncoder)) {
+    let mut encoder =
+      self.ctx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("[wgsim] verify encoder"),
+      });
+    f(&mut encoder);
+    self.ctx.queue.submit(std::iter::once(encoder.finish()));
+  }
+
+  pub fn check<T: Element>(
+    &self,
+    buffer: &wgpu::Buffer,
+    expected: &[T],
+  ) -> Result<(), VerifyError> {
+    self.check_at(None, buffer, expected)
+  }
+
+  // エラーの表示に step を含める
+  pub fn check_step<T: Element>(
+    &self,
+    step: usize,
+    buffer: &wgpu::Buffer,
+    expected: &[T],
+  ) -> Result<(), VerifyError> {
+    self.check_at(Some(step), buffer, expected)
+  }
+
+  // steps 回進め、check_every ステップごとに (と最後に) 比べる
+  // gpu_step はコマンドを積み、cpu_step は同じステップを CPU で進めて比べる値を返す
+  pub fn run_steps<S, T: Element>(
+    &self,
+    steps: usize,
+    check_every: usize,
+    gpu: &mut S,
+    mut gpu_step: impl FnMut(&mut S, &mut wgpu::CommandEncoder, usize),
+    gpu_output: impl for<'s> Fn(&'s S) -> &'s wgpu::Buffer,
+    mut cpu_step: impl FnMut(usize) -> Vec<T>,
+  ) -> Result<(), VerifyError> {
+    let check_every = check_every.max(1);
+
+    for step in 0..steps {
+      self.submit(|encoder| gpu_step(gpu, encoder, step));
+      let expected = cpu_step(step);
+
+      if (step + 1) % check_every == 0 || step + 1 == steps {
+        self.check_step(step, gpu_output(gpu), &expected)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn check_at<T: Element>(
+    &self,
+    step: Option<usize>,
+    buffer: &wgpu::Buffer,
+    expected: &[T],
+  ) -> Result<(), VerifyError> {
+    let size = std::mem::size_of_val(expected) as wgpu::BufferAddress;
+    if buffer.size() < size {
+      return Err(VerifyError::LengthMismatch {
+        label: self.label.clone(),
+        step,
+        expected: expected.len(),
+        actual: (buffer.size() as usize) / std::mem::size_of::<T>().max(1),
+      });
+    }
+    let actual: Vec<T> = self.ctx.read_buffer(buffer, ..size)?;
+
+    let Some((mismatched, first_index, rows)) = mismatch_rows(
+      expected,
+      &actual,
+      &self.tolerance,
+      self.context,
+      self.max_reported,
+    ) else {
+      return Ok(());
+    };
+
+    Err(VerifyError::Mismatch {
+      label: self.label.clone(),
+      step,
+      tolerance: self.tolerance,
+      len: expected.len(),
+      mismatched,
+      first_index,
+      rows,
+    })
+  }
+}
+
+// 一致していれば None、そうでなければ (不一致の数, 最初の位置, 表示する行)
+fn mismatch_rows<T: Element>(
+  expected: &[T],
+  actual: &[T],
+  tolerance: &Tolerance,
+  context: usize,
+  max_reported: usize,
+) -> Option<(usize, usize, Vec<MismatchRow>)> {
+  let diffs: Vec<(usize, String)> = expected
+    .iter()
+    .zip(actual)
+    .enumerate()
+    .filter_map(|(i, (e, a))| T::compare(e, a, tolerance).map(|d| (i, d)))
+    .collect();
+  let &(first_index, _) = diffs.first()?;
+
+  // 報告する不一致とその前後の要素を重複なく並べる
+  let reported = &diffs[..diffs.len().min(max_reported)];
+  let mut indices: Vec<usize> = reported
+    .iter()
+    .flat_map(|(i, _)| {
+      i.saturating_sub(context)..(i + context + 1).min(expected.len())
+    })
+    .collect();
+  indices.sort_unstable();
+  indices.dedup();
+
+  let rows = indices
+    .into_iter()
+    .map(|index| MismatchRow {
+      index,
+      expected: format!("{:?}", expected[index]),
+      actual: format!("{:?}", actual[index]),
+      diff: reported.iter().find(|(i, _)| *i == index).map(|(_, d)| d.clone()),
+    })
+    .collect();
+
+  Some((diffs.len(), first_index, rows))
+}
+
+// assert! の代わりに使うと、不一致の一覧を表示して panic する
+pub fn assert_verified(result: Result<(), VerifyError>) {
+  if let Err(e) = result {
+    panic!("[wgsim] {}", e);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn within<T: Element>(expected: T, actual: T, tolerance: Tolerance) -> bool {
+    T::compare(&expected, &actual, &tolerance).is_none()
+  }
+
+  #[test]
+  fn float_ulps() {
+    let one = 1.0f32;
+    let next = f32::from_bits(one.to_bits() + 1);
+    assert!(within(one, one, Tolerance::exact()));
+    assert!(!within(one, next, Tolerance::exact()));
+    assert!(within(one, next, Tolerance::ulps(1)));
+    assert_eq!(
+      f32::compare(&one, &next, &Tolerance::exact()).unwrap(),
+      format!("diff {:e}, 1 ULP", f32::EPSILON as f64)
+    );
+
+    let two_after = f64::from_bits(1.0f64.to_bits() + 2);
+    assert!(!within(1.0, two_after, Tolerance::ulps(1)));
+    assert!(within(1.0, two_after, Tolerance::ulps(2)));
+  }
+
+  #[test]
+  fn float_signed_zero_and_sign_crossing() {
+    assert!(within(0.0f32, -0.0, Tolerance::exact()));
+    assert!(within(-0.0f64, 0.0, Tolerance::exact()));
+
+    // 最小の非正規化数は 0 をはさんで 2 ULP 離れている
+    let tiny = f32::from_bits(1);
+    assert_eq!(
+      f32::compare(&-tiny, &tiny, &Tolerance::exact()).unwrap(),
+      format!("diff {:e}, 2 ULP", 2.0 * tiny as f64)
+    );
+    assert!(!within(-tiny, tiny, Tolerance::ulps(1)));
+    assert!(within(-tiny, tiny, Tolerance::ulps(2)));
+    assert!(within(-0.0, tiny, Tolerance::ulps(1)));
+
+    // 負の数どうしも隣り合えば 1 ULP
+    let minus_one = -1.0f32;
+    let next = f32::from_bits(minus_one.to_bits() + 1);
+    assert!(within(minus_one, next, Tolerance::ulps(1)));
+    assert!(!within(-1.0f32, 1.0, Tolerance::ulps(1 << 20)));
+  }
+
+  #[test]
+  fn float_nan_and_infinity() {
+    assert!(within(f32::NAN, f32::NAN, Tolerance::exact()));
+    assert!(within(f32::NAN, -f32::NAN, Tolerance::exact()));
+    assert!(!within(f32::NAN, 0.0, Tolerance::abs(f64::INFINITY)));
+    assert!(!within(
+      1.0,
+      f64::NAN,
+      Tolerance::rel(1.0).or_ulps(u64::MAX)
+    ));
+    assert_eq!(
+      f32::compare(&1.0, &f32::NAN, &Tolerance::exact()).unwrap(),
+      "expected 1.0, got NaN"
+    );
+    assert!(within(f32::INFINITY, f32::INFINITY, Tolerance::exact()));
+    assert!(!within(f32::INFINITY, f32::MAX, Tolerance::rel(0.5)));
+    assert!(!within(f64::MAX, f64::INFINITY, Tolerance::ulps(1)));
+    assert!(!within(
+      f32::INFINITY,
+      f32::NEG_INFINITY,
+      Tolerance::exact()
+    ));
+  }
+
+  #[test]
+  fn float_abs_and_rel() {
+    assert!(within(1.0f32, 1.001, Tolerance::abs(1e-2)));
+    assert!(!within(1.0f32, 1.1, Tolerance::abs(1e-2)));
+
+    // rel は大きい方の絶対値に対する割合
+    assert!(within(100.0f64, 101.0, Tolerance::rel(0.01)));
+    assert!(!within(100.0f64, 102.0, Tolerance::rel(0.01)));
+    assert!(within(-100.0f64, -101.0, Tolerance::rel(0.01)));
+    assert!(!within(0.0f64, 1e-30, Tolerance::rel(0.5)));
+
+    // どれか1つを満たせばよい
+    let tolerance = Tolerance::abs(1e-3).or_rel(1e-2);
+    assert!(within(1000.0f32, 1005.0, tolerance));
+    assert!(within(0.0f32, 5e-4, tolerance));
+    assert!(!within(1.0f32, 1.5, tolerance));
+  }
+
+  #[test]
+  fn integer_tolerance() {
+    assert!(within(5u32, 5, Tolerance::exact()));
+    assert!(!within(5u32, 6, Tolerance::exact()));
+    assert!(within(5u32, 7, Tolerance::abs(2.0)));
+    assert!(within(7u32, 5, Tolerance::abs(2.0)));
+    assert!(!within(5u32, 8, Tolerance::abs(2.0)));
+    assert!(within(-1i32, 1, Tolerance::abs(2.0)));
+    assert!(within(0u64, u64::MAX, Tolerance::abs(f64::INFINITY)));
+    // 整数には rel と ulps は効かない
+    assert!(!within(100u8, 101, Tolerance::rel(0.5).or_ulps(10)));
+    assert_eq!(
+      i64::compare(&i64::MIN, &i64::MAX, &Tolerance::exact()).unwrap(),
+      format!("diff {}", u64::MAX)
+    );
+  }
+
+  #[test]
+  fn arrays_report_the_first_component() {
+    let expected = [1.0f32, 2.0, 3.0, 4.0];
+    let mut actual = expected;
+    actual[2] = 3.5;
+    actual[3] = 5.0;
+    assert_eq!(
+      <[f32; 4]>::compare(&expected, &actual, &Tolerance::exact()).unwrap(),
+      "[2] diff 5e-1, 2097152 ULP"
+    );
+    assert!(within(expected, actual, Tolerance::abs(1.0)));
+  }
+
+  #[test]
+  fn rows_include_context_without_duplicates() {
+    let expected: Vec<u32> = (0..20).collect();
+    let mut actual = expected.clone();
+    for i in [3, 5, 15, 18] {
+      actual[i] += 10;
+    }
+
+    let (mismatched, first_index, rows) =
+      mismatch_rows(&expected, &actual, &Tolerance::exact(), 1, 3).unwrap();
+    assert_eq!(mismatched, 4);
+    assert_eq!(first_index, 3);
+    // 4 つ目の不一致 (18) は max_reported を超えるので表示しない
+    let indices: Vec<usize> = rows.iter().map(|r| r.index).collect();
+    assert_eq!(indices, [2, 3, 4, 5, 6, 14, 15, 16]);
+    let marked: Vec<usize> =
+      rows.iter().filter(|r| r.diff.is_some()).map(|r| r.index).collect();
+    assert_eq!(marked, [3, 5, 15]);
+    assert_eq!(rows[1].expected, "3");
+    assert_eq!(rows[1].actual, "13");
+    assert_eq!(rows[1].diff.as_deref(), Some("diff 10"));
+
+    assert!(
+      mismatch_rows(&expected, &expected, &Tolerance::exact(), 1, 3).is_none()
+    );
+  }
+
+  #[test]
+  fn rows_are_clamped_to_the_ends() {
+    let expected = [0.0f32, 1.0, 2.0];
+    let actual = [9.0f32, 1.0, 9.0];
+    let (_, _, rows) =
+      mismatch_rows(&expected, &actual, &Tolerance::exact(), 5, 8).unwrap();
+    let indices: Vec<usize> = rows.iter().map(|r| r.index).collect();
+    assert_eq!(indices, [0, 1, 2]);
+  }
+
+  #[test]
+  fn mismatch_report_format() {
+    let expected: Vec<u32> = (0..20).collect();
+    let mut actual = expected.clone();
+    actual[3] = 30;
+    actual[15] = 150;
+    let (mismatched, first_index, rows) =
+      mismatch_rows(&expected, &actual, &Tolerance::abs(1.0), 1, 8).unwrap();
+
+    let error = VerifyError::Mismatch {
+      label: "life".to_string(),
+      step: Some(4),
+      tolerance: Tolerance::abs(1.0),
+      len: expected.len(),
+      mismatched,
+      first_index,
+      rows,
+    };
+    assert_eq!(
+      error.to_string(),
+      "'life' after step 4: 2 of 20 elements differ, first at [3] \
+       (tolerance: abs 1e0 / rel 0e0 / 0 ULP)\n\
+       \u{20} [     2] expected 2 actual 2\n\
+       > [     3] expected 3 actual 30 (diff 27)\n\
+       \u{20} [     4] expected 4 actual 4\n\
+       \u{20} ...\n\
+       \u{20} [    14] expected 14 actual 14\n\
+       > [    15] expected 15 actual 150 (diff 135)\n\
+       \u{20} [    16] expected 16 actual 16\n"
+    );
+  }
+
+  #[test]
+  fn length_mismatch_format() {
+    let error = VerifyError::LengthMismatch {
+      label: "particles".to_string(),
+      step: None,
+      expected: 8,
+      actual: 4,
+    };
+    assert_eq!(
+      error.to_string(),
+      "'particles': expected 8 elements but the GPU returned 4"
+    );
+  }
+}
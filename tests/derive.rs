@@ -0,0 +1,198 @@
+#![cfg(feature = "derive")]
+
+use wgsim::layout::{
+  is_storage_layout, is_uniform_layout, ShaderType, VertexLayout, WgslStruct,
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Light {
+  position: [f32; 3],
+  intensity: f32,
+  color: [f32; 3],
+  _pad: [u32; 1],
+}
+
+// vec2f の後に 8 バイトのパディングを挟んで vec4f に揃える
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Params {
+  resolution: [f32; 2],
+  _pad: [u32; 2],
+  tint: [f32; 4],
+  time: f32,
+  _tail: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Inner {
+  offset: [f32; 2],
+}
+
+// storage では正しいが、uniform では Inner を 16 の倍数に置く必要がある
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct StorageOnly {
+  scale: f32,
+  _pad: u32,
+  inner: Inner,
+}
+
+// uniform でも有効な並べ方 (Inner の後に 16 バイト空ける)
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct UniformInner {
+  inner: Inner,
+  _pad: [u32; 2],
+  scale: f32,
+  _tail: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::Vertex)]
+#[vertex(instance, location = 2)]
+struct Instance {
+  offset: [f32; 2],
+  #[vertex(format = "Unorm8x4")]
+  color: [u8; 4],
+  #[vertex(skip)]
+  id: u32,
+  scale: f32,
+}
+
+// naga で WGSL として検証し、各メンバーのオフセットを返す
+fn validate(
+  declarations: &str,
+  ty: &str,
+  space: &str,
+) -> Result<Vec<(String, u32)>, String> {
+  let source = format!(
+    "{}\n@group(0) @binding(0) var<{}> value: {};\n",
+    declarations, space, ty
+  );
+  let module = naga::front::wgsl::parse_str(&source)
+    .map_err(|e| e.emit_to_string(&source))?;
+  naga::valid::Validator::new(
+    naga::valid::ValidationFlags::all(),
+    naga::valid::Capabilities::empty(),
+  )
+  .validate(&module)
+  .map_err(|e| format!("{:?}", e.into_inner()))?;
+
+  let (_, ty) =
+    module.types.iter().find(|(_, t)| t.name.as_deref() == Some(ty)).unwrap();
+  let naga::TypeInner::Struct { members, .. } = &ty.inner else {
+    unreachable!()
+  };
+  Ok(members.iter().map(|m| (m.name.clone().unwrap(), m.offset)).collect())
+}
+
+#[test]
+fn wgsl_struct_matches_rust_layout_in_uniform() {
+  let members = validate(&Params::wgsl_struct(), "Params", "uniform").unwrap();
+  assert_eq!(
+    members,
+    [("resolution", 0), ("tint", 16), ("time", 32)]
+      .map(|(name, offset)| (name.to_string(), offset))
+  );
+  assert_eq!(std::mem::offset_of!(Params, tint), 16);
+  assert_eq!(std::mem::offset_of!(Params, time), 32);
+
+  let members = validate(&Light::wgsl_struct(), "Light", "uniform").unwrap();
+  assert_eq!(members[1], ("intensity".to_string(), 12));
+  assert_eq!(members[2], ("color".to_string(), 16));
+  assert_eq!(Light::SIZE, 32);
+  assert_eq!(
+    Light::wgsl_struct(),
+    "struct Light {\n  position: vec3f,\n  intensity: f32,\n  \
+     @size(16) color: vec3f,\n}\n"
+  );
+}
+
+#[test]
+fn uniform_compatibility_matches_naga() {
+  let cases = [
+    (
+      format!("{}{}", Inner::wgsl_struct(), StorageOnly::wgsl_struct()),
+      "StorageOnly",
+      StorageOnly::UNIFORM_COMPATIBLE,
+    ),
+    (
+      format!("{}{}", Inner::wgsl_struct(), UniformInner::wgsl_struct()),
+      "UniformInner",
+      UniformInner::UNIFORM_COMPATIBLE,
+    ),
+    (Params::wgsl_struct(), "Params", Params::UNIFORM_COMPATIBLE),
+    (Light::wgsl_struct(), "Light", Light::UNIFORM_COMPATIBLE),
+  ];
+
+  for (declarations, ty, compatible) in cases {
+    let storage = validate(&declarations, ty, "storage");
+    assert!(storage.is_ok(), "{}: {:?}", ty, storage);
+
+    let uniform = validate(&declarations, ty, "uniform");
+    assert_eq!(uniform.is_ok(), compatible, "{}: {:?}", ty, uniform);
+  }
+  assert_eq!(
+    [
+      StorageOnly::UNIFORM_COMPATIBLE,
+      UniformInner::UNIFORM_COMPATIBLE
+    ],
+    [false, true]
+  );
+}
+
+// UniformBuffer / StorageBuffer が受け付けるか
+#[test]
+fn buffer_layouts() {
+  assert!(!is_uniform_layout::<StorageOnly>());
+  assert!(is_storage_layout::<StorageOnly>());
+  assert!(is_uniform_layout::<UniformInner>());
+  assert!(is_uniform_layout::<Light>());
+  assert!(is_uniform_layout::<Params>());
+}
+
+#[test]
+fn vertex_layout_uses_offsets_and_locations() {
+  let layout = Instance::layout();
+  assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+  assert_eq!(layout.array_stride, 20);
+  assert_eq!(
+    layout.attributes,
+    &wgpu::vertex_attr_array![
+      2 => Float32x2,
+      3 => Unorm8x4,
+      4 => Float32,
+    ]
+    .iter()
+    .zip([0, 8, 16])
+    .map(|(attribute, offset)| wgpu::VertexAttribute {
+      offset,
+      ..*attribute
+    })
+    .collect::<Vec<_>>()[..]
+  );
+  assert_eq!(
+    Instance::wgsl_struct(),
+    "struct Instance {\n  @location(2) offset: vec2f,\n  \
+     @location(3) color: vec4f,\n  @location(4) scale: f32,\n}\n"
+  );
+}
+
+// 手で書いた VertexLayout に f64 があれば WGSL の宣言を作るときに止める
+#[test]
+#[should_panic(expected = "cannot be read by a WGSL vertex shader")]
+fn float64_vertex_formats_have_no_wgsl_type() {
+  wgsim::layout::vertex_wgsl_struct(
+    "Vertex",
+    &["position"],
+    &wgpu::vertex_attr_array![0 => Float64x2],
+  );
+}
+
+#[test]
+fn layout_errors_fail_to_compile() {
+  let t = trybuild::TestCases::new();
+  t.compile_fail("tests/ui/*.rs");
+}
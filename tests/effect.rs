@@ -0,0 +1,94 @@
+use wgsim::ctx::DrawingContext;
+use wgsim::effect::{Effect, EffectChainBuilder};
+use wgsim::primitive::Size;
+use wgsim::readback::TextureRegion;
+
+// アダプタがない環境ではスキップする
+fn context(size: Size) -> Option<DrawingContext<'static>> {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions::default();
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!("skipped: no wgpu adapter is available");
+    return None;
+  }
+
+  Some(pollster::block_on(DrawingContext::new_for_texture(
+    size,
+    wgpu::TextureFormat::Rgba8Unorm,
+  )))
+}
+
+// 描画先の resolution をそのまま色にする
+const RESOLUTION_WGSL: &str = "
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  return vec4f(fullscreen.resolution / 255.0, 0.0, 1.0);
+}
+";
+
+// 前段の結果に描画先の resolution を足す
+const ADD_RESOLUTION_WGSL: &str = "
+@fragment
+fn fs_main(in: FullscreenOutput) -> @location(0) vec4f {
+  let input = textureSample(effect_input, effect_sampler, in.uv);
+  return vec4f(input.rg + fullscreen.resolution / 255.0, 0.0, 1.0);
+}
+";
+
+fn run_chain(ctx: &DrawingContext, scale: f32) -> [u8; 4] {
+  let size = *ctx.size();
+  let mut chain = EffectChainBuilder::new(ctx)
+    .hdr()
+    .scale(scale)
+    .effect(Effect::new("resolution", RESOLUTION_WGSL))
+    .effect(Effect::new("add resolution", ADD_RESOLUTION_WGSL))
+    .build();
+  chain.update(ctx);
+
+  let output = ctx.device.create_texture(&wgpu::TextureDescriptor {
+    label: None,
+    size: wgpu::Extent3d {
+      width: size.width,
+      height: size.height,
+      depth_or_array_layers: 1,
+    },
+    mip_level_count: 1,
+    sample_count: 1,
+    dimension: wgpu::TextureDimension::D2,
+    format: ctx.format(),
+    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+      | wgpu::TextureUsages::COPY_SRC,
+    view_formats: &[],
+  });
+  let view = output.create_view(&wgpu::TextureViewDescriptor::default());
+
+  let mut encoder = ctx
+    .device
+    .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+  chain.run(&mut encoder, &view);
+  ctx.queue.submit([encoder.finish()]);
+
+  let pixels: Vec<[u8; 4]> =
+    ctx.read_texture(&output, TextureRegion::default()).unwrap();
+  pixels[0]
+}
+
+#[test]
+fn intermediate_passes_use_the_scaled_resolution() {
+  let Some(ctx) = context(Size::new(8, 6)) else {
+    return;
+  };
+
+  // 中間は 4x3、最後は 8x6 で描かれる
+  let chain = EffectChainBuilder::new(&ctx).scale(0.5).build();
+  assert_eq!(chain.size(), Size::new(4, 3));
+  assert_eq!(run_chain(&ctx, 0.5), [4 + 8, 3 + 6, 0, 255]);
+}
+
+#[test]
+fn unscaled_passes_use_the_output_resolution() {
+  let Some(ctx) = context(Size::new(8, 6)) else {
+    return;
+  };
+  assert_eq!(run_chain(&ctx, 1.0), [8 + 8, 6 + 6, 0, 255]);
+}
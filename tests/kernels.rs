@@ -0,0 +1,294 @@
+use wgsim::camera::{Camera, Camera2D, CameraBinding, CAMERA_WGSL};
+use wgsim::ctx::DrawingContext;
+use wgsim::instancing::{IndirectDraw, InstanceShape, INDIRECT_WGSL};
+use wgsim::kernel::{
+  ComputeKernel, ComputeKernelBuilder, DispatchOptions, LINEAR_INDEX_WGSL,
+};
+use wgsim::ping_pong::PingPong;
+use wgsim::primitive::{Rect, Size, Vec4};
+use wgsim::util::{BindGroupBuilder, BindGroupLayoutBuilder};
+use wgsim::verify::{assert_verified, KernelVerifier, Tolerance};
+
+// examples の WGSL をそのまま CPU の実装と比べる
+
+// アダプタがない環境ではスキップする
+fn context() -> Option<DrawingContext<'static>> {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions::default();
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!("skipped: no wgpu adapter is available");
+    return None;
+  }
+
+  Some(pollster::block_on(DrawingContext::new_for_texture(
+    Size::new(4, 4),
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+  )))
+}
+
+// 線形合同法 (examples と同じ)
+fn random(seed: &mut u32) -> f32 {
+  *seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+  (*seed >> 8) as f32 / (1 << 24) as f32
+}
+
+fn kernel(
+  ctx: &DrawingContext,
+  source: &str,
+  layouts: &[&wgpu::BindGroupLayout],
+  constants: &[(&str, f64)],
+) -> ComputeKernel {
+  let pipeline_layout =
+    ctx.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+      label: None,
+      bind_group_layouts: layouts,
+      push_constant_ranges: &[],
+    });
+  ComputeKernelBuilder::new(&ctx.device, source, "cs_main")
+    .pipeline_layout(&pipeline_layout)
+    .constants(constants)
+    .build()
+    .unwrap()
+}
+
+fn life_step(cells: &[u32], width: usize, height: usize) -> Vec<u32> {
+  let cell = |x: isize, y: isize| {
+    let x = x.rem_euclid(width as isize) as usize;
+    let y = y.rem_euclid(height as isize) as usize;
+    cells[y * width + x]
+  };
+
+  let mut next = vec![0; cells.len()];
+  for y in 0..height as isize {
+    for x in 0..width as isize {
+      let mut neighbors = 0;
+      for dy in -1..=1 {
+        for dx in -1..=1 {
+          if dx != 0 || dy != 0 {
+            neighbors += cell(x + dx, y + dy);
+          }
+        }
+      }
+      let alive = cell(x, y) == 1;
+      next[y as usize * width + x as usize] =
+        u32::from(neighbors == 3 || (alive && neighbors == 2));
+    }
+  }
+  next
+}
+
+#[test]
+fn game_of_life_step() {
+  let Some(ctx) = context() else { return };
+  // ワークグループ (8x8) で割り切れない大きさにする
+  let (width, height) = (37, 29);
+  let mut seed = 0x2545_f491;
+  let mut cells: Vec<u32> =
+    (0..width * height).map(|_| u32::from(random(&mut seed) < 0.3)).collect();
+
+  let layout = BindGroupLayoutBuilder::new(&ctx.device)
+    .storage(0, wgpu::ShaderStages::COMPUTE, true)
+    .storage(1, wgpu::ShaderStages::COMPUTE, false)
+    .build();
+  let step_kernel = kernel(
+    &ctx,
+    include_str!("../examples/game-of-life/compute.wgsl"),
+    &[&layout.layout],
+    &[("WIDTH", width as f64), ("HEIGHT", height as f64)],
+  );
+  let mut gpu = PingPong::buffers(
+    &ctx.device,
+    &layout.layout,
+    bytemuck::cast_slice(&cells),
+    wgpu::BufferUsages::COPY_SRC,
+  );
+
+  let verifier = KernelVerifier::new(&ctx, "game of life");
+  assert_verified(verifier.run_steps(
+    40,
+    5,
+    &mut gpu,
+    |gpu, encoder, _| {
+      let size = [width as u32, height as u32, 1];
+      gpu.step(encoder, &step_kernel, 0, size, DispatchOptions::new()).unwrap();
+    },
+    |gpu| gpu.current(),
+    |_| {
+      cells = life_step(&cells, width, height);
+      cells.clone()
+    },
+  ));
+}
+
+type Particle = [f32; 4];
+
+fn particles(count: usize) -> Vec<Particle> {
+  let mut seed = 0x9e37_79b9;
+  (0..count)
+    .map(|_| {
+      let angle = random(&mut seed) * std::f32::consts::TAU;
+      let radius = 0.2 + random(&mut seed) * 0.9;
+      let (sin, cos) = angle.sin_cos();
+      [cos * radius, sin * radius, -sin * 0.3, cos * 0.3]
+    })
+    .collect()
+}
+
+// examples/particles/step.wgsl と同じ計算
+fn particle_step(p: Particle) -> Particle {
+  const DT: f32 = 1.0 / 60.0;
+  let [x, y, vx, vy] = p;
+  let r = (x * x + y * y).sqrt() + 1e-3;
+  let swirl = [-y / r, x / r];
+  let pull = [-x * (r - 0.6) * 2.0, -y * (r - 0.6) * 2.0];
+  let vx = (vx + (swirl[0] * 0.8 + pull[0]) * DT) * 0.995;
+  let vy = (vy + (swirl[1] * 0.8 + pull[1]) * DT) * 0.995;
+  [x + vx * DT, y + vy * DT, vx, vy]
+}
+
+// 1 次元のワークグループ数の上限 (65535) を超える数
+// 64 * 65535 = 4194240 個より多いと y に折り返す
+const LARGE_COUNT: usize = 4_200_000;
+
+fn check_particles_step(ctx: &DrawingContext, count: usize, steps: usize) {
+  let mut cpu = particles(count);
+
+  let verifier = KernelVerifier::new(ctx, "particles step")
+    .tolerance(Tolerance::abs(1e-5).or_ulps(4));
+  let mut buffer = verifier.storage_buffer(&cpu);
+  let layout = BindGroupLayoutBuilder::new(&ctx.device)
+    .storage(0, wgpu::ShaderStages::COMPUTE, false)
+    .build();
+  let source = format!(
+    "{}\n{}",
+    LINEAR_INDEX_WGSL,
+    include_str!("../examples/particles/step.wgsl")
+  );
+  let step_kernel =
+    kernel(ctx, &source, &[&layout.layout], &[("COUNT", count as f64)])
+      .with_bind_group(
+        0,
+        BindGroupBuilder::new(&ctx.device, &layout).buffer(0, &buffer).build(),
+      );
+
+  assert_verified(verifier.run_steps(
+    steps,
+    10,
+    &mut buffer,
+    |_, encoder, _| step_kernel.dispatch_linear(encoder, count as u32).unwrap(),
+    |buffer| buffer,
+    |_| {
+      cpu.iter_mut().for_each(|p| *p = particle_step(*p));
+      cpu.clone()
+    },
+  ));
+}
+
+#[test]
+fn particles_step() {
+  let Some(ctx) = context() else { return };
+  // ワークグループ (64) で割り切れない数にする
+  check_particles_step(&ctx, 1000, 60);
+}
+
+#[test]
+fn particles_step_beyond_dispatch_limit() {
+  let Some(ctx) = context() else { return };
+  check_particles_step(&ctx, LARGE_COUNT, 2);
+}
+
+fn check_particles_cull(ctx: &DrawingContext, count: usize) {
+  let all = particles(count);
+
+  // 粒子の分布の一部だけが映るカメラ
+  let camera = Camera2D::fit(Rect::new(0.0, -0.3, 0.8, 0.6), Size::new(64, 48));
+  let camera_binding = CameraBinding::new(&ctx.device, &camera);
+  let inv_view_proj = camera.uniform().inv_view_proj;
+  let to_world = |x: f32, y: f32| {
+    let p = inv_view_proj * Vec4::new(x, y, 0.0, 1.0);
+    [p.x / p.w, p.y / p.w]
+  };
+  let (min, max) = (to_world(-1.0, -1.0), to_world(1.0, 1.0));
+  let margin = 0.02;
+  let mut expected: Vec<Particle> = all
+    .iter()
+    .copied()
+    .filter(|p| {
+      (0..2).all(|i| p[i] >= min[i] - margin && p[i] <= max[i] + margin)
+    })
+    .collect();
+  assert!(!expected.is_empty() && expected.len() < count);
+
+  let verifier = KernelVerifier::new(ctx, "particles cull");
+  let particles = verifier.storage_buffer(&all);
+  let visible = verifier.storage_buffer(&vec![[0.0f32; 4]; count]);
+  let mut indirect = IndirectDraw::new(&ctx.device, InstanceShape::PointSprite);
+
+  let layout = BindGroupLayoutBuilder::new(&ctx.device)
+    .storage(0, wgpu::ShaderStages::COMPUTE, true)
+    .storage(1, wgpu::ShaderStages::COMPUTE, false)
+    .storage(2, wgpu::ShaderStages::COMPUTE, false)
+    .build();
+  let source = format!(
+    "{}\n{}\n{}\n{}",
+    CAMERA_WGSL,
+    INDIRECT_WGSL,
+    LINEAR_INDEX_WGSL,
+    include_str!("../examples/particles/cull.wgsl")
+  );
+  let cull_kernel = kernel(
+    ctx,
+    &source,
+    &[camera_binding.layout(), &layout.layout],
+    &[("COUNT", count as f64)],
+  )
+  .with_bind_group(
+    1,
+    BindGroupBuilder::new(&ctx.device, &layout)
+      .buffer(0, &particles)
+      .buffer(1, &visible)
+      .buffer(2, indirect.buffer())
+      .build(),
+  );
+
+  // 毎回数え直すので、何度実行しても同じ数になる
+  let vertex_count = InstanceShape::PointSprite.vertex_count();
+  let args = [vertex_count, expected.len() as u32, 0, 0];
+  assert_verified(verifier.run_steps(
+    3,
+    1,
+    &mut indirect,
+    |indirect, encoder, _| {
+      indirect.reset(encoder);
+      let workgroups =
+        cull_kernel.workgroup_count_linear(count as u32).unwrap();
+      cull_kernel.dispatch_workgroups(
+        encoder,
+        workgroups,
+        DispatchOptions::new().bind_group(0, camera_binding.bind_group()),
+      );
+    },
+    |indirect| indirect.buffer(),
+    |_| args.to_vec(),
+  ));
+
+  // 詰める順番は決まらないので並べ替えて比べる
+  let mut actual: Vec<Particle> = ctx.read_buffer(&visible, ..).unwrap();
+  actual.truncate(expected.len());
+  let key = |p: &Particle| p.map(f32::to_bits);
+  actual.sort_by_key(key);
+  expected.sort_by_key(key);
+  assert_verified(verifier.check(&verifier.storage_buffer(&actual), &expected));
+}
+
+#[test]
+fn particles_cull() {
+  let Some(ctx) = context() else { return };
+  check_particles_cull(&ctx, 1000);
+}
+
+#[test]
+fn particles_cull_beyond_dispatch_limit() {
+  let Some(ctx) = context() else { return };
+  check_particles_cull(&ctx, LARGE_COUNT);
+}
@@ -0,0 +1,171 @@
+use wgpu::util::DeviceExt;
+
+use wgsim::ctx::DrawingContext;
+use wgsim::primitive::Size;
+use wgsim::readback::{ReadbackError, TextureRegion};
+
+// アダプタがない環境ではスキップする
+fn context() -> Option<DrawingContext<'static>> {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions::default();
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!("skipped: no wgpu adapter is available");
+    return None;
+  }
+
+  Some(pollster::block_on(DrawingContext::new_for_texture(
+    Size::new(4, 4),
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+  )))
+}
+
+// 0, 1, 2, ... と並んだ 64 バイトのバッファ
+fn bytes_buffer(
+  ctx: &DrawingContext,
+  usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+  let contents: Vec<u8> = (0..64).collect();
+  ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+    label: None,
+    contents: &contents,
+    usage,
+  })
+}
+
+#[test]
+fn unaligned_buffer_ranges() {
+  let Some(ctx) = context() else { return };
+  let buffer = bytes_buffer(&ctx, wgpu::BufferUsages::COPY_SRC);
+
+  // 4 バイト境界に揃っていない範囲
+  let bytes: Vec<u8> = ctx.read_buffer(&buffer, 3..13).unwrap();
+  assert_eq!(bytes, (3..13).collect::<Vec<u8>>());
+  let bytes: Vec<u8> = ctx.read_buffer(&buffer, 61..=63).unwrap();
+  assert_eq!(bytes, [61, 62, 63]);
+  let empty: Vec<u8> = ctx.read_buffer(&buffer, 5..5).unwrap();
+  assert!(empty.is_empty());
+
+  // 途中の範囲を u32 として読む (境界に揃っていなくてもよい)
+  let words: Vec<u32> = ctx.read_buffer(&buffer, 8..16).unwrap();
+  assert_eq!(words, [0x0b0a_0908, 0x0f0e_0d0c]);
+  let words: Vec<u32> = ctx.read_buffer(&buffer, 1..9).unwrap();
+  assert_eq!(words, [0x0403_0201, 0x0807_0605]);
+
+  let all: Vec<u8> = ctx.read_buffer(&buffer, ..).unwrap();
+  assert_eq!(all.len(), 64);
+}
+
+#[test]
+fn buffer_readback_errors() {
+  let Some(ctx) = context() else { return };
+  let buffer = bytes_buffer(&ctx, wgpu::BufferUsages::COPY_SRC);
+
+  assert_eq!(
+    ctx.read_buffer::<u32>(&buffer, 0..6),
+    Err(ReadbackError::SizeMismatch {
+      len: 6,
+      element_size: 4
+    })
+  );
+  assert_eq!(
+    ctx.read_buffer::<u8>(&buffer, 60..68),
+    Err(ReadbackError::OutOfBounds { end: 68, size: 64 })
+  );
+
+  let storage = bytes_buffer(&ctx, wgpu::BufferUsages::STORAGE);
+  assert_eq!(
+    ctx.read_buffer::<u8>(&storage, ..).err(),
+    Some(ReadbackError::MissingCopySrc)
+  );
+}
+
+#[test]
+fn try_take_returns_the_data_once() {
+  let Some(ctx) = context() else { return };
+  let buffer = bytes_buffer(&ctx, wgpu::BufferUsages::COPY_SRC);
+  let mut readback = ctx.request_buffer_readback::<u8>(&buffer, 2..6).unwrap();
+
+  // 描画ループと同じように、終わるまで待たずに何度も確かめる
+  let start = std::time::Instant::now();
+  let data = loop {
+    if let Some(result) = readback.try_take(&ctx.device) {
+      break result.unwrap();
+    }
+    assert!(start.elapsed().as_secs() < 10, "readback never finished");
+    std::thread::yield_now();
+  };
+  assert_eq!(data, [2, 3, 4, 5]);
+  assert!(readback.is_ready());
+  assert_eq!(
+    readback.try_take(&ctx.device),
+    Some(Err(ReadbackError::AlreadyTaken))
+  );
+}
+
+#[test]
+fn texture_region_rows_are_unpadded() {
+  let Some(ctx) = context() else { return };
+  let (width, height) = (5, 4);
+  let texture = ctx.device.create_texture_with_data(
+    &ctx.queue,
+    &wgpu::TextureDescriptor {
+      label: None,
+      size: wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+      },
+      mip_level_count: 1,
+      sample_count: 1,
+      dimension: wgpu::TextureDimension::D2,
+      format: wgpu::TextureFormat::Rgba8Unorm,
+      usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::COPY_DST,
+      view_formats: &[],
+    },
+    wgpu::util::TextureDataOrder::LayerMajor,
+    // 各画素は (x, y, 0, 255)
+    &(0..height)
+      .flat_map(|y| (0..width).flat_map(move |x| [x as u8, y as u8, 0, 255]))
+      .collect::<Vec<u8>>(),
+  );
+
+  // 1 行 12 バイトは 256 バイトに揃えてコピーされる
+  let region = TextureRegion {
+    origin: wgpu::Origin3d { x: 1, y: 1, z: 0 },
+    size: Some(wgpu::Extent3d {
+      width: 3,
+      height: 2,
+      depth_or_array_layers: 1,
+    }),
+    ..Default::default()
+  };
+  let pixels: Vec<[u8; 4]> = ctx.read_texture(&texture, region).unwrap();
+  assert_eq!(
+    pixels,
+    [
+      [1, 1, 0, 255],
+      [2, 1, 0, 255],
+      [3, 1, 0, 255],
+      [1, 2, 0, 255],
+      [2, 2, 0, 255],
+      [3, 2, 0, 255],
+    ]
+  );
+
+  // size を省略すると origin から端まで
+  let region = TextureRegion {
+    origin: wgpu::Origin3d { x: 3, y: 2, z: 0 },
+    ..Default::default()
+  };
+  let pixels: Vec<u32> = ctx.read_texture(&texture, region).unwrap();
+  assert_eq!(pixels.len(), 4);
+
+  // 1 画素は 4 バイトなので [u8; 3] には分けられない
+  assert_eq!(
+    ctx.read_texture::<[u8; 3]>(&texture, TextureRegion::default()),
+    Err(ReadbackError::SizeMismatch {
+      len: 80,
+      element_size: 3
+    })
+  );
+}
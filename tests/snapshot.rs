@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use wgsim::snapshot::{Snapshot, SnapshotError, SnapshotReport};
+
+// examples/screen-image の State と setup をそのまま使う
+#[allow(dead_code)]
+mod screen_image {
+  include!("../examples/screen-image/main.rs");
+
+  pub fn check(
+    snapshot: &wgsim::snapshot::Snapshot,
+  ) -> Result<wgsim::snapshot::SnapshotReport, wgsim::snapshot::SnapshotError>
+  {
+    pollster::block_on(snapshot.check::<State>(setup()))
+  }
+}
+
+// Snapshot は既定でソフトウェアのアダプタ (lavapipe や llvmpipe) を使う
+// 見つからない環境ではスキップする
+fn has_fallback_adapter() -> bool {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions {
+    force_fallback_adapter: true,
+    ..Default::default()
+  };
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!(
+      "skipped: no software wgpu adapter is available \
+       (install mesa-vulkan-drivers for lavapipe or Mesa llvmpipe for GL)"
+    );
+    return false;
+  }
+  true
+}
+
+// テストごとに空のディレクトリを用意する
+fn temp_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!(
+    "wgsim-snapshot-{}-{}",
+    name,
+    std::process::id()
+  ));
+  let _ = std::fs::remove_dir_all(&dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+fn screen_image(snapshot: Snapshot) -> Result<SnapshotReport, SnapshotError> {
+  screen_image::check(&snapshot.size(128, 128))
+}
+
+#[test]
+fn screen_image_matches_reference() {
+  if !has_fallback_adapter() {
+    return;
+  }
+
+  match screen_image(Snapshot::new("screen-image")) {
+    Ok(report) => assert_eq!(report.pixel_count, 128 * 128),
+    Err(e) => panic!("{}", e),
+  }
+}
+
+#[test]
+fn missing_reference_saves_actual() {
+  if !has_fallback_adapter() {
+    return;
+  }
+
+  let dir = temp_dir("missing");
+  let snapshot =
+    Snapshot::new("screen-image").dir(&dir).update_reference(false);
+
+  match screen_image(snapshot) {
+    Err(SnapshotError::MissingReference { reference, actual }) => {
+      assert!(!reference.exists());
+      assert_eq!(actual, dir.join("screen-image.actual.png"));
+      assert_eq!(image::open(&actual).unwrap().width(), 128);
+    }
+    result => panic!("unexpected result: {:?}", result),
+  }
+}
+
+#[test]
+fn mismatch_writes_diff_and_update_accepts_it() {
+  if !has_fallback_adapter() {
+    return;
+  }
+
+  let dir = temp_dir("mismatch");
+  let snapshot =
+    || Snapshot::new("screen-image").dir(&dir).update_reference(false);
+
+  // 左半分を黒く塗った参照画像と比べる
+  let committed = Snapshot::new("screen-image").reference_path();
+  let mut reference = image::open(committed).unwrap().to_rgba8();
+  for (x, _, pixel) in reference.enumerate_pixels_mut() {
+    if x < 64 {
+      *pixel = image::Rgba([0, 0, 0, 255]);
+    }
+  }
+  reference.save(dir.join("screen-image.png")).unwrap();
+
+  match screen_image(snapshot()) {
+    Err(SnapshotError::Mismatch {
+      report,
+      actual,
+      diff,
+    }) => {
+      assert!(report.mismatched_ratio() > 0.3, "{}", report);
+      assert!(report.mean_delta_e > 1.0, "{}", report);
+      assert!(report.psnr.is_finite(), "{}", report);
+      let diff = image::open(diff).unwrap().to_rgba8();
+      assert_eq!(diff.get_pixel(0, 64).0, [255, 0, 0, 255]);
+      assert!(actual.exists());
+    }
+    result => panic!("unexpected result: {:?}", result),
+  }
+
+  // 許容範囲を広げれば通り、失敗時のファイルは消える
+  let report = screen_image(snapshot().max_mismatched_ratio(1.0)).unwrap();
+  assert!(report.mismatched_pixels > 0);
+  assert!(!dir.join("screen-image.actual.png").exists());
+  assert!(!dir.join("screen-image.diff.png").exists());
+
+  // update_reference で置き換えると一致する
+  screen_image(snapshot().update_reference(true)).unwrap();
+  let report = screen_image(snapshot()).unwrap();
+  assert_eq!(report.mismatched_pixels, 0);
+  assert_eq!(report.psnr, f64::INFINITY);
+
+  let _ = std::fs::remove_dir_all(&dir);
+}
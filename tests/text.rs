@@ -0,0 +1,57 @@
+use wgsim::ctx::DrawingContext;
+use wgsim::primitive::Size;
+use wgsim::text::{Text, TextError, TextRenderer};
+
+// アダプタがない環境ではスキップする
+fn context() -> Option<DrawingContext<'static>> {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions::default();
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!("skipped: no wgpu adapter is available");
+    return None;
+  }
+
+  Some(pollster::block_on(DrawingContext::new_for_texture(
+    Size::new(64, 64),
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+  )))
+}
+
+const ALPHANUMERIC: &str =
+  "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+#[test]
+fn prepare_clears_the_atlas_when_it_fills_up() {
+  let Some(ctx) = context() else { return };
+  let mut renderer = TextRenderer::new(&ctx);
+
+  // 1 回ずつなら収まるが、積み重なるとアトラスからあふれる
+  for size in (40..=120).step_by(4) {
+    renderer.queue(Text::new(ALPHANUMERIC).size(size as f32));
+    renderer.prepare(&ctx).unwrap();
+  }
+
+  // 1 回で使う分が収まらない場合はエラーになる
+  for size in (40..=120).step_by(4) {
+    renderer.queue(Text::new(ALPHANUMERIC).size(size as f32));
+  }
+  assert!(matches!(renderer.prepare(&ctx), Err(TextError::AtlasFull)));
+
+  // 次の prepare では元に戻る
+  renderer.queue(Text::new("hello").size(16.0));
+  renderer.prepare(&ctx).unwrap();
+}
+
+#[test]
+fn measure_uses_the_renderer_font() {
+  let Some(ctx) = context() else { return };
+  let renderer = TextRenderer::new(&ctx);
+
+  let one = renderer.measure(&Text::new("a").size(20.0));
+  let rect = renderer.measure(&Text::new("hello\nwgsim!").size(20.0));
+  assert_eq!(rect.origin.x, 0.0);
+  assert_eq!(rect.origin.y, 0.0);
+  assert!((rect.size.width - one.size.width * 6.0).abs() < 1e-3);
+  // 2 行目は行の間隔 (line_gap を含む) だけ下にずれる
+  assert!(rect.size.height >= one.size.height * 2.0 - 1e-3);
+}
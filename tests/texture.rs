@@ -0,0 +1,112 @@
+use wgsim::ctx::DrawingContext;
+use wgsim::primitive::Size;
+use wgsim::texture::{TextureBuilder, TextureError};
+
+// アダプタがない環境ではスキップする
+fn context() -> Option<DrawingContext<'static>> {
+  let instance = wgpu::Instance::default();
+  let options = wgpu::RequestAdapterOptions {
+    force_fallback_adapter: false,
+    ..Default::default()
+  };
+  if pollster::block_on(instance.request_adapter(&options)).is_none() {
+    eprintln!("skipped: no wgpu adapter is available");
+    return None;
+  }
+
+  Some(pollster::block_on(DrawingContext::new_for_texture(
+    Size::new(4, 4),
+    wgpu::TextureFormat::Rgba8UnormSrgb,
+  )))
+}
+
+#[test]
+fn single_image_array_binds_as_texture_2d_array() {
+  let Some(ctx) = context() else { return };
+  let image = image::DynamicImage::new_rgba8(4, 4);
+  let texture = TextureBuilder::new(&ctx).array_from_images(&[image]).unwrap();
+  assert_eq!(texture.view_dimension, wgpu::TextureViewDimension::D2Array);
+
+  let shader = "
+    @group(0) @binding(0) var layers: texture_2d_array<f32>;
+    @group(0) @binding(1) var<storage, read_write> out: vec4f;
+
+    @compute @workgroup_size(1)
+    fn cs_main() {
+      out = textureLoad(layers, vec2i(0, 0), 0, 0);
+    }
+  ";
+
+  ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+  let module = ctx.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+    label: None,
+    source: wgpu::ShaderSource::Wgsl(shader.into()),
+  });
+  let pipeline =
+    ctx.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+      label: None,
+      layout: None,
+      module: &module,
+      entry_point: Some("cs_main"),
+      compilation_options: Default::default(),
+      cache: None,
+    });
+  let out = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+    label: None,
+    size: 16,
+    usage: wgpu::BufferUsages::STORAGE,
+    mapped_at_creation: false,
+  });
+  let _bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+    label: None,
+    layout: &pipeline.get_bind_group_layout(0),
+    entries: &[
+      wgpu::BindGroupEntry {
+        binding: 0,
+        resource: wgpu::BindingResource::TextureView(&texture.view),
+      },
+      wgpu::BindGroupEntry {
+        binding: 1,
+        resource: out.as_entire_binding(),
+      },
+    ],
+  });
+  let error = pollster::block_on(ctx.device.pop_error_scope());
+  assert!(error.is_none(), "{:?}", error);
+}
+
+#[test]
+fn mismatched_layers_are_errors() {
+  let Some(ctx) = context() else { return };
+  let builder = TextureBuilder::new(&ctx);
+
+  let images = [
+    image::DynamicImage::new_rgba8(4, 4),
+    image::DynamicImage::new_rgba8(4, 2),
+  ];
+  assert!(matches!(
+    builder.array_from_images(&images),
+    Err(TextureError::LayerSize { layer: 1, .. })
+  ));
+
+  let images = [
+    image::DynamicImage::new_rgba8(4, 4),
+    image::DynamicImage::new_rgba16(4, 4),
+  ];
+  assert!(matches!(
+    builder.array_from_images(&images),
+    Err(TextureError::LayerFormat { layer: 1, .. })
+  ));
+
+  assert!(matches!(
+    builder.array_from_images(&[]),
+    Err(TextureError::EmptyArray)
+  ));
+  assert!(matches!(
+    builder.from_rgba8(Size::new(2, 2), &[0; 15]),
+    Err(TextureError::DataSize {
+      expected: 16,
+      found: 15
+    })
+  ));
+}
@@ -0,0 +1,8 @@
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::Vertex)]
+struct Vertex {
+  #[vertex(format = "Float64x2")]
+  position: [f64; 2],
+}
+
+fn main() {}
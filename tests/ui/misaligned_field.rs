@@ -0,0 +1,9 @@
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Light {
+  intensity: f32,
+  // WGSL では vec3f は 16 バイト境界に置かれる
+  position: [f32; 3],
+}
+
+fn main() {}
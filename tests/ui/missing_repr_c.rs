@@ -0,0 +1,6 @@
+#[derive(Clone, Copy, wgsim::ShaderType)]
+struct Params {
+  scale: f32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Particle {
+  position: [f32; 3],
+  mass: f32,
+  velocity: [f32; 2],
+}
+
+fn main() {}
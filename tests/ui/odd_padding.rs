@@ -0,0 +1,9 @@
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, wgsim::ShaderType)]
+struct Params {
+  scale: f32,
+  _pad: [u8; 2],
+  _pad2: [u8; 2],
+}
+
+fn main() {}
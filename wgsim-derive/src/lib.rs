@@ -0,0 +1,312 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+  parse_macro_input, Data, DeriveInput, Error, Fields, FieldsNamed, Ident,
+  LitInt, LitStr, Result,
+};
+
+// #[derive(Vertex)]
+// フィールドの順に @location(0) から割り当て、wgsim::layout::VertexLayout と
+// WgslStruct を実装する
+//
+// 構造体: #[vertex(instance)] でインスタンスごとの属性にする
+//         #[vertex(location = N)] で最初の location を N にする
+// フィールド: #[vertex(location = N)] で location を指定する (以降は N + 1 から)
+//             #[vertex(format = "Unorm8x4")] で wgpu::VertexFormat を指定する
+//             #[vertex(skip)] で属性にしない
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_vertex(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+// #[derive(ShaderType)]
+// フィールドの位置が WGSL のアラインメント規則と一致するかをコンパイル時に検査し、
+// wgsim::layout::ShaderType と WgslStruct を実装する
+// 検査するのは storage と共通の規則で、uniform の追加の規則
+// (入れ子の構造体のオフセットを 16 の倍数にするなど) を満たすかは
+// ShaderType::UNIFORM_COMPATIBLE に入る (UniformBuffer が検査する)
+//
+// 名前が _ で始まるフィールドはパディングとみなし、WGSL では直前のメンバーの
+// @size に含める
+#[proc_macro_derive(ShaderType)]
+pub fn derive_shader_type(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand_shader_type(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn named_fields<'a>(
+  input: &'a DeriveInput,
+  derive: &str,
+) -> Result<&'a FieldsNamed> {
+  if !input.generics.params.is_empty() {
+    return Err(Error::new_spanned(
+      &input.generics,
+      format!("#[derive({})] does not support generic structs", derive),
+    ));
+  }
+
+  let has_repr_c = input.attrs.iter().any(|attr| {
+    let mut found = false;
+    if attr.path().is_ident("repr") {
+      let _ = attr.parse_nested_meta(|meta| {
+        found |= meta.path.is_ident("C");
+        Ok(())
+      });
+    }
+    found
+  });
+  if !has_repr_c {
+    return Err(Error::new_spanned(
+      &input.ident,
+      format!("#[derive({})] requires #[repr(C)]", derive),
+    ));
+  }
+
+  match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => Ok(fields),
+      _ => Err(Error::new_spanned(
+        &input.ident,
+        format!("#[derive({})] requires named fields", derive),
+      )),
+    },
+    _ => Err(Error::new_spanned(
+      &input.ident,
+      format!("#[derive({})] can only be used on structs", derive),
+    )),
+  }
+}
+
+fn expand_vertex(input: &DeriveInput) -> Result<TokenStream2> {
+  let fields = named_fields(input, "Vertex")?;
+  let name = &input.ident;
+  let wgpu = quote!(::wgsim::layout::__private::wgpu);
+
+  let mut instance = false;
+  let mut location = 0u32;
+  for attr in input.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+    attr.parse_nested_meta(|meta| {
+      if meta.path.is_ident("instance") {
+        instance = true;
+        Ok(())
+      } else if meta.path.is_ident("location") {
+        location = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+        Ok(())
+      } else {
+        Err(meta.error("expected `instance` or `location = N`"))
+      }
+    })?;
+  }
+
+  let mut attributes = Vec::new();
+  let mut field_names = Vec::new();
+  for field in &fields.named {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut format = None;
+    let mut skip = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("vertex")) {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("location") {
+          location = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+          Ok(())
+        } else if meta.path.is_ident("format") {
+          let value = meta.value()?.parse::<LitStr>()?;
+          // WGSL には f64 がないのでシェーダで受け取れない
+          if value.value().starts_with("Float64") {
+            return Err(Error::new_spanned(
+              &value,
+              "Float64 vertex formats cannot be read by a WGSL vertex shader",
+            ));
+          }
+          format = Some(Ident::new(&value.value(), value.span()));
+          Ok(())
+        } else if meta.path.is_ident("skip") {
+          skip = true;
+          Ok(())
+        } else {
+          Err(
+            meta.error("expected `location = N`, `format = \"...\"` or `skip`"),
+          )
+        }
+      })?;
+    }
+    if skip {
+      continue;
+    }
+
+    let ty = &field.ty;
+    let format = match format {
+      Some(format) => quote!(#wgpu::VertexFormat::#format),
+      None => {
+        quote!(<#ty as ::wgsim::layout::VertexAttributeType>::FORMAT)
+      }
+    };
+    attributes.push(quote! {
+      #wgpu::VertexAttribute {
+        format: #format,
+        offset: ::core::mem::offset_of!(#name, #ident) as #wgpu::BufferAddress,
+        shader_location: #location,
+      }
+    });
+    field_names.push(LitStr::new(&ident.to_string(), Span::call_site()));
+    location += 1;
+  }
+
+  let step_mode = if instance {
+    quote!(#wgpu::VertexStepMode::Instance)
+  } else {
+    quote!(#wgpu::VertexStepMode::Vertex)
+  };
+  let wgsl_name = LitStr::new(&name.to_string(), Span::call_site());
+
+  Ok(quote! {
+    impl ::wgsim::layout::VertexLayout for #name {
+      const ATTRIBUTES: &'static [#wgpu::VertexAttribute] = &[#(#attributes),*];
+      const STEP_MODE: #wgpu::VertexStepMode = #step_mode;
+    }
+
+    impl ::wgsim::layout::WgslStruct for #name {
+      fn wgsl_struct() -> ::std::string::String {
+        ::wgsim::layout::vertex_wgsl_struct(
+          #wgsl_name,
+          &[#(#field_names),*],
+          <Self as ::wgsim::layout::VertexLayout>::ATTRIBUTES,
+        )
+      }
+    }
+  })
+}
+
+fn expand_shader_type(input: &DeriveInput) -> Result<TokenStream2> {
+  let fields = named_fields(input, "ShaderType")?;
+  let name = &input.ident;
+  let layout = quote!(::wgsim::layout);
+
+  if fields.named.is_empty() {
+    return Err(Error::new_spanned(
+      name,
+      "#[derive(ShaderType)] requires at least one field",
+    ));
+  }
+
+  let mut checks = Vec::new();
+  let mut uniform_checks = Vec::new();
+  let mut aligns = Vec::new();
+  let mut wgsl_fields = Vec::new();
+  for field in &fields.named {
+    let ident = field.ident.as_ref().expect("named field");
+    let ty = &field.ty;
+    let field_name = ident.to_string();
+    let field_lit = LitStr::new(&field_name, Span::call_site());
+
+    if field_name.starts_with('_') {
+      let message = LitStr::new(
+        &format!(
+          "[wgsim] padding field `{}::{}` must be a multiple of 4 bytes",
+          name, field_name
+        ),
+        Span::call_site(),
+      );
+      let offset_message = offset_message(name, &field_name);
+      checks.push(quote! {
+        let size = ::core::mem::size_of::<#ty>();
+        assert!(size % 4 == 0, #message);
+        assert!(::core::mem::offset_of!(#name, #ident) == end, #offset_message);
+        end += size;
+      });
+      wgsl_fields.push(quote! {
+        (#field_lit, None, ::core::mem::size_of::<#ty>())
+      });
+    } else {
+      let offset_message = offset_message(name, &field_name);
+      checks.push(quote! {
+        let offset = #layout::round_up(end, <#ty as #layout::ShaderType>::ALIGN);
+        assert!(::core::mem::offset_of!(#name, #ident) == offset, #offset_message);
+        end = offset + <#ty as #layout::ShaderType>::SIZE;
+      });
+      aligns.push(quote!(<#ty as #layout::ShaderType>::ALIGN));
+      wgsl_fields.push(quote! {
+        (
+          #field_lit,
+          Some(<#ty as #layout::ShaderType>::WGSL_TYPE),
+          <#ty as #layout::ShaderType>::SIZE,
+        )
+      });
+      // 構造体のメンバーの後には roundUp(16, SizeOf(S)) バイト空ける必要がある
+      uniform_checks.push(quote! {
+        let offset = ::core::mem::offset_of!(#name, #ident);
+        let align = <#ty as #layout::ShaderType>::UNIFORM_ALIGN;
+        let size = <#ty as #layout::ShaderType>::SIZE;
+        ok = ok
+          && <#ty as #layout::ShaderType>::UNIFORM_COMPATIBLE
+          && offset >= next
+          && offset % align == 0;
+        next = if align > <#ty as #layout::ShaderType>::ALIGN {
+          offset + #layout::round_up(size, 16)
+        } else {
+          offset + size
+        };
+      });
+    }
+  }
+
+  let align = aligns
+    .into_iter()
+    .fold(quote!(4), |a, b| quote!(#layout::max_align(#a, #b)));
+  let size_message = LitStr::new(
+    &format!(
+      "[wgsim] size of `{}` differs from its WGSL layout; add padding at the end \
+       so that the size is a multiple of the largest field alignment",
+      name
+    ),
+    Span::call_site(),
+  );
+  let wgsl_name = LitStr::new(&name.to_string(), Span::call_site());
+
+  Ok(quote! {
+    impl #layout::ShaderType for #name {
+      const ALIGN: usize = #align;
+      const SIZE: usize = ::core::mem::size_of::<#name>();
+      const WGSL_TYPE: &'static str = #wgsl_name;
+      const UNIFORM_ALIGN: usize = #layout::round_up(#align, 16);
+      const UNIFORM_COMPATIBLE: bool = {
+        let mut ok = true;
+        let mut next = 0usize;
+        #(#uniform_checks)*
+        // 構造体の末尾には制約がない
+        let _ = next;
+        ok
+      };
+    }
+
+    const _: () = {
+      let mut end = 0usize;
+      #(#checks)*
+      assert!(
+        #layout::round_up(end, <#name as #layout::ShaderType>::ALIGN)
+          == ::core::mem::size_of::<#name>(),
+        #size_message
+      );
+    };
+
+    impl #layout::WgslStruct for #name {
+      fn wgsl_struct() -> ::std::string::String {
+        #layout::shader_wgsl_struct(#wgsl_name, &[#(#wgsl_fields),*])
+      }
+    }
+  })
+}
+
+fn offset_message(name: &Ident, field: &str) -> LitStr {
+  LitStr::new(
+    &format!(
+      "[wgsim] field `{}::{}` is not at its WGSL offset; reorder the fields or \
+       add a padding field (named `_...`) before it",
+      name, field
+    ),
+    Span::call_site(),
+  )
+}